@@ -0,0 +1,47 @@
+#!/bin/bash
+# Dynamic Elo estimation script for Axelrot vs Stockfish (or another engine)
+# Usage: ./estimate_elo.sh [ENGINE_PATH]
+# If ENGINE_PATH is not provided, defaults to Stockfish and tests all ELO_LEVELS.
+# Games are played by the built-in match runner (cargo build --release).
+
+MATCH="$(pwd)/target/release/match"
+GAMES_PER_LEVEL=20
+AXELROT_PATH="$(pwd)/target/release/axelrot"
+AXELROT_NAME="axelrot"
+
+run_match() {
+    local opponent_name="$1" pgn="$2"
+    shift 2
+    "$MATCH" \
+        -engine name="$AXELROT_NAME" cmd="$AXELROT_PATH" \
+        -engine name="$opponent_name" "$@" \
+        -games $GAMES_PER_LEVEL \
+        -tc 300+0 \
+        -concurrency 5 \
+        -openings ./Perfect_2023/BIN/Perfect2023.bin \
+        -pgnout "$pgn" \
+        -ratinginterval 0 \
+        -draw movenumber=40 movecount=8 score=5
+}
+
+if [ -z "$1" ]; then
+    OPPONENT_PATH="stockfish"
+    OPPONENT_NAME="stockfish"
+    ELO_LEVELS=(1350 1400 1600 1800)
+    for ELO in "${ELO_LEVELS[@]}"; do
+        echo -e "\n=== Testing $AXELROT_NAME vs $OPPONENT_NAME Elo $ELO ==="
+        run_match "$OPPONENT_NAME" "${AXELROT_NAME}_vs_${OPPONENT_NAME}_${ELO}.pgn" \
+            cmd="$OPPONENT_PATH" option.UCI_LimitStrength=true option.UCI_Elo=$ELO \
+            | tee "match_${AXELROT_NAME}_vs_${OPPONENT_NAME}_${ELO}.log" | sed -n '/^Finished match/,$p'
+    done
+else
+    OPPONENT_PATH="$1"
+    OPPONENT_NAME=$(basename "$OPPONENT_PATH")
+    OPPONENT_NAME="${OPPONENT_NAME%%.*}"
+    echo -e "\n=== Testing $AXELROT_NAME vs $OPPONENT_NAME ==="
+    run_match "$OPPONENT_NAME" "${AXELROT_NAME}_vs_${OPPONENT_NAME}.pgn" cmd="$OPPONENT_PATH" \
+        | tee "match_${AXELROT_NAME}_vs_${OPPONENT_NAME}.log" | sed -n '/^Finished match/,$p'
+fi
+
+echo -e "\n=== Done! ==="
+echo "The Elo difference at each level is in the summaries above; $AXELROT_NAME's rating is near the level where it scores ~50%."
@@ -0,0 +1,247 @@
+use crate::pgn::{eval_comment, Game, Node};
+use crate::{search, SearchInfo, MATE};
+use chess::{Board, BoardStatus, ChessMove, Color};
+use std::time::Instant;
+
+// Reviews the main line of games: every position is searched, the played
+// move is compared with the engine's choice, and costly moves get a NAG, an
+// evaluation comment and the engine's line as a variation.
+
+// Scores are capped here before losses are measured, so a missed mate does
+// not count for more than losing a lot of material.
+const SCORE_CAP: i32 = 1000;
+
+// Standard NAGs for move quality.
+pub const NAG_INACCURACY: u8 = 6;
+pub const NAG_MISTAKE: u8 = 2;
+pub const NAG_BLUNDER: u8 = 4;
+
+#[derive(Clone, Copy, Debug)]
+pub struct AnnotateConfig {
+    pub depth: i32,
+    pub movetime: Option<u64>,
+    pub nodes: Option<u64>,
+    // Centipawn losses from which a move is marked ?!, ? and ??.
+    pub inaccuracy: i32,
+    pub mistake: i32,
+    pub blunder: i32,
+    // Length of the best-line variations, in plies.
+    pub variation_plies: usize,
+}
+
+impl Default for AnnotateConfig {
+    fn default() -> Self {
+        AnnotateConfig {
+            depth: 4,
+            movetime: None,
+            nodes: None,
+            inaccuracy: 50,
+            mistake: 100,
+            blunder: 300,
+            variation_plies: 8,
+        }
+    }
+}
+
+// The engine's view of one position.
+#[derive(Clone, Debug)]
+struct Analysis {
+    score: i32,
+    depth: i32,
+    time_ms: u64,
+    pv: Vec<ChessMove>,
+}
+
+#[derive(Clone, Debug)]
+pub struct MoveReview {
+    pub ply: usize,
+    pub color: Color,
+    pub played: ChessMove,
+    pub best: Option<ChessMove>,
+    // Both from the mover's point of view: the best score before the move
+    // and the score of the position the played move leads to.
+    pub best_score: i32,
+    pub played_score: i32,
+    pub loss: i32,
+    pub accuracy: f64,
+    pub nag: Option<u8>,
+}
+
+// Expected points in percent for a centipawn score, as lichess models it.
+pub fn win_percent(score: i32) -> f64 {
+    let score = score.clamp(-SCORE_CAP, SCORE_CAP) as f64;
+    50.0 + 50.0 * (2.0 / (1.0 + (-0.003_682_08 * score).exp()) - 1.0)
+}
+
+// Accuracy of a move from the mover's win percentage before and after it.
+pub fn move_accuracy(before: i32, after: i32) -> f64 {
+    let drop = (win_percent(before) - win_percent(after)).max(0.0);
+    (103.1668 * (-0.04354 * drop).exp() - 3.1669).clamp(0.0, 100.0)
+}
+
+fn analyse(board: &Board, config: &AnnotateConfig) -> Analysis {
+    match board.status() {
+        BoardStatus::Checkmate => return Analysis { score: -MATE, depth: 0, time_ms: 0, pv: Vec::new() },
+        BoardStatus::Stalemate => return Analysis { score: 0, depth: 0, time_ms: 0, pv: Vec::new() },
+        BoardStatus::Ongoing => {}
+    }
+    let start = Instant::now();
+    let mut info = SearchInfo::new(config.movetime.unwrap_or(u64::MAX));
+    info.node_limit = config.nodes;
+    let result = search(board, config.depth, &mut info);
+    Analysis { score: result.score, depth: result.depth, time_ms: start.elapsed().as_millis() as u64, pv: result.pv }
+}
+
+// A score of the position after a move, seen from before it: negated, and
+// mate distances one ply longer.
+fn from_parent(score: i32) -> i32 {
+    match -score {
+        s if s > MATE - 500 => s - 1,
+        s if s < -(MATE - 500) => s + 1,
+        s => s,
+    }
+}
+
+fn classify(loss: i32, config: &AnnotateConfig) -> Option<u8> {
+    if loss >= config.blunder {
+        Some(NAG_BLUNDER)
+    } else if loss >= config.mistake {
+        Some(NAG_MISTAKE)
+    } else if loss >= config.inaccuracy {
+        Some(NAG_INACCURACY)
+    } else {
+        None
+    }
+}
+
+fn line_nodes(pv: &[ChessMove], plies: usize) -> Vec<Node> {
+    pv.iter().take(plies).map(|&mv| Node::new(mv)).collect()
+}
+
+// Returns the annotated game and a review of every main line move.
+// `report` is told after each searched position.
+pub fn annotate(game: &Game, config: &AnnotateConfig, report: impl Fn(usize, usize)) -> Result<(Game, Vec<MoveReview>), String> {
+    let start = game.start_board()?;
+    let mut boards = vec![start];
+    for &mv in &game.moves {
+        boards.push(boards.last().unwrap().make_move_new(mv));
+    }
+    let analyses: Vec<Analysis> = boards
+        .iter()
+        .enumerate()
+        .map(|(i, board)| {
+            let analysis = analyse(board, config);
+            report(i + 1, boards.len());
+            analysis
+        })
+        .collect();
+
+    let mut annotated = Game { tree: Vec::new(), moves: Vec::new(), ..game.clone() };
+    annotated.set_tag("Annotator", "axelrot");
+    let mut reviews = Vec::new();
+    for (ply, mut node) in game.main_line().into_iter().enumerate() {
+        let (before, after) = (&analyses[ply], &analyses[ply + 1]);
+        let best = before.pv.first().copied();
+        let best_score = before.score;
+        let played_score = from_parent(after.score);
+        let loss = if best == Some(node.mv) {
+            0
+        } else {
+            (best_score.clamp(-SCORE_CAP, SCORE_CAP) - played_score.clamp(-SCORE_CAP, SCORE_CAP)).max(0)
+        };
+        let nag = classify(loss, config);
+        let comment = eval_comment(played_score, after.depth.max(0) as u32, after.time_ms);
+        node.after = Some(match node.after.take() {
+            Some(old) => format!("{} {}", old, comment),
+            None => comment,
+        });
+        if let Some(nag) = nag {
+            node.nags.retain(|n| !(1..=6).contains(n));
+            node.nags.push(nag);
+            let mut line = line_nodes(&before.pv, config.variation_plies);
+            if let Some(first) = line.first_mut() {
+                first.after = Some(eval_comment(best_score, before.depth.max(0) as u32, before.time_ms));
+                node.variations.insert(0, line);
+            }
+        }
+        reviews.push(MoveReview {
+            ply,
+            color: boards[ply].side_to_move(),
+            played: node.mv,
+            best,
+            best_score,
+            played_score,
+            loss,
+            accuracy: move_accuracy(best_score, played_score),
+            nag,
+        });
+        annotated.push(node);
+    }
+    Ok((annotated, reviews))
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct PlayerSummary {
+    pub name: String,
+    pub moves: usize,
+    pub total_loss: i64,
+    pub total_accuracy: f64,
+    pub inaccuracies: usize,
+    pub mistakes: usize,
+    pub blunders: usize,
+}
+
+impl PlayerSummary {
+    // Average centipawn loss.
+    pub fn acpl(&self) -> f64 {
+        self.total_loss as f64 / self.moves.max(1) as f64
+    }
+
+    pub fn accuracy(&self) -> f64 {
+        self.total_accuracy / self.moves.max(1) as f64
+    }
+
+    pub fn add(&mut self, review: &MoveReview) {
+        self.moves += 1;
+        self.total_loss += review.loss as i64;
+        self.total_accuracy += review.accuracy;
+        match review.nag {
+            Some(NAG_INACCURACY) => self.inaccuracies += 1,
+            Some(NAG_MISTAKE) => self.mistakes += 1,
+            Some(NAG_BLUNDER) => self.blunders += 1,
+            _ => {}
+        }
+    }
+}
+
+// Adds a game's reviews to the running per-player totals, by name.
+pub fn summarize(players: &mut Vec<PlayerSummary>, game: &Game, reviews: &[MoveReview]) {
+    for review in reviews {
+        let name = game.tag(if review.color == Color::White { "White" } else { "Black" }).unwrap_or("?");
+        let index = match players.iter().position(|p| p.name == name) {
+            Some(index) => index,
+            None => {
+                players.push(PlayerSummary { name: name.to_string(), ..PlayerSummary::default() });
+                players.len() - 1
+            }
+        };
+        players[index].add(review);
+    }
+}
+
+pub fn format_summary(players: &[PlayerSummary]) -> String {
+    let mut text = format!("{:<24} {:>6} {:>7} {:>9} {:>4} {:>4} {:>4}\n", "Player", "Moves", "ACPL", "Accuracy", "?!", "?", "??");
+    for p in players {
+        text += &format!(
+            "{:<24} {:>6} {:>7.1} {:>8.1}% {:>4} {:>4} {:>4}\n",
+            p.name,
+            p.moves,
+            p.acpl(),
+            p.accuracy(),
+            p.inaccuracies,
+            p.mistakes,
+            p.blunders
+        );
+    }
+    text
+}
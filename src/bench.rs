@@ -0,0 +1,67 @@
+use crate::{search, SearchInfo, SearchResult};
+use chess::Board;
+use std::str::FromStr;
+use std::time::Instant;
+
+// Fixed search benchmark. Every position is searched to the same depth with
+// a fresh transposition table, no time limit and an isolated search (no
+// experience, tablebases, NNUE or loaded parameters), so the total node count
+// is a signature of the search: it only changes when search behaviour does.
+pub const BENCH_DEPTH: i32 = 3;
+
+pub const BENCH_POSITIONS: [&str; 16] = [
+    "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+    "r1bqkb1r/pppp1ppp/2n2n2/4p3/2B1P3/5N2/PPPP1PPP/RNBQK2R w KQkq - 4 4",
+    "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 11",
+    "4rrk1/pp1n3p/3q2pQ/2p1pb2/2PP4/2P3N1/P2B2PP/4RRK1 b - - 7 19",
+    "rq3rk1/ppp2ppp/1bnpb3/3N2B1/3NP3/7P/PPPQ1PP1/2KR3R w - - 7 14",
+    "r1bq1r1k/1pp1n1pp/1p1p4/4p2Q/4Pp2/1BNP4/PPP2PPP/3R1RK1 w - - 2 14",
+    "r3r1k1/2p2ppp/p1p1bn2/8/1q2P3/2NPQN2/PPP3PP/R4RK1 b - - 2 15",
+    "r1bbk1nr/pp3p1p/2n5/1N4p1/2Np1B2/8/PPP2PPP/2KR1B1R w kq - 0 13",
+    "r1bq1rk1/ppp1nppp/4n3/3p3Q/3P4/1BP1B3/PP1N2PP/R4RK1 w - - 1 16",
+    "4r1k1/r1q2ppp/ppp2n2/4P3/5Rb1/1N1BQ3/PPP3PP/R5K1 w - - 1 17",
+    "2rqkb1r/ppp2p2/2npb1p1/1N1Nn2p/2P1PP2/8/PP2B1PP/R1BQK2R b KQ - 0 11",
+    "r1b2rk1/2q1b1pp/p2ppn2/1p6/3QP3/1BN1B3/PPP3PP/R4RK1 w - - 0 15",
+    "6k1/6p1/6Pp/ppp5/3pn2P/1P3K2/1PP2P2/8 b - - 0 1",
+    "8/8/1p1r1k2/p1pPN1p1/P3KnP1/1P6/8/3R4 b - - 6 54",
+    "7r/1p4k1/p1bp2pp/2p1p3/P1P1P3/1P1PQ3/4BPqP/R5K1 w - - 0 31",
+    "8/3p3B/5p2/5P2/p7/PP5b/k7/6K1 w - - 0 1",
+];
+
+pub struct BenchResult {
+    pub nodes: u64,
+    pub elapsed_ms: u64,
+}
+
+impl BenchResult {
+    pub fn nps(&self) -> u64 {
+        self.nodes * 1000 / self.elapsed_ms.max(1)
+    }
+}
+
+// Searches every bench position to `depth`; `report` sees each result.
+pub fn run(depth: i32, mut report: impl FnMut(usize, &str, &SearchResult)) -> BenchResult {
+    let start = Instant::now();
+    let mut nodes = 0;
+    for (i, fen) in BENCH_POSITIONS.iter().enumerate() {
+        let board = Board::from_str(fen).expect("bench positions are valid");
+        let mut info = SearchInfo::isolated(u64::MAX);
+        let result = search(&board, depth, &mut info);
+        nodes += result.nodes;
+        report(i, fen, &result);
+    }
+    BenchResult { nodes, elapsed_ms: start.elapsed().as_millis() as u64 }
+}
+
+// The report engine testing frameworks parse: per-position lines, then the
+// total time, node count and speed.
+pub fn print(depth: i32) -> BenchResult {
+    let result = run(depth, |i, fen, r| {
+        println!("Position {}/{}: {} nodes {} bestmove {}", i + 1, BENCH_POSITIONS.len(), fen, r.nodes, r.best_move.map_or("0000".to_string(), |m| m.to_string()));
+    });
+    println!();
+    println!("Total time (ms) : {}", result.elapsed_ms);
+    println!("Nodes searched  : {}", result.nodes);
+    println!("Nodes/second    : {}", result.nps());
+    result
+}
@@ -0,0 +1,79 @@
+use axelrot::annotate::{annotate, format_summary, summarize, AnnotateConfig};
+use axelrot::pgn::{parse_games, write_game};
+use std::env;
+use std::path::PathBuf;
+use std::process;
+
+fn usage() -> ! {
+    eprintln!(
+        "usage: annotate <pgn> [--output <pgn>] [--depth <n>] [--movetime <ms>] [--nodes <n>] \
+         [--inaccuracy <cp>] [--mistake <cp>] [--blunder <cp>] [--variation-plies <n>]"
+    );
+    process::exit(1);
+}
+
+fn fail(message: String) -> ! {
+    eprintln!("error: {}", message);
+    process::exit(1);
+}
+
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    let mut config = AnnotateConfig::default();
+    let mut input = None;
+    let mut output = None;
+
+    let mut i = 0;
+    while i < args.len() {
+        if !args[i].starts_with("--") {
+            if input.replace(PathBuf::from(&args[i])).is_some() {
+                usage();
+            }
+            i += 1;
+            continue;
+        }
+        let value = args.get(i + 1).cloned().unwrap_or_else(|| usage());
+        let number = || value.parse::<u64>().unwrap_or_else(|_| usage());
+        match args[i].as_str() {
+            "--output" => output = Some(PathBuf::from(&value)),
+            "--depth" => config.depth = number().clamp(1, 64) as i32,
+            "--movetime" => config.movetime = Some(number()),
+            "--nodes" => config.nodes = Some(number()),
+            "--inaccuracy" => config.inaccuracy = number() as i32,
+            "--mistake" => config.mistake = number() as i32,
+            "--blunder" => config.blunder = number() as i32,
+            "--variation-plies" => config.variation_plies = number() as usize,
+            _ => usage(),
+        }
+        i += 2;
+    }
+    let input = input.unwrap_or_else(|| usage());
+    let output = output.unwrap_or_else(|| input.with_extension("annotated.pgn"));
+
+    let text = std::fs::read_to_string(&input).unwrap_or_else(|e| fail(format!("cannot read {}: {}", input.display(), e)));
+    let mut annotated = String::new();
+    let mut players = Vec::new();
+    let mut skipped = 0;
+    for (index, game) in parse_games(&text).into_iter().enumerate() {
+        let result = game.and_then(|game| {
+            let (annotated_game, reviews) = annotate(&game, &config, |done, total| {
+                eprint!("\rgame {}: position {}/{}", index + 1, done, total);
+            })?;
+            eprintln!();
+            // Games that fail to write are skipped, so they stay out of the summary too.
+            let text = write_game(&annotated_game)?;
+            summarize(&mut players, &game, &reviews);
+            Ok(text)
+        });
+        match result {
+            Ok(text) => annotated += &text,
+            Err(e) => {
+                eprintln!("skipping {}", e);
+                skipped += 1;
+            }
+        }
+    }
+    std::fs::write(&output, annotated).unwrap_or_else(|e| fail(format!("cannot write {}: {}", output.display(), e)));
+    println!("wrote {} ({} games skipped)", output.display(), skipped);
+    print!("{}", format_summary(&players));
+}
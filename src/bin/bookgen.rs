@@ -0,0 +1,96 @@
+use axelrot::book::Book;
+use axelrot::bookgen::{merge, BookBuilder, BookgenConfig};
+use axelrot::pgn::{parse_games, to_san};
+use chess::Board;
+use std::env;
+use std::path::{Path, PathBuf};
+use std::process;
+use std::str::FromStr;
+
+fn usage() -> ! {
+    eprintln!("usage: bookgen build <pgn>... --output <book.bin> [--max-ply <n>] [--min-count <n>] [--min-elo <n>]");
+    eprintln!("       bookgen merge <book.bin>... --output <book.bin>");
+    eprintln!("       bookgen dump <book.bin> [--fen <fen>]");
+    process::exit(1);
+}
+
+fn fail(message: String) -> ! {
+    eprintln!("error: {}", message);
+    process::exit(1);
+}
+
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    let Some(command) = args.first() else { usage() };
+    let mut inputs = Vec::new();
+    let mut output = None;
+    let mut fen = None;
+    let mut config = BookgenConfig::default();
+
+    let mut i = 1;
+    while i < args.len() {
+        let value = || args.get(i + 1).cloned().unwrap_or_else(|| usage());
+        let number = || value().parse::<u32>().unwrap_or_else(|_| usage());
+        match args[i].as_str() {
+            "--output" => output = Some(PathBuf::from(value())),
+            "--max-ply" => config.max_ply = number() as usize,
+            "--min-count" => config.min_count = number(),
+            "--min-elo" => config.min_elo = number(),
+            "--fen" => fen = Some(value()),
+            arg if !arg.starts_with("--") => {
+                inputs.push(PathBuf::from(arg));
+                i += 1;
+                continue;
+            }
+            _ => usage(),
+        }
+        i += 2;
+    }
+
+    match command.as_str() {
+        "build" => {
+            let output = output.unwrap_or_else(|| usage());
+            let mut builder = BookBuilder::new(config);
+            let mut skipped = 0;
+            for path in &inputs {
+                let text = std::fs::read_to_string(path)
+                    .unwrap_or_else(|e| fail(format!("cannot read {}: {}", path.display(), e)));
+                for game in parse_games(&text) {
+                    match game.and_then(|g| builder.add_game(&g)) {
+                        Ok(true) => {}
+                        Ok(false) => skipped += 1,
+                        Err(e) => {
+                            eprintln!("warning: {}: {}", path.display(), e);
+                            skipped += 1;
+                        }
+                    }
+                }
+            }
+            let book = builder.build();
+            book.save(&output).unwrap_or_else(|e| fail(e));
+            println!("{} games ({} skipped), {} entries -> {}", builder.games(), skipped, book.len(), output.display());
+        }
+        "merge" => {
+            let output = output.unwrap_or_else(|| usage());
+            let books: Vec<Book> = inputs.iter().map(|p| Book::open(p).unwrap_or_else(|e| fail(e))).collect();
+            let book = merge(&books);
+            book.save(&output).unwrap_or_else(|e| fail(e));
+            println!("merged {} books, {} entries -> {}", books.len(), book.len(), output.display());
+        }
+        "dump" => {
+            let [path] = inputs.as_slice() else { usage() };
+            let book = Book::open(Path::new(path)).unwrap_or_else(|e| fail(e));
+            let board = match &fen {
+                Some(fen) => Board::from_str(fen).unwrap_or_else(|_| fail(format!("invalid FEN {}", fen))),
+                None => Board::default(),
+            };
+            let moves = book.moves(&board);
+            let total: u64 = moves.iter().map(|&(_, w)| w as u64).sum();
+            for (mv, weight) in moves {
+                let percent = if total > 0 { 100.0 * weight as f64 / total as f64 } else { 0.0 };
+                println!("{:<8} {:<6} weight {:>5} ({:.1}%)", to_san(&board, mv), mv, weight, percent);
+            }
+        }
+        _ => usage(),
+    }
+}
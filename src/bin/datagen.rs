@@ -0,0 +1,59 @@
+use axelrot::datagen::{run, DatagenConfig};
+use std::env;
+use std::path::PathBuf;
+use std::process;
+use std::thread;
+
+fn usage() -> ! {
+    eprintln!(
+        "usage: datagen [--output <path>] [--games <n>] [--threads <n>] [--nodes <n>] \
+         [--random-plies <n>] [--max-plies <n>] [--seed <n>]"
+    );
+    process::exit(1);
+}
+
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    let mut config = DatagenConfig {
+        threads: thread::available_parallelism().map(|n| n.get()).unwrap_or(1),
+        ..DatagenConfig::default()
+    };
+
+    let mut i = 0;
+    while i < args.len() {
+        let value = args.get(i + 1).cloned().unwrap_or_else(|| usage());
+        let number = || value.parse::<u64>().unwrap_or_else(|_| usage());
+        match args[i].as_str() {
+            "--output" => config.output = PathBuf::from(&value),
+            "--games" => config.games = number() as usize,
+            "--threads" => config.threads = number() as usize,
+            "--nodes" => config.nodes = number(),
+            "--random-plies" => config.random_plies = number() as usize,
+            "--max-plies" => config.max_plies = number() as usize,
+            "--seed" => config.seed = number(),
+            _ => usage(),
+        }
+        i += 2;
+    }
+
+    println!(
+        "generating {} games at {} nodes on {} threads (seed {}) into {}",
+        config.games,
+        config.nodes,
+        config.threads,
+        config.seed,
+        config.binary_path().display()
+    );
+    let result = run(&config, |finished, total, positions| {
+        if finished % 10 == 0 || finished == total {
+            println!("games {}/{}  positions {}", finished, total, positions);
+        }
+    });
+    match result {
+        Ok(positions) => println!("done, {} new positions", positions),
+        Err(e) => {
+            eprintln!("error: {}", e);
+            process::exit(1);
+        }
+    }
+}
@@ -0,0 +1,202 @@
+use axelrot::matches::{
+    run, DrawAdjudication, EngineSpec, MatchConfig, Openings, ResignAdjudication, Tally, TimeControl,
+};
+use axelrot::stats::{self, Sprt};
+use axelrot::syzygy;
+use std::env;
+use std::path::{Path, PathBuf};
+use std::process;
+
+fn usage() -> ! {
+    eprintln!("usage: match -engine cmd=<path> [name=<name>] [arg=<arg>] [option.<name>=<value>] [depth=<n>] [nodes=<n>]");
+    eprintln!("             -engine ... [-each <engine settings>] [-games <n>] [-concurrency <n>] [-tc <base+inc>]");
+    eprintln!("             [-openings <file.epd|book.bin>] [-bookplies <n>] [-pgnout <file>] [-timemargin <ms>]");
+    eprintln!("             [-draw movenumber=<n> movecount=<n> score=<cp>] [-resign movecount=<n> score=<cp>]");
+    eprintln!("             [-sprt elo0=<elo> elo1=<elo> alpha=<p> beta=<p>] [-ratinginterval <n>]");
+    eprintln!("             [-tb <syzygy path>] [-maxmoves <n>] [-event <name>] [-seed <n>]");
+    process::exit(1);
+}
+
+fn fail(message: String) -> ! {
+    eprintln!("error: {}", message);
+    process::exit(1);
+}
+
+fn number<T: std::str::FromStr>(value: &str) -> T {
+    value.parse().unwrap_or_else(|_| usage())
+}
+
+// Applies `key=value` engine settings.
+fn configure(spec: &mut EngineSpec, settings: &[String]) {
+    for setting in settings {
+        let Some((key, value)) = setting.split_once('=') else { usage() };
+        match key {
+            "cmd" => spec.cmd = PathBuf::from(value),
+            "name" => spec.name = value.to_string(),
+            "arg" => spec.args.push(value.to_string()),
+            "depth" => spec.depth = Some(number(value)),
+            "nodes" => spec.nodes = Some(number(value)),
+            "proto" if value == "uci" => {}
+            _ => match key.strip_prefix("option.") {
+                Some(name) => spec.options.push((name.to_string(), value.to_string())),
+                None => usage(),
+            },
+        }
+    }
+}
+
+fn settings(settings: &[String]) -> Vec<(&str, &str)> {
+    settings.iter().map(|s| s.split_once('=').unwrap_or_else(|| usage())).collect()
+}
+
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    // Each flag takes the values up to the next flag.
+    let mut flags: Vec<(&str, Vec<String>)> = Vec::new();
+    for arg in &args {
+        match arg.strip_prefix('-') {
+            Some(flag) if !flag.is_empty() && !flag.starts_with(|c: char| c.is_ascii_digit()) => {
+                flags.push((flag.trim_start_matches('-'), Vec::new()))
+            }
+            _ => flags.last_mut().unwrap_or_else(|| usage()).1.push(arg.clone()),
+        }
+    }
+
+    let mut engines = Vec::new();
+    let mut each = Vec::new();
+    let mut openings = None;
+    let mut book_plies = 8;
+    let mut tc = None;
+    let mut rating_interval = 10;
+    let mut rest = Vec::new();
+    for (flag, values) in &flags {
+        match *flag {
+            "engine" => engines.push(values.clone()),
+            "each" => each.extend(values.iter().cloned()),
+            "openings" => openings = Some(values.iter().find_map(|v| v.strip_prefix("file=")).unwrap_or_else(|| values.first().map(String::as_str).unwrap_or_else(|| usage())).to_string()),
+            "bookplies" => book_plies = values.first().map(|v| number(v)).unwrap_or_else(|| usage()),
+            "tc" => tc = Some(values.first().unwrap_or_else(|| usage()).parse::<TimeControl>().unwrap_or_else(|e| fail(e))),
+            _ => rest.push((*flag, values)),
+        }
+    }
+    let [first, second] = engines.as_slice() else { usage() };
+    let specs = [first, second].map(|settings| {
+        let mut spec = EngineSpec::default();
+        configure(&mut spec, &each);
+        configure(&mut spec, settings);
+        if spec.cmd.as_os_str().is_empty() {
+            usage();
+        }
+        if spec.name.is_empty() {
+            spec.name = spec.cmd.file_stem().map(|s| s.to_string_lossy().into_owned()).unwrap_or_default();
+        }
+        spec
+    });
+    let mut config = MatchConfig::new(specs);
+    if config.engines[0].name == config.engines[1].name {
+        config.engines[1].name += "-2";
+    }
+    if let Some(tc) = tc {
+        config.tc = tc;
+    }
+    if let Some(path) = openings {
+        config.openings = Some(Openings::open(Path::new(&path), book_plies).unwrap_or_else(|e| fail(e)));
+    }
+    for (flag, values) in rest {
+        let value = || values.first().map(String::as_str).unwrap_or_else(|| usage());
+        match flag {
+            "games" => config.games = number(value()),
+            "concurrency" => config.concurrency = number(value()),
+            "pgnout" => config.pgn_out = Some(PathBuf::from(value())),
+            "timemargin" => config.time_margin_ms = number(value()),
+            "maxmoves" => config.max_moves = number(value()),
+            "event" => config.event = values.join(" "),
+            "seed" => config.seed = number(value()),
+            "ratinginterval" => rating_interval = number(value()),
+            "sprt" => {
+                let mut sprt = Sprt::default();
+                for (key, value) in settings(values) {
+                    match key {
+                        "elo0" => sprt.elo0 = number(value),
+                        "elo1" => sprt.elo1 = number(value),
+                        "alpha" => sprt.alpha = number(value),
+                        "beta" => sprt.beta = number(value),
+                        _ => usage(),
+                    }
+                }
+                config.sprt = Some(sprt);
+            }
+            "repeat" | "recover" => {}
+            "tb" => {
+                let count = syzygy::set_path(value()).unwrap_or_else(|e| fail(e));
+                println!("found {} tablebases", count);
+                config.tb_adjudication = true;
+            }
+            "draw" => {
+                let mut draw = DrawAdjudication { movenumber: 40, movecount: 8, score: 10 };
+                for (key, value) in settings(values) {
+                    match key {
+                        "movenumber" => draw.movenumber = number(value),
+                        "movecount" => draw.movecount = number(value),
+                        "score" => draw.score = number(value),
+                        _ => usage(),
+                    }
+                }
+                config.draw = Some(draw);
+            }
+            "resign" => {
+                let mut resign = ResignAdjudication { movecount: 3, score: 1000 };
+                for (key, value) in settings(values) {
+                    match key {
+                        "movecount" => resign.movecount = number(value),
+                        "score" => resign.score = number(value),
+                        _ => usage(),
+                    }
+                }
+                config.resign = Some(resign);
+            }
+            _ => usage(),
+        }
+    }
+
+    println!(
+        "{} vs {}: {} games, tc {}+{} ms, concurrency {}",
+        config.engines[0].name,
+        config.engines[1].name,
+        config.games,
+        config.tc.base_ms,
+        config.tc.inc_ms,
+        config.concurrency
+    );
+    let result = run(&config, |game, tally| {
+        println!(
+            "Finished game {} ({} vs {}): {} {{{}}}",
+            game.round + 1,
+            game.white,
+            game.black,
+            game.result,
+            game.reason
+        );
+        println!(
+            "Score of {} vs {}: {} - {} - {} [{:.3}] {}",
+            config.engines[0].name,
+            config.engines[1].name,
+            tally.wins,
+            tally.losses,
+            tally.draws,
+            (tally.wins as f64 + tally.draws as f64 / 2.0) / tally.games() as f64,
+            tally.games()
+        );
+        if rating_interval > 0 && (tally.games() as usize).is_multiple_of(rating_interval) {
+            print!("{}", stats::summary(tally.wdl(), &tally.pairs, config.sprt.as_ref()));
+        }
+    });
+    match result {
+        Ok(games) => {
+            let tally = Tally::new(&games);
+            println!("Finished match");
+            print!("{}", stats::summary(tally.wdl(), &tally.pairs, config.sprt.as_ref()));
+        }
+        Err(e) => fail(e),
+    }
+}
@@ -0,0 +1,72 @@
+use axelrot::dtm::{DtmTables, Material};
+use std::env;
+use std::path::PathBuf;
+use std::process;
+use std::thread;
+use std::time::Instant;
+
+fn usage() -> ! {
+    eprintln!("usage: tbgen <material>... [--output <dir>] [--threads <n>]");
+    eprintln!("  materials are written like KQvK or KRvKP, at most 4 pieces");
+    process::exit(1);
+}
+
+fn fail(message: String) -> ! {
+    eprintln!("error: {}", message);
+    process::exit(1);
+}
+
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    let mut output = PathBuf::from("tablebases");
+    let mut threads = thread::available_parallelism().map(|n| n.get()).unwrap_or(1);
+    let mut materials = Vec::new();
+
+    let mut i = 0;
+    while i < args.len() {
+        match args[i].as_str() {
+            "--output" => output = PathBuf::from(args.get(i + 1).unwrap_or_else(|| usage())),
+            "--threads" => {
+                threads = args.get(i + 1).and_then(|v| v.parse().ok()).unwrap_or_else(|| usage());
+            }
+            name if !name.starts_with('-') => {
+                materials.push(Material::parse(name).unwrap_or_else(|e| fail(e)));
+                i += 1;
+                continue;
+            }
+            _ => usage(),
+        }
+        i += 2;
+    }
+    if materials.is_empty() {
+        usage();
+    }
+
+    std::fs::create_dir_all(&output).unwrap_or_else(|e| fail(format!("cannot create {}: {}", output.display(), e)));
+    // Tables already on disk are reused instead of being generated again.
+    let mut tables = DtmTables::open(output.to_str().unwrap_or_else(|| usage())).unwrap_or_else(|e| fail(e));
+    for material in &materials {
+        let start = Instant::now();
+        let built = tables.generate(material, threads).unwrap_or_else(|e| fail(e));
+        for material in &built {
+            let table = tables.get(material).unwrap();
+            let path = output.join(table.file_name());
+            table.save(&path).unwrap_or_else(|e| fail(e));
+            let stats = table.stats();
+            println!(
+                "{}: {} positions, {} wins, {} losses, longest mate {} plies -> {}",
+                material,
+                table.len(),
+                stats.wins,
+                stats.losses,
+                stats.longest_mate,
+                path.display()
+            );
+        }
+        if built.is_empty() {
+            println!("{}: already generated", material.canonical());
+        } else {
+            println!("{}: done in {:.1}s", material.canonical(), start.elapsed().as_secs_f64());
+        }
+    }
+}
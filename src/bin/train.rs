@@ -0,0 +1,108 @@
+use axelrot::datagen::read_records;
+use axelrot::nnue_train::{sample_from_record, FloatNetwork, LrSchedule, TrainConfig, Trainer};
+use std::env;
+use std::process;
+
+fn usage() -> ! {
+    eprintln!(
+        "usage: train <records.bin>... [--output <net.nnue>] [--hidden <n>] [--epochs <n>] \
+         [--batch <n>] [--lr <f>] [--wdl <f>] [--lr-step <n> --lr-gamma <f> | --cosine <min-lr>] \
+         [--checkpoint <file>] [--resume <file>] [--seed <n>]"
+    );
+    process::exit(1);
+}
+
+fn fail(message: String) -> ! {
+    eprintln!("error: {}", message);
+    process::exit(1);
+}
+
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    let mut config = TrainConfig::default();
+    let mut inputs = Vec::new();
+    let mut output = "axelrot.nnue".to_string();
+    let mut checkpoint = None;
+    let mut resume = None;
+    let mut lr_step = None;
+    let mut lr_gamma = 0.3f32;
+    let mut cosine = None;
+
+    let mut i = 0;
+    while i < args.len() {
+        if !args[i].starts_with("--") {
+            inputs.push(args[i].clone());
+            i += 1;
+            continue;
+        }
+        let value = args.get(i + 1).cloned().unwrap_or_else(|| usage());
+        let int = || value.parse::<usize>().unwrap_or_else(|_| usage());
+        let float = || value.parse::<f32>().unwrap_or_else(|_| usage());
+        match args[i].as_str() {
+            "--output" => output = value.clone(),
+            "--hidden" => config.hidden = int(),
+            "--epochs" => config.epochs = int(),
+            "--batch" => config.batch_size = int(),
+            "--lr" => config.lr = float(),
+            "--wdl" => config.wdl = float().clamp(0.0, 1.0),
+            "--lr-step" => lr_step = Some(int()),
+            "--lr-gamma" => lr_gamma = float(),
+            "--cosine" => cosine = Some(float()),
+            "--checkpoint" => checkpoint = Some(value.clone()),
+            "--resume" => resume = Some(value.clone()),
+            "--seed" => config.seed = int() as u64,
+            _ => usage(),
+        }
+        i += 2;
+    }
+    if inputs.is_empty() {
+        usage();
+    }
+    config.schedule = match (cosine, lr_step) {
+        (Some(min_lr), _) => LrSchedule::Cosine { epochs: config.epochs, min_lr },
+        (None, Some(every)) => LrSchedule::Step { every, gamma: lr_gamma },
+        (None, None) => config.schedule,
+    };
+    let checkpoint = checkpoint.unwrap_or_else(|| format!("{}.ckpt", output));
+
+    let mut samples = Vec::new();
+    for path in &inputs {
+        let records = read_records(path).unwrap_or_else(|e| fail(e));
+        samples.extend(records.iter().map(|r| sample_from_record(r, config.wdl)));
+    }
+    if samples.is_empty() {
+        fail("no training records found".to_string());
+    }
+
+    let (mut trainer, start) = match &resume {
+        Some(path) => Trainer::load_checkpoint(path, config.clone()).unwrap_or_else(|e| fail(e)),
+        None => (Trainer::new(FloatNetwork::random(config.hidden, config.seed), config.clone()), 0),
+    };
+    println!(
+        "training {} hidden on {} positions for {} epochs (batch {}, lr {}, wdl {})",
+        trainer.net.hidden,
+        samples.len(),
+        config.epochs,
+        config.batch_size,
+        config.lr,
+        config.wdl
+    );
+    if start > 0 {
+        println!("resuming after epoch {}", start);
+    }
+    println!("initial loss {:.6}", trainer.net.loss(&samples));
+
+    for epoch in start..config.epochs {
+        let loss = trainer.epoch(&samples, epoch);
+        println!(
+            "epoch {:>3}  lr {:.6}  loss {:.6}",
+            epoch + 1,
+            config.schedule.rate(config.lr, epoch),
+            loss
+        );
+        trainer.save_checkpoint(&checkpoint, epoch + 1).unwrap_or_else(|e| fail(e));
+    }
+
+    trainer.net.quantise().save(&output).unwrap_or_else(|e| fail(e));
+    println!("wrote {} (checkpoint {})", output, checkpoint);
+}
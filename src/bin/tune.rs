@@ -0,0 +1,79 @@
+use axelrot::params::EvalParams;
+use axelrot::tune::{load_positions, resolve_positions, Tuner};
+use std::env;
+use std::process;
+use std::thread;
+
+fn usage() -> ! {
+    eprintln!("usage: tune <positions-file> [--params <file>] [--output <file>] [--epochs <n>] [--threads <n>]");
+    process::exit(1);
+}
+
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    let mut positions_path = None;
+    let mut params_path = None;
+    let mut output = "tuned_params.json".to_string();
+    let mut epochs = 100usize;
+    let mut threads = thread::available_parallelism().map(|n| n.get()).unwrap_or(1);
+
+    let mut i = 0;
+    while i < args.len() {
+        let value = args.get(i + 1).cloned();
+        match args[i].as_str() {
+            "--params" => params_path = value,
+            "--output" => output = value.unwrap_or_else(|| usage()),
+            "--epochs" => epochs = value.and_then(|v| v.parse().ok()).unwrap_or_else(|| usage()),
+            "--threads" => threads = value.and_then(|v| v.parse().ok()).unwrap_or_else(|| usage()),
+            arg if !arg.starts_with("--") && positions_path.is_none() => {
+                positions_path = Some(arg.to_string());
+                i += 1;
+                continue;
+            }
+            _ => usage(),
+        }
+        i += 2;
+    }
+    let positions_path = positions_path.unwrap_or_else(|| usage());
+
+    let params = match params_path {
+        Some(path) => EvalParams::load(&path).unwrap_or_else(|e| {
+            eprintln!("error: {}", e);
+            process::exit(1);
+        }),
+        None => EvalParams::default(),
+    };
+    let mut positions = load_positions(&positions_path).unwrap_or_else(|e| {
+        eprintln!("error: {}", e);
+        process::exit(1);
+    });
+    if positions.is_empty() {
+        eprintln!("error: no positions found in {}", positions_path);
+        process::exit(1);
+    }
+    resolve_positions(&mut positions, &params);
+    println!("loaded {} positions, tuning on {} threads", positions.len(), threads);
+
+    let mut tuner = Tuner::new(params, &positions, threads);
+    println!("K = {:.4}, initial error = {:.8}", tuner.k, tuner.best_error);
+    for epoch in 1..=epochs {
+        let previous = tuner.best_error;
+        let changed = tuner.epoch(&positions);
+        println!(
+            "epoch {:>4}  error {:.8}  delta {:+.8}  changed {}",
+            epoch,
+            tuner.best_error,
+            tuner.best_error - previous,
+            changed
+        );
+        if let Err(e) = tuner.params.save(&output) {
+            eprintln!("error: {}", e);
+            process::exit(1);
+        }
+        if changed == 0 {
+            println!("converged");
+            break;
+        }
+    }
+    println!("wrote {}", output);
+}
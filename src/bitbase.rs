@@ -0,0 +1,260 @@
+use chess::{
+    get_bishop_moves, get_king_moves, get_knight_moves, get_pawn_attacks, get_rook_moves, BitBoard, Board, Color,
+    Piece, Square, ALL_SQUARES, EMPTY,
+};
+use std::sync::LazyLock;
+
+// Positions are normalised so the side with the extra piece is White. Index
+// layout: side to move (0 = strong), strong king, weak king, piece square.
+const SIZE: usize = 2 * 64 * 64 * 64;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum BitbaseResult {
+    Win(Color),
+    Draw,
+}
+
+pub struct Bitbase {
+    piece: Piece,
+    wins: Vec<u64>,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum State {
+    Invalid,
+    Unknown,
+    Draw,
+    Win,
+}
+
+fn index(strong_to_move: bool, strong_king: usize, weak_king: usize, piece_sq: usize) -> usize {
+    (((!strong_to_move as usize) * 64 + strong_king) * 64 + weak_king) * 64 + piece_sq
+}
+
+fn sq(i: usize) -> Square {
+    ALL_SQUARES[i]
+}
+
+fn bb(i: usize) -> BitBoard {
+    BitBoard::from_square(sq(i))
+}
+
+fn piece_attacks(piece: Piece, from: usize, occupied: BitBoard) -> BitBoard {
+    match piece {
+        Piece::Pawn => get_pawn_attacks(sq(from), Color::White, !EMPTY),
+        Piece::Knight => get_knight_moves(sq(from)),
+        Piece::Bishop => get_bishop_moves(sq(from), occupied),
+        Piece::Rook => get_rook_moves(sq(from), occupied),
+        Piece::Queen => get_bishop_moves(sq(from), occupied) | get_rook_moves(sq(from), occupied),
+        Piece::King => get_king_moves(sq(from)),
+    }
+}
+
+struct Generator {
+    piece: Piece,
+    states: Vec<State>,
+}
+
+impl Generator {
+    fn new(piece: Piece) -> Self {
+        let mut generator = Generator { piece, states: vec![State::Invalid; SIZE] };
+        for i in 0..SIZE {
+            generator.states[i] = generator.initial(i);
+        }
+        generator
+    }
+
+    fn decode(i: usize) -> (bool, usize, usize, usize) {
+        (i >> 18 == 0, (i >> 12) & 63, (i >> 6) & 63, i & 63)
+    }
+
+    fn initial(&self, i: usize) -> State {
+        let (strong_to_move, sk, wk, psq) = Self::decode(i);
+        if sk == wk || sk == psq || wk == psq || get_king_moves(sq(sk)) & bb(wk) != EMPTY {
+            return State::Invalid;
+        }
+        if self.piece == Piece::Pawn && !(8..56).contains(&psq) {
+            return State::Invalid;
+        }
+        let occupied = bb(sk) | bb(wk) | bb(psq);
+        let weak_in_check = piece_attacks(self.piece, psq, occupied) & bb(wk) != EMPTY;
+        if strong_to_move && weak_in_check {
+            return State::Invalid;
+        }
+        if !strong_to_move {
+            // The lone king wins the piece back: nothing left to mate with.
+            let piece_defended = get_king_moves(sq(sk)) & bb(psq) != EMPTY;
+            if get_king_moves(sq(wk)) & bb(psq) != EMPTY && !piece_defended {
+                return State::Draw;
+            }
+            if self.weak_moves(sk, wk, psq).next().is_none() {
+                return if weak_in_check { State::Win } else { State::Draw };
+            }
+        }
+        State::Unknown
+    }
+
+    fn weak_moves(&self, sk: usize, wk: usize, psq: usize) -> impl Iterator<Item = usize> {
+        // Squares behind the weak king along a slider's line stay attacked.
+        let occupied = bb(sk) | bb(psq);
+        let attacked = get_king_moves(sq(sk)) | piece_attacks(self.piece, psq, occupied);
+        (get_king_moves(sq(wk)) & !attacked & !bb(sk)).into_iter().map(|s| s.to_index())
+    }
+
+    fn classify(&self, i: usize) -> State {
+        let (strong_to_move, sk, wk, psq) = Self::decode(i);
+        if strong_to_move {
+            let mut all_draw = true;
+            let king_targets = get_king_moves(sq(sk)) & !get_king_moves(sq(wk)) & !bb(psq);
+            for to in king_targets {
+                match self.states[index(false, to.to_index(), wk, psq)] {
+                    State::Win => return State::Win,
+                    State::Draw => {}
+                    _ => all_draw = false,
+                }
+            }
+            let (moves, count) = self.piece_moves(sk, wk, psq);
+            for to in &moves[..count] {
+                let child = match *to {
+                    Some(to) => self.states[index(false, sk, wk, to)],
+                    None => State::Win,
+                };
+                match child {
+                    State::Win => return State::Win,
+                    State::Draw => {}
+                    _ => all_draw = false,
+                }
+            }
+            if all_draw { State::Draw } else { State::Unknown }
+        } else {
+            let mut all_win = true;
+            for to in self.weak_moves(sk, wk, psq) {
+                let child = if to == psq { State::Draw } else { self.states[index(true, sk, to, psq)] };
+                match child {
+                    State::Draw => return State::Draw,
+                    State::Win => {}
+                    _ => all_win = false,
+                }
+            }
+            if all_win { State::Win } else { State::Unknown }
+        }
+    }
+
+    // Destinations of the extra piece. `None` stands for a promotion the
+    // defending king cannot stop, which is scored as a win outright.
+    fn piece_moves(&self, sk: usize, wk: usize, psq: usize) -> ([Option<usize>; 27], usize) {
+        let mut moves = [None; 27];
+        let mut count = 0;
+        let mut push = |to: Option<usize>| {
+            moves[count] = to;
+            count += 1;
+        };
+        let occupied = bb(sk) | bb(wk) | bb(psq);
+        if self.piece != Piece::Pawn {
+            for to in piece_attacks(self.piece, psq, occupied) & !bb(sk) & !bb(wk) {
+                push(Some(to.to_index()));
+            }
+        } else if occupied & bb(psq + 8) == EMPTY {
+            let one = psq + 8;
+            if one >= 56 {
+                let safe = get_king_moves(sq(wk)) & bb(one) == EMPTY || get_king_moves(sq(sk)) & bb(one) != EMPTY;
+                if safe {
+                    push(None);
+                }
+            } else {
+                push(Some(one));
+                if psq < 16 && occupied & bb(psq + 16) == EMPTY {
+                    push(Some(psq + 16));
+                }
+            }
+        }
+        (moves, count)
+    }
+
+    // Repeatedly resolves unknown positions from their children until nothing
+    // changes; whatever the strong side still cannot force is a draw.
+    fn run(mut self) -> Bitbase {
+        let mut pending: Vec<usize> = (0..SIZE).filter(|&i| self.states[i] == State::Unknown).collect();
+        loop {
+            let before = pending.len();
+            pending.retain(|&i| {
+                let state = self.classify(i);
+                self.states[i] = state;
+                state == State::Unknown
+            });
+            if pending.len() == before {
+                break;
+            }
+        }
+        for i in pending {
+            self.states[i] = State::Draw;
+        }
+        let mut wins = vec![0u64; SIZE / 64];
+        for (i, state) in self.states.iter().enumerate() {
+            if *state == State::Win {
+                wins[i / 64] |= 1 << (i % 64);
+            }
+        }
+        Bitbase { piece: self.piece, wins }
+    }
+}
+
+impl Bitbase {
+    // Generates the table for king plus `piece` against a lone king.
+    pub fn generate(piece: Piece) -> Self {
+        Generator::new(piece).run()
+    }
+
+    pub fn piece(&self) -> Piece {
+        self.piece
+    }
+
+    pub fn is_win(&self, strong_to_move: bool, strong_king: Square, weak_king: Square, piece_sq: Square) -> bool {
+        let i = index(strong_to_move, strong_king.to_index(), weak_king.to_index(), piece_sq.to_index());
+        self.wins[i / 64] & (1 << (i % 64)) != 0
+    }
+
+    pub fn probe(&self, board: &Board) -> Option<BitbaseResult> {
+        if board.combined().popcnt() != 3 {
+            return None;
+        }
+        let pieces = board.pieces(self.piece);
+        if pieces.popcnt() != 1 || self.piece == Piece::King {
+            return None;
+        }
+        let piece_sq = pieces.to_square();
+        let strong = board.color_on(piece_sq)?;
+        let flip = |s: Square| match strong {
+            Color::White => s,
+            Color::Black => sq(s.to_index() ^ 56),
+        };
+        let win = self.is_win(
+            board.side_to_move() == strong,
+            flip(board.king_square(strong)),
+            flip(board.king_square(!strong)),
+            flip(piece_sq),
+        );
+        Some(if win { BitbaseResult::Win(strong) } else { BitbaseResult::Draw })
+    }
+}
+
+static KPK: LazyLock<Bitbase> = LazyLock::new(|| Bitbase::generate(Piece::Pawn));
+static KRK: LazyLock<Bitbase> = LazyLock::new(|| Bitbase::generate(Piece::Rook));
+static KQK: LazyLock<Bitbase> = LazyLock::new(|| Bitbase::generate(Piece::Queen));
+
+// Exact result for the built-in 3-piece endings; tables are generated the
+// first time each one is needed.
+pub fn probe(board: &Board) -> Option<BitbaseResult> {
+    if board.combined().popcnt() != 3 {
+        return None;
+    }
+    if *board.pieces(Piece::Pawn) != EMPTY {
+        KPK.probe(board)
+    } else if *board.pieces(Piece::Rook) != EMPTY {
+        KRK.probe(board)
+    } else if *board.pieces(Piece::Queen) != EMPTY {
+        KQK.probe(board)
+    } else {
+        None
+    }
+}
@@ -0,0 +1,109 @@
+use crate::book::{encode_move, polyglot_key, Book, BookEntry};
+use crate::pgn::Game;
+use chess::Color;
+use std::collections::HashMap;
+
+#[derive(Clone, Debug)]
+pub struct BookgenConfig {
+    // Only the first `max_ply` plies of each game go into the book.
+    pub max_ply: usize,
+    // Moves played fewer times than this are left out.
+    pub min_count: u32,
+    // Moves by players rated below this are ignored; unrated players count
+    // as 2000.
+    pub min_elo: u32,
+}
+
+impl Default for BookgenConfig {
+    fn default() -> Self {
+        BookgenConfig { max_ply: 24, min_count: 1, min_elo: 0 }
+    }
+}
+
+const UNRATED: u32 = 2000;
+
+#[derive(Clone, Copy, Default, Debug)]
+struct MoveStats {
+    games: u32,
+    score: f64,
+}
+
+// Aggregates (position, move) statistics over many games. Each time a move
+// is played it earns 2 points for a win and 1 for a draw, as Polyglot books
+// weigh moves, scaled by the player's rating relative to 2000.
+pub struct BookBuilder {
+    config: BookgenConfig,
+    stats: HashMap<(u64, u16), MoveStats>,
+    games: usize,
+}
+
+impl BookBuilder {
+    pub fn new(config: BookgenConfig) -> Self {
+        BookBuilder { config, stats: HashMap::new(), games: 0 }
+    }
+
+    pub fn games(&self) -> usize {
+        self.games
+    }
+
+    // Returns false for games without a result, which are skipped.
+    pub fn add_game(&mut self, game: &Game) -> Result<bool, String> {
+        let white_points = match game.result.as_str() {
+            "1-0" => 2.0,
+            "0-1" => 0.0,
+            "1/2-1/2" => 1.0,
+            _ => return Ok(false),
+        };
+        let elo = |tag: &str| game.tag(tag).and_then(|e| e.parse::<u32>().ok()).unwrap_or(UNRATED);
+        let ratings = [elo("WhiteElo"), elo("BlackElo")];
+        let mut board = game.start_board()?;
+        for &mv in game.moves.iter().take(self.config.max_ply) {
+            let mover = board.side_to_move();
+            let rating = ratings[mover.to_index()];
+            if rating >= self.config.min_elo {
+                let points = if mover == Color::White { white_points } else { 2.0 - white_points };
+                let stats = self.stats.entry((polyglot_key(&board), encode_move(&board, mv))).or_default();
+                stats.games += 1;
+                stats.score += points * rating as f64 / UNRATED as f64;
+            }
+            board = board.make_move_new(mv);
+        }
+        self.games += 1;
+        Ok(true)
+    }
+
+    pub fn build(&self) -> Book {
+        let mut by_key: HashMap<u64, Vec<(u16, MoveStats)>> = HashMap::new();
+        for (&(key, mv), &stats) in &self.stats {
+            if stats.games >= self.config.min_count {
+                by_key.entry(key).or_default().push((mv, stats));
+            }
+        }
+        let mut entries = Vec::new();
+        for (key, moves) in by_key {
+            // Weights are 16 bits, so busy positions are scaled down as a whole.
+            let max = moves.iter().map(|(_, s)| s.score).fold(0.0, f64::max);
+            let scale = if max > u16::MAX as f64 { u16::MAX as f64 / max } else { 1.0 };
+            for (mv, stats) in moves {
+                let weight = (stats.score * scale).round() as u16;
+                if weight > 0 {
+                    entries.push(BookEntry { key, mv, weight, learn: 0 });
+                }
+            }
+        }
+        Book::from_entries(entries)
+    }
+}
+
+// Combines books by adding up the weights of moves they share.
+pub fn merge(books: &[Book]) -> Book {
+    let mut weights: HashMap<(u64, u16), u16> = HashMap::new();
+    for book in books {
+        for e in book.entries() {
+            let weight = weights.entry((e.key, e.mv)).or_default();
+            *weight = weight.saturating_add(e.weight);
+        }
+    }
+    let entries = weights.into_iter().map(|((key, mv), weight)| BookEntry { key, mv, weight, learn: 0 }).collect();
+    Book::from_entries(entries)
+}
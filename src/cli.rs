@@ -0,0 +1,381 @@
+use crate::datagen::Rng;
+use crate::epd::{SuiteConfig, SuiteSummary};
+use crate::game::GameState;
+use crate::perft::{self, PerftOptions};
+use crate::{bench, epd, format_score, mate_in, pgn, search, uci, SearchInfo, SearchResult};
+use chess::{Board, ChessMove, Color, MoveGen};
+use std::time::Instant;
+
+// Command-line subcommands; without arguments the engine runs the UCI loop
+// instead. Every subcommand prints plain text, or one JSON document with
+// --json.
+pub const USAGE: &str = "usage: axelrot                  run the UCI (or XBoard) engine
+       axelrot bench [<depth>] [--json]
+       axelrot perft <fen|startpos> <depth> [--divide] [--threads <n>] [--hash <mb>] [--json]
+       axelrot analyze <fen|startpos> [--depth <n>] [--movetime <ms>] [--multipv <n>] [--json]
+       axelrot epd <file> [--depth <n>] [--movetime <ms>] [--nodes <n>] [--threads <n>] [--json]
+       axelrot selfplay [--fen <fen>] [--games <n>] [--random-plies <n>] [--max-plies <n>]
+                        [--depth <n>] [--movetime <ms>] [--json]";
+
+// Depth used when neither --depth nor --movetime is given.
+pub const DEFAULT_DEPTH: i32 = 5;
+const MAX_DEPTH: i32 = 64;
+
+struct Args {
+    positional: Vec<String>,
+    json: bool,
+    divide: bool,
+    depth: Option<i32>,
+    movetime: Option<u64>,
+    nodes: Option<u64>,
+    multipv: usize,
+    threads: usize,
+    hash_mb: usize,
+    games: usize,
+    random_plies: usize,
+    max_plies: usize,
+    fen: Option<String>,
+}
+
+impl Args {
+    fn parse(args: &[String]) -> Result<Self, String> {
+        let mut parsed = Args {
+            positional: Vec::new(),
+            json: false,
+            divide: false,
+            depth: None,
+            movetime: None,
+            nodes: None,
+            multipv: 1,
+            threads: 1,
+            hash_mb: 0,
+            games: 1,
+            random_plies: 0,
+            max_plies: 400,
+            fen: None,
+        };
+        let mut args = args.iter();
+        while let Some(arg) = args.next() {
+            let mut value = || args.next().cloned().ok_or_else(|| format!("missing value for {}", arg));
+            let number = |v: String| v.parse::<u64>().map_err(|_| format!("invalid value {} for {}", v, arg));
+            match arg.as_str() {
+                "--json" => parsed.json = true,
+                "--divide" => parsed.divide = true,
+                "--depth" => parsed.depth = Some(number(value()?)?.clamp(1, MAX_DEPTH as u64) as i32),
+                "--movetime" => parsed.movetime = Some(number(value()?)?),
+                "--nodes" => parsed.nodes = Some(number(value()?)?.max(1)),
+                "--multipv" => parsed.multipv = number(value()?)?.max(1) as usize,
+                "--threads" => parsed.threads = number(value()?)?.max(1) as usize,
+                "--hash" => parsed.hash_mb = number(value()?)? as usize,
+                "--games" => parsed.games = number(value()?)? as usize,
+                "--random-plies" => parsed.random_plies = number(value()?)? as usize,
+                "--max-plies" => parsed.max_plies = number(value()?)? as usize,
+                "--fen" => parsed.fen = Some(value()?),
+                flag if flag.starts_with("--") => return Err(format!("unknown option {}", flag)),
+                _ => parsed.positional.push(arg.clone()),
+            }
+        }
+        Ok(parsed)
+    }
+
+    fn limits(&self) -> Limits {
+        let bounded = self.movetime.is_some() || self.nodes.is_some();
+        let depth = self.depth.unwrap_or(if bounded { MAX_DEPTH } else { DEFAULT_DEPTH });
+        Limits { depth, movetime: self.movetime, nodes: self.nodes }
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct Limits {
+    pub depth: i32,
+    pub movetime: Option<u64>,
+    pub nodes: Option<u64>,
+}
+
+impl Limits {
+    pub fn info(&self) -> SearchInfo {
+        let mut info = SearchInfo::new(self.movetime.unwrap_or(u64::MAX));
+        info.node_limit = self.nodes;
+        info
+    }
+}
+
+// Runs a subcommand; errors are returned ready to print.
+pub fn run(args: &[String]) -> Result<(), String> {
+    let Some((command, rest)) = args.split_first() else { return Err(USAGE.to_string()) };
+    let result = Args::parse(rest).and_then(|args| match command.as_str() {
+        "bench" => run_bench(&args),
+        "perft" => run_perft(&args),
+        "analyze" => run_analyze(&args),
+        "epd" => run_epd(&args),
+        "selfplay" => run_selfplay(&args),
+        "help" | "--help" | "-h" => {
+            println!("{}", USAGE);
+            Ok(())
+        }
+        _ => Err(USAGE.to_string()),
+    });
+    result.map_err(|e| if e.starts_with("usage:") { e } else { format!("error: {}\n{}", e, USAGE) })
+}
+
+fn board_arg(text: &str) -> Result<Board, String> {
+    if text == "startpos" {
+        return Ok(Board::default());
+    }
+    uci::parse_fen(text).map(|(board, _)| board)
+}
+
+fn json_str(text: &str) -> String {
+    let mut out = String::from("\"");
+    for c in text.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            c if (c as u32) < 0x20 => out.push_str(&format!("\\u{:04x}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+fn json_list(items: impl IntoIterator<Item = String>) -> String {
+    format!("[{}]", items.into_iter().collect::<Vec<_>>().join(","))
+}
+
+// {"cp": N} or {"mate": N}, matching the UCI score.
+fn json_score(score: i32) -> String {
+    match mate_in(score) {
+        Some(moves) => format!("{{\"mate\":{}}}", moves),
+        None => format!("{{\"cp\":{}}}", score),
+    }
+}
+
+fn json_moves(moves: &[ChessMove]) -> String {
+    json_list(moves.iter().map(|m| json_str(&m.to_string())))
+}
+
+// Moves in SAN with move numbers, as in PGN movetext.
+pub fn san_line(board: &Board, moves: &[ChessMove]) -> String {
+    let mut board = *board;
+    let mut text = Vec::new();
+    for (i, &mv) in moves.iter().enumerate() {
+        let number = (i == 0 || board.side_to_move() == Color::White).then(|| match board.side_to_move() {
+            Color::White => format!("{}. ", text.len() / 2 + 1),
+            Color::Black => format!("{}... ", text.len() / 2 + 1),
+        });
+        text.push(format!("{}{}", number.unwrap_or_default(), pgn::to_san(&board, mv)));
+        board = board.make_move_new(mv);
+    }
+    text.join(" ")
+}
+
+// One search with `lines` MultiPV lines; they end up in `SearchResult::lines`.
+pub fn analyze(board: &Board, limits: Limits, lines: usize) -> SearchResult {
+    let mut info = limits.info();
+    info.config.multi_pv = lines.max(1);
+    search(board, limits.depth, &mut info)
+}
+
+fn run_bench(args: &Args) -> Result<(), String> {
+    let depth = match args.positional.first() {
+        Some(d) => d.parse().map_err(|_| format!("invalid bench depth {}", d))?,
+        None => args.depth.unwrap_or(bench::BENCH_DEPTH),
+    };
+    if !args.json {
+        bench::print(depth);
+        return Ok(());
+    }
+    let mut positions = Vec::new();
+    let result = bench::run(depth, |_, fen, r| {
+        let best = r.best_move.map_or("0000".to_string(), |m| m.to_string());
+        positions.push(format!("{{\"fen\":{},\"nodes\":{},\"bestmove\":{}}}", json_str(fen), r.nodes, json_str(&best)));
+    });
+    println!(
+        "{{\"depth\":{},\"nodes\":{},\"time_ms\":{},\"nps\":{},\"positions\":{}}}",
+        depth,
+        result.nodes,
+        result.elapsed_ms,
+        result.nps(),
+        json_list(positions)
+    );
+    Ok(())
+}
+
+fn run_perft(args: &Args) -> Result<(), String> {
+    let [fen, depth] = args.positional.as_slice() else { return Err(USAGE.to_string()) };
+    let board = board_arg(fen)?;
+    let depth: u32 = depth.parse().map_err(|_| format!("invalid perft depth {}", depth))?;
+    let options = PerftOptions { hash_mb: args.hash_mb, threads: args.threads };
+    let start = Instant::now();
+    let split = perft::divide(&board, depth, options);
+    let nodes: u64 = if depth == 0 { 1 } else { split.iter().map(|(_, n)| n).sum() };
+    let elapsed = start.elapsed().as_millis() as u64;
+    let nps = nodes * 1000 / elapsed.max(1);
+    if args.json {
+        let divide = split.iter().map(|(mv, n)| format!("{}:{}", json_str(&mv.to_string()), n));
+        let divide = format!("{{{}}}", divide.collect::<Vec<_>>().join(","));
+        println!(
+            "{{\"fen\":{},\"depth\":{},\"nodes\":{},\"time_ms\":{},\"nps\":{},\"divide\":{}}}",
+            json_str(&board.to_string()),
+            depth,
+            nodes,
+            elapsed,
+            nps,
+            divide
+        );
+    } else {
+        if args.divide {
+            for (mv, n) in &split {
+                println!("{}: {}", mv, n);
+            }
+            println!();
+        }
+        println!("Nodes: {}", nodes);
+        println!("Time: {} ms", elapsed);
+        println!("NPS: {}", nps);
+    }
+    Ok(())
+}
+
+fn run_analyze(args: &Args) -> Result<(), String> {
+    let [fen] = args.positional.as_slice() else { return Err(USAGE.to_string()) };
+    let board = board_arg(fen)?;
+    let r = analyze(&board, args.limits(), args.multipv);
+    if args.json {
+        let lines = r.lines.iter().enumerate().map(|(i, (score, pv))| {
+            format!(
+                "{{\"multipv\":{},\"depth\":{},\"score\":{},\"nodes\":{},\"pv\":{}}}",
+                i + 1,
+                r.depth,
+                json_score(*score),
+                r.nodes,
+                json_moves(pv)
+            )
+        });
+        println!("{{\"fen\":{},\"lines\":{}}}", json_str(&board.to_string()), json_list(lines));
+    } else {
+        for (i, (score, pv)) in r.lines.iter().enumerate() {
+            println!("{}. {} depth {} nodes {}  {}", i + 1, format_score(*score), r.depth, r.nodes, san_line(&board, pv));
+        }
+        if r.lines.is_empty() {
+            println!("no legal moves");
+        }
+    }
+    Ok(())
+}
+
+fn run_epd(args: &Args) -> Result<(), String> {
+    let [path] = args.positional.as_slice() else { return Err(USAGE.to_string()) };
+    let text = std::fs::read_to_string(path).map_err(|e| format!("cannot read {}: {}", path, e))?;
+    let records = epd::parse(&text).into_iter().collect::<Result<Vec<_>, _>>().map_err(|e| format!("{}: {}", path, e))?;
+    let limits = args.limits();
+    let config = SuiteConfig { depth: limits.depth, movetime: limits.movetime, nodes: limits.nodes, threads: args.threads };
+    let results = epd::run_suite(&records, &config, |r| {
+        if !args.json {
+            println!("{}", epd_line(r));
+        }
+    });
+    let summary = SuiteSummary::new(&results);
+    if args.json {
+        let positions = results.iter().map(|r| {
+            format!(
+                "{{\"id\":{},\"fen\":{},\"bestmove\":{},\"score\":{},\"depth\":{},\"nodes\":{},\"time_ms\":{},\"solved\":{},\"solve_time_ms\":{},\"points\":{},\"max_points\":{}}}",
+                json_str(&r.id),
+                json_str(&r.fen),
+                json_str(&r.best_move),
+                json_score(r.score),
+                r.depth,
+                r.nodes,
+                r.time_ms,
+                r.solved.map_or("null".to_string(), |s| s.to_string()),
+                r.solve_time_ms.map_or("null".to_string(), |t| t.to_string()),
+                r.points,
+                r.max_points
+            )
+        });
+        println!(
+            "{{\"positions\":{},\"checked\":{},\"solved\":{},\"points\":{},\"max_points\":{},\"nodes\":{},\"time_ms\":{},\"results\":{}}}",
+            summary.positions,
+            summary.checked,
+            summary.solved,
+            summary.points,
+            summary.max_points,
+            summary.nodes,
+            summary.time_ms,
+            json_list(positions)
+        );
+    } else {
+        println!("Solved {}/{}", summary.solved, summary.checked);
+        if summary.max_points > 0 {
+            println!("Points {}/{}", summary.points, summary.max_points);
+        }
+        println!("Nodes {} time {} ms", summary.nodes, summary.time_ms);
+    }
+    Ok(())
+}
+
+fn epd_line(r: &epd::EpdResult) -> String {
+    let mut line = format!("{}: {}", r.id, r.best_move);
+    match (r.solved, r.solve_time_ms) {
+        (Some(true), Some(ms)) => line += &format!(" solved in {} ms", ms),
+        (Some(true), None) => line += " solved",
+        (Some(false), _) => line += " failed",
+        (None, _) => {}
+    }
+    if r.max_points > 0 {
+        line += &format!(" {}/{} points", r.points, r.max_points);
+    }
+    line + &format!(" ({} depth {})", format_score(r.score), r.depth)
+}
+
+fn run_selfplay(args: &Args) -> Result<(), String> {
+    let start = match &args.fen {
+        Some(fen) => board_arg(fen)?,
+        None => Board::default(),
+    };
+    let limits = args.limits();
+    let mut games = Vec::new();
+    for index in 0..args.games {
+        let mut game = GameState::new(start);
+        // Random opening plies so repeated games differ.
+        let mut rng = Rng::new(index as u64 + 1);
+        let end = loop {
+            if let Some(end) = game.end() {
+                break Some(end);
+            }
+            if game.moves.len() >= args.max_plies {
+                break None;
+            }
+            let mv = if game.moves.len() < args.random_plies {
+                let moves: Vec<ChessMove> = MoveGen::new_legal(&game.board).collect();
+                moves[rng.below(moves.len())]
+            } else {
+                let mut info = limits.info();
+                match search(&game.board, limits.depth, &mut info).best_move {
+                    Some(mv) => mv,
+                    None => break None,
+                }
+            };
+            game.play(mv);
+        };
+        let (result, reason) = end.map_or(("*", "max plies"), |e| (e.result, e.reason));
+        if args.json {
+            games.push(format!(
+                "{{\"result\":{},\"reason\":{},\"plies\":{},\"moves\":{}}}",
+                json_str(result),
+                json_str(reason),
+                game.moves.len(),
+                json_moves(&game.moves)
+            ));
+        } else {
+            println!("Game {}: {} ({}, {} plies)", index + 1, result, reason, game.moves.len());
+            println!("{} {}", san_line(&start, &game.moves), result);
+        }
+    }
+    if args.json {
+        println!("{{\"fen\":{},\"games\":{}}}", json_str(&start.to_string()), json_list(games));
+    }
+    Ok(())
+}
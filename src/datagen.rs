@@ -0,0 +1,384 @@
+use crate::endgame::insufficient_material;
+use crate::{search, SearchInfo, MATE};
+use chess::{Board, BoardStatus, CastleRights, ChessMove, Color, File, MoveGen, Piece, Rank, Square, ALL_PIECES};
+use std::collections::HashSet;
+use std::fs::{self, File as FsFile, OpenOptions};
+use std::io::{BufRead, BufReader, Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::thread;
+
+pub const RECORD_SIZE: usize = 32;
+
+// SplitMix64: tiny, seedable and identical on every platform, which is all the
+// opening randomisation needs.
+pub struct Rng(u64);
+
+impl Rng {
+    pub fn new(seed: u64) -> Self {
+        Rng(seed)
+    }
+    pub fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+    pub fn below(&mut self, n: usize) -> usize {
+        (self.next_u64() % n as u64) as usize
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Record {
+    pub board: Board,
+    // Search score in centipawns from White's point of view.
+    pub score: i16,
+    // Game result from White's point of view: 0 loss, 1 draw, 2 win.
+    pub result: u8,
+    pub fullmove: u16,
+}
+
+impl Record {
+    pub fn result_f64(&self) -> f64 {
+        self.result as f64 / 2.0
+    }
+
+    pub fn fen(&self) -> String {
+        let fen = self.board.to_string();
+        let fields: Vec<&str> = fen.split_whitespace().collect();
+        format!("{} 0 {}", fields[..4].join(" "), self.fullmove)
+    }
+
+    pub fn to_text(&self) -> String {
+        format!("{} | {} | {:.1}", self.fen(), self.score, self.result_f64())
+    }
+
+    // 32-byte layout: occupancy (8), one nibble per occupied square in
+    // square order (16), flags (1: side to move, castling rights), en-passant
+    // square or 64 (1), score (2), result (1), padding (1), fullmove (2).
+    pub fn to_bytes(&self) -> [u8; RECORD_SIZE] {
+        let mut out = [0u8; RECORD_SIZE];
+        let occupied = *self.board.combined();
+        out[..8].copy_from_slice(&occupied.0.to_le_bytes());
+        for (i, sq) in occupied.into_iter().enumerate() {
+            let piece = self.board.piece_on(sq).unwrap().to_index() as u8;
+            let color = if self.board.color_on(sq) == Some(Color::White) { 0 } else { 8 };
+            out[8 + i / 2] |= (piece | color) << ((i % 2) * 4);
+        }
+        let castle = |c: Color| self.board.castle_rights(c).to_index() as u8;
+        out[24] = (self.board.side_to_move() == Color::Black) as u8
+            | castle(Color::White) << 1
+            | castle(Color::Black) << 3;
+        out[25] = self.board.en_passant().map_or(64, |sq| sq.to_index() as u8);
+        out[26..28].copy_from_slice(&self.score.to_le_bytes());
+        out[28] = self.result;
+        out[30..32].copy_from_slice(&self.fullmove.to_le_bytes());
+        out
+    }
+
+    pub fn from_bytes(bytes: &[u8; RECORD_SIZE]) -> Result<Self, String> {
+        let occupied = u64::from_le_bytes(bytes[..8].try_into().unwrap());
+        let mut squares = [None; 64];
+        let mut index = 0;
+        for (sq_index, slot) in squares.iter_mut().enumerate() {
+            if occupied & (1u64 << sq_index) == 0 {
+                continue;
+            }
+            if index >= 32 {
+                return Err("too many pieces in record".to_string());
+            }
+            let nibble = (bytes[8 + index / 2] >> ((index % 2) * 4)) & 0xf;
+            let piece = *ALL_PIECES.get((nibble & 7) as usize).ok_or("invalid piece in record")?;
+            let color = if nibble & 8 == 0 { Color::White } else { Color::Black };
+            *slot = Some((piece, color));
+            index += 1;
+        }
+        let mut fen = String::new();
+        for rank in (0..8).rev() {
+            let mut empty = 0;
+            for file in 0..8 {
+                match squares[rank * 8 + file] {
+                    None => empty += 1,
+                    Some((piece, color)) => {
+                        if empty > 0 {
+                            fen.push_str(&empty.to_string());
+                            empty = 0;
+                        }
+                        fen.push_str(&piece.to_string(color));
+                    }
+                }
+            }
+            if empty > 0 {
+                fen.push_str(&empty.to_string());
+            }
+            if rank > 0 {
+                fen.push('/');
+            }
+        }
+        let flags = bytes[24];
+        let stm = if flags & 1 == 0 { Color::White } else { Color::Black };
+        let rights = |bits: u8, color: Color| CastleRights::from_index((bits & 3) as usize).to_string(color);
+        let mut castling = rights(flags >> 1, Color::White) + &rights(flags >> 3, Color::Black);
+        if castling.is_empty() {
+            castling.push('-');
+        }
+        // The board stores the square of the pawn that can be taken; FEN wants
+        // the square behind it.
+        let ep = if bytes[25] < 64 {
+            square_from_index(bytes[25]).forward(stm).map_or("-".to_string(), |sq| sq.to_string())
+        } else {
+            "-".to_string()
+        };
+        let fullmove = u16::from_le_bytes([bytes[30], bytes[31]]);
+        let board = format!("{} {} {} {} 0 {}", fen, if stm == Color::White { "w" } else { "b" }, castling, ep, fullmove.max(1))
+            .parse::<Board>()
+            .map_err(|e| format!("invalid record position: {}", e))?;
+        Ok(Record {
+            board,
+            score: i16::from_le_bytes([bytes[26], bytes[27]]),
+            result: bytes[28],
+            fullmove,
+        })
+    }
+}
+
+fn square_from_index(index: u8) -> Square {
+    Square::make_square(Rank::from_index(index as usize / 8), File::from_index(index as usize % 8))
+}
+
+pub fn read_records(path: impl AsRef<Path>) -> Result<Vec<Record>, String> {
+    let path = path.as_ref();
+    let mut bytes = Vec::new();
+    FsFile::open(path)
+        .and_then(|mut f| f.read_to_end(&mut bytes))
+        .map_err(|e| format!("cannot read {}: {}", path.display(), e))?;
+    if bytes.len() % RECORD_SIZE != 0 {
+        return Err(format!("{}: truncated record file", path.display()));
+    }
+    bytes
+        .chunks_exact(RECORD_SIZE)
+        .map(|chunk| Record::from_bytes(chunk.try_into().unwrap()))
+        .collect()
+}
+
+#[derive(Clone, Debug)]
+pub struct DatagenConfig {
+    pub games: usize,
+    pub threads: usize,
+    pub nodes: u64,
+    pub random_plies: usize,
+    pub max_plies: usize,
+    pub seed: u64,
+    pub output: PathBuf,
+}
+
+impl Default for DatagenConfig {
+    fn default() -> Self {
+        DatagenConfig {
+            games: 1000,
+            threads: 1,
+            nodes: 5000,
+            random_plies: 8,
+            max_plies: 400,
+            seed: 1,
+            output: PathBuf::from("datagen"),
+        }
+    }
+}
+
+impl DatagenConfig {
+    pub fn binary_path(&self) -> PathBuf {
+        self.output.with_extension("bin")
+    }
+    pub fn text_path(&self) -> PathBuf {
+        self.output.with_extension("txt")
+    }
+    pub fn progress_path(&self) -> PathBuf {
+        self.output.with_extension("done")
+    }
+}
+
+fn is_noisy(board: &Board, mv: ChessMove) -> bool {
+    board.piece_on(mv.get_dest()).is_some()
+        || mv.get_promotion().is_some()
+        || (board.piece_on(mv.get_source()) == Some(Piece::Pawn) && Some(mv.get_dest()) == board.en_passant().and_then(|sq| sq.forward(board.side_to_move())))
+}
+
+fn random_opening(rng: &mut Rng, plies: usize) -> Option<(Board, Vec<u64>)> {
+    let mut board = Board::default();
+    let mut hashes = vec![board.get_hash()];
+    for _ in 0..plies {
+        let moves: Vec<ChessMove> = MoveGen::new_legal(&board).collect();
+        if moves.is_empty() {
+            return None;
+        }
+        board = board.make_move_new(moves[rng.below(moves.len())]);
+        hashes.push(board.get_hash());
+    }
+    (board.status() == BoardStatus::Ongoing).then_some((board, hashes))
+}
+
+// Plays one self-play game. Each game gets its own seed derived from the base
+// seed and its index, so the output does not depend on thread scheduling.
+// Games that reach `max_plies` without a result are dropped rather than
+// labelled as draws.
+pub fn play_game(config: &DatagenConfig, game_index: usize) -> Option<Vec<Record>> {
+    let mut rng = Rng::new(config.seed ^ (game_index as u64).wrapping_mul(0x2545_f491_4f6c_dd1d));
+    let (mut board, mut hashes) = loop {
+        if let Some(opening) = random_opening(&mut rng, config.random_plies) {
+            break opening;
+        }
+    };
+
+    let mut positions: Vec<(Board, i16, u16)> = Vec::new();
+    let mut fullmove = 1 + config.random_plies as u16 / 2;
+    let mut halfmove_clock = 0;
+    let mut result: Option<u8> = None;
+    for _ in 0..config.max_plies {
+        match board.status() {
+            BoardStatus::Checkmate => {
+                result = Some(if board.side_to_move() == Color::White { 0 } else { 2 });
+                break;
+            }
+            BoardStatus::Stalemate => {
+                result = Some(1);
+                break;
+            }
+            BoardStatus::Ongoing => {}
+        }
+        let repetitions = hashes.iter().filter(|&&h| h == board.get_hash()).count();
+        if halfmove_clock >= 100 || repetitions >= 3 || insufficient_material(&board) {
+            result = Some(1);
+            break;
+        }
+
+        // A budget too small to finish depth 1 gives no move; search again
+        // with more nodes rather than cutting the game short.
+        let mut nodes = config.nodes.max(1);
+        let (found, mv) = loop {
+            let mut info = SearchInfo::new(u64::MAX);
+            info.node_limit = Some(nodes);
+            let found = search(&board, 64, &mut info);
+            if let Some(mv) = found.best_move {
+                break (found, mv);
+            }
+            nodes *= 2;
+        };
+        let white_score = if board.side_to_move() == Color::White { found.score } else { -found.score };
+
+        // Decisive scores end the game early; the search is already sure.
+        if found.score.abs() >= MATE - 100 {
+            result = Some(if white_score > 0 { 2 } else { 0 });
+            break;
+        }
+        if board.checkers().popcnt() == 0 && !is_noisy(&board, mv) {
+            positions.push((board, white_score.clamp(-30000, 30000) as i16, fullmove));
+        }
+
+        let reset = board.piece_on(mv.get_source()) == Some(Piece::Pawn) || board.piece_on(mv.get_dest()).is_some();
+        halfmove_clock = if reset { 0 } else { halfmove_clock + 1 };
+        if board.side_to_move() == Color::Black {
+            fullmove += 1;
+        }
+        board = board.make_move_new(mv);
+        hashes.push(board.get_hash());
+    }
+
+    let result = result?;
+    Some(
+        positions
+            .into_iter()
+            .map(|(board, score, fullmove)| Record { board, score, result, fullmove })
+            .collect(),
+    )
+}
+
+fn completed_games(path: &Path) -> HashSet<usize> {
+    match FsFile::open(path) {
+        Ok(f) => BufReader::new(f)
+            .lines()
+            .map_while(Result::ok)
+            .filter_map(|l| l.trim().parse().ok())
+            .collect(),
+        Err(_) => HashSet::new(),
+    }
+}
+
+struct Outputs {
+    binary: FsFile,
+    text: FsFile,
+    progress: FsFile,
+}
+
+// Runs (or resumes) a datagen session. Games already listed in the progress
+// file are skipped, so an interrupted run can simply be started again with
+// the same configuration. Returns the number of positions written.
+pub fn run(config: &DatagenConfig, report: impl Fn(usize, usize, usize) + Sync) -> Result<usize, String> {
+    let open = |path: PathBuf| {
+        if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+            fs::create_dir_all(parent).map_err(|e| format!("cannot create {}: {}", parent.display(), e))?;
+        }
+        OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&path)
+            .map_err(|e| format!("cannot open {}: {}", path.display(), e))
+    };
+    let done = completed_games(&config.progress_path());
+    let outputs = Mutex::new((
+        Outputs {
+            binary: open(config.binary_path())?,
+            text: open(config.text_path())?,
+            progress: open(config.progress_path())?,
+        },
+        done.len(),
+        0usize,
+    ));
+    let next_game = AtomicUsize::new(0);
+    let error = Mutex::new(None);
+
+    thread::scope(|s| {
+        for _ in 0..config.threads.max(1) {
+            s.spawn(|| loop {
+                let game = next_game.fetch_add(1, Ordering::Relaxed);
+                if game >= config.games || error.lock().unwrap().is_some() {
+                    break;
+                }
+                if done.contains(&game) {
+                    continue;
+                }
+                let records = play_game(config, game).unwrap_or_default();
+                let mut guard = outputs.lock().unwrap();
+                let (out, finished, written) = &mut *guard;
+                let mut bytes = Vec::with_capacity(records.len() * RECORD_SIZE);
+                let mut text = String::new();
+                for record in &records {
+                    bytes.extend_from_slice(&record.to_bytes());
+                    text.push_str(&record.to_text());
+                    text.push('\n');
+                }
+                let written_ok = out
+                    .binary
+                    .write_all(&bytes)
+                    .and_then(|_| out.text.write_all(text.as_bytes()))
+                    .and_then(|_| writeln!(out.progress, "{}", game))
+                    .and_then(|_| out.binary.flush());
+                if let Err(e) = written_ok {
+                    *error.lock().unwrap() = Some(format!("write failed: {}", e));
+                    break;
+                }
+                *finished += 1;
+                *written += records.len();
+                report(*finished, config.games, *written);
+            });
+        }
+    });
+
+    if let Some(e) = error.into_inner().unwrap() {
+        return Err(e);
+    }
+    Ok(outputs.into_inner().unwrap().2)
+}
@@ -0,0 +1,728 @@
+use crate::tablebase::{Dtm, Tablebase, Wdl};
+use chess::{
+    get_bishop_moves, get_king_moves, get_knight_moves, get_rook_moves, BitBoard, Board, BoardBuilder, CastleRights,
+    Color, MoveGen, Piece, Square, ALL_SQUARES, EMPTY,
+};
+use std::collections::HashMap;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, LazyLock, RwLock};
+
+// Distance-to-mate tables built by retrograde analysis on top of the move
+// generator. Each table covers one material signature with the stronger side
+// as White; every position is stored as a byte holding the mate distance in
+// plies plus one (0 for draws and illegal positions).
+//
+// En passant captures are not part of the retrograde graph: a double push is
+// treated like any other pawn move. Probing a position that has an en passant
+// square searches one ply so the capture itself is still scored correctly.
+//
+// Generation keeps an 8-byte node for every index, 16 or 32 king slots times
+// 64 squares per other piece, so five pieces would need several gigabytes.
+pub const MAX_PIECES: usize = 4;
+const MAX_PLY: usize = 254;
+const MAGIC: &[u8; 4] = b"AXTB";
+const VERSION: u8 = 1;
+const EXTENSION: &str = "axtb";
+
+const PIECE_ORDER: [Piece; 5] = [Piece::Queen, Piece::Rook, Piece::Bishop, Piece::Knight, Piece::Pawn];
+
+fn sq(i: usize) -> Square {
+    ALL_SQUARES[i]
+}
+
+fn bb(i: usize) -> BitBoard {
+    BitBoard::from_square(sq(i))
+}
+
+fn order(piece: Piece) -> usize {
+    PIECE_ORDER.iter().position(|&p| p == piece).unwrap_or(PIECE_ORDER.len())
+}
+
+fn value(piece: Piece) -> u32 {
+    match piece {
+        Piece::Queen => 9,
+        Piece::Rook => 5,
+        Piece::Bishop | Piece::Knight => 3,
+        _ => 1,
+    }
+}
+
+// Non-king pieces of each side, strongest first.
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+pub struct Material {
+    pieces: [Vec<Piece>; 2],
+}
+
+impl Material {
+    // Parses names like "KQvK" or "KRvKP".
+    pub fn parse(name: &str) -> Result<Material, String> {
+        let invalid = || format!("invalid material {}", name);
+        let (white, black) = name.split_once(['v', 'V']).ok_or_else(invalid)?;
+        let side = |s: &str| -> Result<Vec<Piece>, String> {
+            let rest = s.strip_prefix(['K', 'k']).ok_or_else(invalid)?;
+            let mut pieces = rest
+                .chars()
+                .map(|c| match c.to_ascii_uppercase() {
+                    'Q' => Ok(Piece::Queen),
+                    'R' => Ok(Piece::Rook),
+                    'B' => Ok(Piece::Bishop),
+                    'N' => Ok(Piece::Knight),
+                    'P' => Ok(Piece::Pawn),
+                    _ => Err(invalid()),
+                })
+                .collect::<Result<Vec<_>, _>>()?;
+            pieces.sort_by_key(|&p| order(p));
+            Ok(pieces)
+        };
+        let material = Material { pieces: [side(white)?, side(black)?] };
+        if material.piece_count() > MAX_PIECES {
+            return Err(format!("{} has more than {} pieces", name, MAX_PIECES));
+        }
+        Ok(material)
+    }
+
+    pub fn from_board(board: &Board) -> Material {
+        let side = |color: Color| {
+            PIECE_ORDER
+                .iter()
+                .flat_map(|&p| {
+                    let count = (board.pieces(p) & board.color_combined(color)).popcnt() as usize;
+                    std::iter::repeat_n(p, count)
+                })
+                .collect()
+        };
+        Material { pieces: [side(Color::White), side(Color::Black)] }
+    }
+
+    pub fn piece_count(&self) -> usize {
+        2 + self.pieces[0].len() + self.pieces[1].len()
+    }
+
+    pub fn has_pawns(&self) -> bool {
+        self.pieces.iter().flatten().any(|&p| p == Piece::Pawn)
+    }
+
+    pub fn flipped(&self) -> Material {
+        Material { pieces: [self.pieces[1].clone(), self.pieces[0].clone()] }
+    }
+
+    // Tables are stored with the stronger side as White.
+    pub fn is_canonical(&self) -> bool {
+        let key = |pieces: &[Piece]| {
+            let strength: Vec<usize> = pieces.iter().map(|&p| PIECE_ORDER.len() - order(p)).collect();
+            (pieces.iter().map(|&p| value(p)).sum::<u32>(), pieces.len(), strength)
+        };
+        key(&self.pieces[0]) >= key(&self.pieces[1])
+    }
+
+    pub fn canonical(&self) -> Material {
+        if self.is_canonical() { self.clone() } else { self.flipped() }
+    }
+
+    // Canonical materials reachable by one capture, promotion or both, which
+    // have to be generated before this one.
+    pub fn successors(&self) -> Vec<Material> {
+        let mut out = Vec::new();
+        let without = |m: &Material, side: usize, i: usize| {
+            let mut m = m.clone();
+            m.pieces[side].remove(i);
+            m
+        };
+        for side in 0..2 {
+            for i in 0..self.pieces[1 - side].len() {
+                out.push(without(self, 1 - side, i));
+            }
+            for (i, &piece) in self.pieces[side].iter().enumerate() {
+                if piece != Piece::Pawn {
+                    continue;
+                }
+                for promotion in [Piece::Queen, Piece::Rook, Piece::Bishop, Piece::Knight] {
+                    let mut promoted = self.clone();
+                    promoted.pieces[side][i] = promotion;
+                    promoted.pieces[side].sort_by_key(|&p| order(p));
+                    for j in 0..promoted.pieces[1 - side].len() {
+                        out.push(without(&promoted, 1 - side, j));
+                    }
+                    out.push(promoted);
+                }
+            }
+        }
+        let mut unique: Vec<Material> = Vec::new();
+        for m in out.into_iter().map(|m| m.canonical()) {
+            if m.piece_count() > 2 && !unique.contains(&m) {
+                unique.push(m);
+            }
+        }
+        unique
+    }
+}
+
+impl fmt::Display for Material {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let side = |pieces: &[Piece]| {
+            let letters: String = pieces.iter().map(|p| p.to_string(Color::White)).collect();
+            format!("K{}", letters)
+        };
+        write!(f, "{}v{}", side(&self.pieces[0]), side(&self.pieces[1]))
+    }
+}
+
+fn dtm_from_value(value: u8) -> Dtm {
+    match value {
+        0 => Dtm::Draw,
+        v if v % 2 == 0 => Dtm::Win(v as u32 - 1),
+        v => Dtm::Loss(v as u32 - 1),
+    }
+}
+
+// The result for the side to move, given the result after one of its moves.
+fn parent_dtm(child: Dtm) -> Dtm {
+    match child {
+        Dtm::Win(plies) => Dtm::Loss(plies + 1),
+        Dtm::Loss(plies) => Dtm::Win(plies + 1),
+        Dtm::Draw => Dtm::Draw,
+    }
+}
+
+fn dtm_rank(dtm: Dtm) -> i64 {
+    match dtm {
+        Dtm::Win(plies) => 1000 - plies as i64,
+        Dtm::Loss(plies) => -1000 + plies as i64,
+        Dtm::Draw => 0,
+    }
+}
+
+// Squares in table orientation: White is the stronger side and the white king
+// is normalised into a1-d4 (a1-d8 with pawns). `squares` follows the order of
+// `Layout::pieces`.
+#[derive(Clone, Copy)]
+struct Position {
+    white_to_move: bool,
+    kings: [usize; 2],
+    squares: [usize; MAX_PIECES - 2],
+}
+
+struct Layout {
+    material: Material,
+    pieces: Vec<(Piece, Color)>,
+    has_pawns: bool,
+    size: usize,
+}
+
+impl Layout {
+    fn new(material: &Material) -> Layout {
+        let mut pieces: Vec<(Piece, Color)> = material.pieces[0].iter().map(|&p| (p, Color::White)).collect();
+        pieces.extend(material.pieces[1].iter().map(|&p| (p, Color::Black)));
+        let has_pawns = material.has_pawns();
+        let king_slots = if has_pawns { 32 } else { 16 };
+        let size = king_slots * 64usize.pow(pieces.len() as u32 + 1) * 2;
+        Layout { material: material.clone(), pieces, has_pawns, size }
+    }
+
+    fn encode(&self, pos: &Position) -> usize {
+        let n = self.pieces.len();
+        let mut flip = 0;
+        if pos.kings[0] & 7 > 3 {
+            flip ^= 7;
+        }
+        if !self.has_pawns && pos.kings[0] >> 3 > 3 {
+            flip ^= 56;
+        }
+        let mut squares = pos.squares;
+        for s in &mut squares[..n] {
+            *s ^= flip;
+        }
+        // Identical pieces are interchangeable, so only the sorted order is stored.
+        for i in 1..n {
+            let mut j = i;
+            while j > 0 && self.pieces[j - 1] == self.pieces[j] && squares[j - 1] > squares[j] {
+                squares.swap(j - 1, j);
+                j -= 1;
+            }
+        }
+        let wk = pos.kings[0] ^ flip;
+        let mut idx = ((wk >> 3) * 4 + (wk & 7)) * 64 + (pos.kings[1] ^ flip);
+        for &s in &squares[..n] {
+            idx = idx * 64 + s;
+        }
+        idx * 2 + !pos.white_to_move as usize
+    }
+
+    fn decode(&self, idx: usize) -> Position {
+        let mut rest = idx >> 1;
+        let mut squares = [0; MAX_PIECES - 2];
+        for i in (0..self.pieces.len()).rev() {
+            squares[i] = rest % 64;
+            rest /= 64;
+        }
+        let black_king = rest % 64;
+        let slot = rest / 64;
+        Position { white_to_move: idx & 1 == 0, kings: [(slot / 4) * 8 + slot % 4, black_king], squares }
+    }
+
+    fn board(&self, pos: &Position) -> Option<Board> {
+        let n = self.pieces.len();
+        let mut occupied = bb(pos.kings[0]) | bb(pos.kings[1]);
+        for &s in &pos.squares[..n] {
+            occupied |= bb(s);
+        }
+        if occupied.popcnt() as usize != n + 2 || get_king_moves(sq(pos.kings[0])) & bb(pos.kings[1]) != EMPTY {
+            return None;
+        }
+        let mut builder = BoardBuilder::new();
+        builder.piece(sq(pos.kings[0]), Piece::King, Color::White);
+        builder.piece(sq(pos.kings[1]), Piece::King, Color::Black);
+        for (&(piece, color), &s) in self.pieces.iter().zip(&pos.squares[..n]) {
+            if piece == Piece::Pawn && !(8..56).contains(&s) {
+                return None;
+            }
+            builder.piece(sq(s), piece, color);
+        }
+        builder.side_to_move(if pos.white_to_move { Color::White } else { Color::Black });
+        Board::try_from(&builder).ok()
+    }
+
+    // Index of `board`, which must have this material in either orientation.
+    fn index_of(&self, board: &Board) -> usize {
+        let flipped = Material::from_board(board) != self.material;
+        let map = |s: Square| if flipped { s.to_index() ^ 56 } else { s.to_index() };
+        let real = |color: Color| if flipped { !color } else { color };
+        let mut pos = Position {
+            white_to_move: real(board.side_to_move()) == Color::White,
+            kings: [map(board.king_square(real(Color::White))), map(board.king_square(real(Color::Black)))],
+            squares: [0; MAX_PIECES - 2],
+        };
+        let mut i = 0;
+        for group in self.pieces.chunk_by(|a, b| a == b) {
+            let (piece, color) = group[0];
+            for s in board.pieces(piece) & board.color_combined(real(color)) {
+                pos.squares[i] = map(s);
+                i += 1;
+            }
+        }
+        self.encode(&pos)
+    }
+
+    // Calls `f` with every position from which the side that just moved
+    // could have reached `pos` without a capture or promotion.
+    fn for_each_predecessor(&self, pos: &Position, mut f: impl FnMut(usize)) {
+        let n = self.pieces.len();
+        let mover = if pos.white_to_move { Color::Black } else { Color::White };
+        let mut occupied = bb(pos.kings[0]) | bb(pos.kings[1]);
+        for &s in &pos.squares[..n] {
+            occupied |= bb(s);
+        }
+        let mut previous = *pos;
+        previous.white_to_move = !pos.white_to_move;
+
+        let k = mover.to_index();
+        for from in get_king_moves(sq(pos.kings[k])) & !occupied {
+            let mut q = previous;
+            q.kings[k] = from.to_index();
+            f(self.encode(&q));
+        }
+        for i in 0..n {
+            let (piece, color) = self.pieces[i];
+            if color != mover {
+                continue;
+            }
+            let to = pos.squares[i];
+            let origins = match piece {
+                Piece::Pawn => pawn_origins(to, color, occupied),
+                Piece::Knight => get_knight_moves(sq(to)),
+                Piece::Bishop => get_bishop_moves(sq(to), occupied),
+                Piece::Rook => get_rook_moves(sq(to), occupied),
+                _ => get_bishop_moves(sq(to), occupied) | get_rook_moves(sq(to), occupied),
+            };
+            for from in origins & !occupied {
+                let mut q = previous;
+                q.squares[i] = from.to_index();
+                f(self.encode(&q));
+            }
+        }
+    }
+}
+
+fn pawn_origins(to: usize, color: Color, occupied: BitBoard) -> BitBoard {
+    let (one, two, double_rank) = match color {
+        Color::White if to >= 16 => (to - 8, to.wrapping_sub(16), 24..32),
+        Color::Black if to < 48 => (to + 8, to + 16, 32..40),
+        _ => return EMPTY,
+    };
+    if occupied & bb(one) != EMPTY {
+        return EMPTY;
+    }
+    if double_rank.contains(&to) && occupied & bb(two) == EMPTY { bb(one) | bb(two) } else { bb(one) }
+}
+
+#[derive(Clone, Copy, Default)]
+struct Node {
+    // Legal moves that stay inside the table and are not yet known to lose.
+    moves: u8,
+    has_draw: bool,
+    valid: bool,
+    // Best win and longest loss through captures and promotions, in plies
+    // plus one (0 = none).
+    conversion_win: u16,
+    conversion_loss: u16,
+}
+
+pub struct TableStats {
+    pub wins: usize,
+    pub losses: usize,
+    pub longest_mate: u32,
+}
+
+pub struct DtmTable {
+    layout: Layout,
+    values: Vec<u8>,
+}
+
+impl DtmTable {
+    fn init(layout: &Layout, idx: usize, tables: &DtmTables) -> Result<Node, String> {
+        let pos = layout.decode(idx);
+        if layout.encode(&pos) != idx {
+            return Ok(Node::default());
+        }
+        let Some(board) = layout.board(&pos) else { return Ok(Node::default()) };
+        let mut node = Node { valid: true, ..Node::default() };
+        let mut legal = 0;
+        for mv in MoveGen::new_legal(&board) {
+            legal += 1;
+            if board.piece_on(mv.get_dest()).is_none() && mv.get_promotion().is_none() {
+                node.moves += 1;
+                continue;
+            }
+            let child = board.make_move_new(mv);
+            let dtm = tables.probe_dtm(&child).ok_or_else(|| {
+                format!("{} needs the {} table first", layout.material, Material::from_board(&child).canonical())
+            })?;
+            match parent_dtm(dtm) {
+                Dtm::Win(plies) => {
+                    let plies = plies as u16 + 1;
+                    if node.conversion_win == 0 || plies < node.conversion_win {
+                        node.conversion_win = plies;
+                    }
+                }
+                Dtm::Loss(plies) => node.conversion_loss = node.conversion_loss.max(plies as u16 + 1),
+                Dtm::Draw => node.has_draw = true,
+            }
+        }
+        if legal == 0 {
+            if *board.checkers() == EMPTY {
+                node.has_draw = true;
+            } else {
+                node.conversion_loss = 1;
+            }
+        }
+        Ok(node)
+    }
+
+    // Builds the table for `material`; every table it converts into must
+    // already be in `tables`.
+    fn generate(material: &Material, tables: &DtmTables, threads: usize) -> Result<DtmTable, String> {
+        let layout = Layout::new(material);
+        let mut nodes = vec![Node::default(); layout.size];
+        let chunk = layout.size.div_ceil(threads.max(1));
+        std::thread::scope(|scope| {
+            let workers: Vec<_> = nodes
+                .chunks_mut(chunk)
+                .enumerate()
+                .map(|(c, slice)| {
+                    let layout = &layout;
+                    scope.spawn(move || {
+                        for (i, node) in slice.iter_mut().enumerate() {
+                            *node = Self::init(layout, c * chunk + i, tables)?;
+                        }
+                        Ok(())
+                    })
+                })
+                .collect();
+            workers.into_iter().try_for_each(|w| w.join().unwrap_or_else(|_| Err("generator thread panicked".to_string())))
+        })?;
+
+        // Positions are resolved in order of distance, so the first time one
+        // comes out of a bucket is its final value.
+        let mut buckets: Vec<Vec<u32>> = vec![Vec::new(); MAX_PLY + 2];
+        let mut too_long = false;
+        for (i, node) in nodes.iter().enumerate() {
+            let ply = if node.conversion_win > 0 {
+                node.conversion_win as usize - 1
+            } else if node.valid && node.moves == 0 && !node.has_draw {
+                node.conversion_loss as usize - 1
+            } else {
+                continue;
+            };
+            match buckets.get_mut(ply) {
+                Some(bucket) => bucket.push(i as u32),
+                None => too_long = true,
+            }
+        }
+        let mut values = vec![0u8; layout.size];
+        for ply in 0..buckets.len() {
+            let bucket = std::mem::take(&mut buckets[ply]);
+            if ply > MAX_PLY && !bucket.is_empty() {
+                too_long = true;
+            }
+            if too_long {
+                break;
+            }
+            let win = ply % 2 == 1;
+            for p in bucket {
+                let p = p as usize;
+                if values[p] != 0 {
+                    continue;
+                }
+                values[p] = ply as u8 + 1;
+                layout.for_each_predecessor(&layout.decode(p), |q| {
+                    let node = &mut nodes[q];
+                    if values[q] != 0 || !node.valid {
+                        return;
+                    }
+                    if !win {
+                        buckets[ply + 1].push(q as u32);
+                        return;
+                    }
+                    node.moves -= 1;
+                    if node.moves == 0 && node.conversion_win == 0 && !node.has_draw {
+                        let loss = (ply + 1).max((node.conversion_loss as usize).saturating_sub(1));
+                        match buckets.get_mut(loss) {
+                            Some(bucket) => bucket.push(q as u32),
+                            None => too_long = true,
+                        }
+                    }
+                });
+            }
+        }
+        if too_long {
+            return Err(format!("{} has mates longer than {} plies", material, MAX_PLY));
+        }
+        Ok(DtmTable { layout, values })
+    }
+
+    pub fn material(&self) -> &Material {
+        &self.layout.material
+    }
+
+    pub fn len(&self) -> usize {
+        self.values.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+
+    pub fn file_name(&self) -> String {
+        format!("{}.{}", self.layout.material, EXTENSION)
+    }
+
+    // Mate distance ignoring en passant; None for other material.
+    pub fn probe(&self, board: &Board) -> Option<Dtm> {
+        let material = Material::from_board(board);
+        if material != self.layout.material && material.flipped() != self.layout.material {
+            return None;
+        }
+        Some(dtm_from_value(self.values[self.layout.index_of(board)]))
+    }
+
+    pub fn stats(&self) -> TableStats {
+        let mut stats = TableStats { wins: 0, losses: 0, longest_mate: 0 };
+        for &value in &self.values {
+            match dtm_from_value(value) {
+                Dtm::Win(plies) => {
+                    stats.wins += 1;
+                    stats.longest_mate = stats.longest_mate.max(plies);
+                }
+                Dtm::Loss(_) => stats.losses += 1,
+                Dtm::Draw => {}
+            }
+        }
+        stats
+    }
+
+    // Header, then the values run-length encoded as (LEB128 run, value) pairs.
+    pub fn save(&self, path: &Path) -> Result<(), String> {
+        let name = self.layout.material.to_string();
+        let mut bytes = Vec::new();
+        bytes.extend_from_slice(MAGIC);
+        bytes.push(VERSION);
+        bytes.push(name.len() as u8);
+        bytes.extend_from_slice(name.as_bytes());
+        bytes.extend_from_slice(&(self.values.len() as u64).to_le_bytes());
+        for run in self.values.chunk_by(|a, b| a == b) {
+            let mut len = run.len();
+            while len >= 0x80 {
+                bytes.push((len & 0x7F) as u8 | 0x80);
+                len >>= 7;
+            }
+            bytes.push(len as u8);
+            bytes.push(run[0]);
+        }
+        fs::write(path, bytes).map_err(|e| format!("cannot write {}: {}", path.display(), e))
+    }
+
+    pub fn load(path: &Path) -> Result<DtmTable, String> {
+        let bytes = fs::read(path).map_err(|e| format!("cannot read {}: {}", path.display(), e))?;
+        let corrupt = || format!("corrupt tablebase file {}", path.display());
+        if bytes.len() < 6 || &bytes[..4] != MAGIC || bytes[4] != VERSION {
+            return Err(corrupt());
+        }
+        let name_end = 6 + bytes[5] as usize;
+        let name = bytes.get(6..name_end).and_then(|n| std::str::from_utf8(n).ok()).ok_or_else(corrupt)?;
+        let material = Material::parse(name).map_err(|_| corrupt())?;
+        let layout = Layout::new(&material);
+        let count = bytes.get(name_end..name_end + 8).ok_or_else(corrupt)?;
+        if u64::from_le_bytes(count.try_into().unwrap()) != layout.size as u64 {
+            return Err(corrupt());
+        }
+        let mut values = Vec::with_capacity(layout.size);
+        let mut data = bytes[name_end + 8..].iter();
+        while values.len() < layout.size {
+            let mut len = 0usize;
+            let mut shift = 0;
+            loop {
+                let byte = *data.next().ok_or_else(corrupt)?;
+                len |= ((byte & 0x7F) as usize) << shift;
+                shift += 7;
+                if byte & 0x80 == 0 || shift > 56 {
+                    break;
+                }
+            }
+            let value = *data.next().ok_or_else(corrupt)?;
+            if values.len() + len > layout.size {
+                return Err(corrupt());
+            }
+            values.resize(values.len() + len, value);
+        }
+        Ok(DtmTable { layout, values })
+    }
+}
+
+#[derive(Default)]
+pub struct DtmTables {
+    tables: HashMap<Material, DtmTable>,
+    max_pieces: usize,
+}
+
+impl DtmTables {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // Loads every table found in the given directories, separated like PATH.
+    pub fn open(paths: &str) -> Result<Self, String> {
+        let separator = if cfg!(windows) { ';' } else { ':' };
+        let mut tables = DtmTables::new();
+        for dir in paths.split(separator).filter(|d| !d.trim().is_empty()) {
+            let dir = Path::new(dir.trim());
+            let entries = fs::read_dir(dir).map_err(|e| format!("cannot read {}: {}", dir.display(), e))?;
+            let mut names: Vec<PathBuf> = entries.filter_map(|e| e.ok().map(|e| e.path())).collect();
+            names.sort();
+            for path in names.iter().filter(|p| p.extension().and_then(|e| e.to_str()) == Some(EXTENSION)) {
+                tables.insert(DtmTable::load(path)?);
+            }
+        }
+        Ok(tables)
+    }
+
+    pub fn insert(&mut self, table: DtmTable) {
+        self.max_pieces = self.max_pieces.max(table.material().piece_count());
+        self.tables.insert(table.material().clone(), table);
+    }
+
+    pub fn get(&self, material: &Material) -> Option<&DtmTable> {
+        self.tables.get(&material.canonical())
+    }
+
+    pub fn len(&self) -> usize {
+        self.tables.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.tables.is_empty()
+    }
+
+    // Generates `material` and, first, every missing table it converts into.
+    // Returns the materials that were built, in the order they were built.
+    pub fn generate(&mut self, material: &Material, threads: usize) -> Result<Vec<Material>, String> {
+        let material = material.canonical();
+        let mut built = Vec::new();
+        if material.piece_count() <= 2 || self.tables.contains_key(&material) {
+            return Ok(built);
+        }
+        for successor in material.successors() {
+            built.extend(self.generate(&successor, threads)?);
+        }
+        let table = DtmTable::generate(&material, self, threads)?;
+        self.insert(table);
+        built.push(material);
+        Ok(built)
+    }
+
+    pub fn probe_dtm(&self, board: &Board) -> Option<Dtm> {
+        let no_rights = CastleRights::NoRights;
+        if board.castle_rights(Color::White) != no_rights || board.castle_rights(Color::Black) != no_rights {
+            return None;
+        }
+        if board.combined().popcnt() == 2 {
+            return Some(Dtm::Draw);
+        }
+        if board.en_passant().is_some() {
+            return self.search(board);
+        }
+        self.get(&Material::from_board(board))?.probe(board)
+    }
+
+    fn search(&self, board: &Board) -> Option<Dtm> {
+        let mut best: Option<Dtm> = None;
+        for mv in MoveGen::new_legal(board) {
+            let dtm = parent_dtm(self.probe_dtm(&board.make_move_new(mv))?);
+            if best.is_none_or(|b| dtm_rank(dtm) > dtm_rank(b)) {
+                best = Some(dtm);
+            }
+        }
+        match best {
+            Some(dtm) => Some(dtm),
+            None if *board.checkers() != EMPTY => Some(Dtm::Loss(0)),
+            None => Some(Dtm::Draw),
+        }
+    }
+}
+
+impl Tablebase for DtmTables {
+    fn max_pieces(&self) -> usize {
+        self.max_pieces
+    }
+
+    fn probe_wdl(&self, board: &Board) -> Option<Wdl> {
+        self.probe_dtm(board).map(Dtm::wdl)
+    }
+
+    fn probe_dtm(&self, board: &Board) -> Option<Dtm> {
+        DtmTables::probe_dtm(self, board)
+    }
+}
+
+static TABLES: LazyLock<RwLock<Option<Arc<DtmTables>>>> = LazyLock::new(|| RwLock::new(None));
+static CARDINALITY: AtomicUsize = AtomicUsize::new(0);
+
+// Loads the tables under `path`; an empty path or `<empty>` disables probing.
+// Returns the number of tables found.
+pub fn set_path(path: &str) -> Result<usize, String> {
+    let tables = if path.is_empty() || path == "<empty>" { None } else { Some(DtmTables::open(path)?) };
+    let count = tables.as_ref().map_or(0, |t| t.len());
+    CARDINALITY.store(tables.as_ref().map_or(0, |t| t.max_pieces), Ordering::Relaxed);
+    *TABLES.write().unwrap_or_else(|e| e.into_inner()) = tables.map(Arc::new);
+    Ok(count)
+}
+
+pub fn active() -> Option<Arc<DtmTables>> {
+    TABLES.read().unwrap_or_else(|e| e.into_inner()).clone()
+}
+
+pub fn cardinality() -> usize {
+    CARDINALITY.load(Ordering::Relaxed)
+}
@@ -0,0 +1,183 @@
+use crate::bitbase::{self, BitbaseResult};
+use crate::params::EvalParams;
+use chess::{BitBoard, Board, Color, Piece, Square, EMPTY};
+
+pub const SCALE_NORMAL: i32 = 64;
+pub const KNOWN_WIN: i32 = 2000;
+
+const DARK_SQUARES: u64 = 0xaa55_aa55_aa55_aa55;
+
+fn count(board: &Board, piece: Piece, color: Color) -> u32 {
+    (board.pieces(piece) & board.color_combined(color)).popcnt()
+}
+
+fn non_pawn_material(board: &Board, color: Color, params: &EvalParams) -> i32 {
+    [Piece::Knight, Piece::Bishop, Piece::Rook, Piece::Queen]
+        .iter()
+        .map(|&p| count(board, p, color) as i32 * params.piece_values[p.to_index()])
+        .sum()
+}
+
+fn distance(a: Square, b: Square) -> i32 {
+    let file = (a.get_file().to_index() as i32 - b.get_file().to_index() as i32).abs();
+    let rank = (a.get_rank().to_index() as i32 - b.get_rank().to_index() as i32).abs();
+    file.max(rank)
+}
+
+// 0 in the centre, 6 in the corners.
+fn edge_distance_bonus(sq: Square) -> i32 {
+    let file = sq.get_file().to_index() as i32;
+    let rank = sq.get_rank().to_index() as i32;
+    let centre = |x: i32| (2 * x - 7).abs() / 2;
+    centre(file) + centre(rank)
+}
+
+fn is_dark(sq: Square) -> bool {
+    DARK_SQUARES & (1u64 << sq.to_index()) != 0
+}
+
+fn relative_rank(sq: Square, color: Color) -> i32 {
+    match color {
+        Color::White => sq.get_rank().to_index() as i32,
+        Color::Black => 7 - sq.get_rank().to_index() as i32,
+    }
+}
+
+fn to_stm(board: &Board, strong: Color, score: i32) -> i32 {
+    if board.side_to_move() == strong { score } else { -score }
+}
+
+pub fn insufficient_material(board: &Board) -> bool {
+    let pawns_and_majors = board.pieces(Piece::Pawn) | board.pieces(Piece::Rook) | board.pieces(Piece::Queen);
+    if pawns_and_majors != EMPTY {
+        return false;
+    }
+    let knights = *board.pieces(Piece::Knight);
+    let bishops = *board.pieces(Piece::Bishop);
+    if (knights | bishops).popcnt() <= 1 {
+        return true;
+    }
+    // Any number of bishops all on one square colour cannot mate.
+    knights == EMPTY && (bishops.0 & DARK_SQUARES == 0 || bishops.0 & !DARK_SQUARES == 0)
+}
+
+// KX vs K where X is enough to mate: drive the lone king to the edge and
+// bring the strong king closer.
+fn mate_lone_king(board: &Board, strong: Color, params: &EvalParams) -> i32 {
+    let weak_king = board.king_square(!strong);
+    let strong_king = board.king_square(strong);
+    let material = non_pawn_material(board, strong, params)
+        + count(board, Piece::Pawn, strong) as i32 * params.piece_values[Piece::Pawn.to_index()];
+    KNOWN_WIN + material + 20 * edge_distance_bonus(weak_king) + 10 * (7 - distance(strong_king, weak_king))
+}
+
+// KBNK: the lone king can only be mated in a corner of the bishop's colour.
+fn kbnk(board: &Board, strong: Color, params: &EvalParams) -> i32 {
+    let weak_king = board.king_square(!strong);
+    let strong_king = board.king_square(strong);
+    let bishop = (board.pieces(Piece::Bishop) & board.color_combined(strong)).to_square();
+    let corners = if is_dark(bishop) { [Square::A1, Square::H8] } else { [Square::H1, Square::A8] };
+    let corner_distance = corners.iter().map(|&c| distance(weak_king, c)).min().unwrap();
+    KNOWN_WIN + non_pawn_material(board, strong, params) + 40 * (7 - corner_distance)
+        + 10 * (7 - distance(strong_king, weak_king))
+}
+
+// Scores positions with dedicated endgame knowledge, from the side to move's
+// point of view. Returns None when the normal evaluation should be used.
+pub fn specialized_eval(board: &Board, params: &EvalParams) -> Option<i32> {
+    if insufficient_material(board) {
+        return Some(0);
+    }
+    for strong in [Color::White, Color::Black] {
+        let weak = !strong;
+        let weak_alone = board.color_combined(weak).popcnt() == 1;
+        if !weak_alone {
+            continue;
+        }
+        let pieces = |p: Piece| count(board, p, strong);
+        let (pawns, knights, bishops, rooks, queens) =
+            (pieces(Piece::Pawn), pieces(Piece::Knight), pieces(Piece::Bishop), pieces(Piece::Rook), pieces(Piece::Queen));
+
+        if pawns == 0 && knights == 1 && bishops == 1 && rooks == 0 && queens == 0 {
+            return Some(to_stm(board, strong, kbnk(board, strong, params)));
+        }
+        if pawns == 0 && knights == 2 && bishops == 0 && rooks == 0 && queens == 0 {
+            return Some(0);
+        }
+        // Three-piece endings are looked up exactly in the built-in bitbases.
+        if let Some(result) = bitbase::probe(board) {
+            return Some(match result {
+                BitbaseResult::Draw => 0,
+                BitbaseResult::Win(_) if pawns == 1 => {
+                    let pawn = (board.pieces(Piece::Pawn) & board.color_combined(strong)).to_square();
+                    let bonus = params.piece_values[Piece::Pawn.to_index()] + 20 * relative_rank(pawn, strong);
+                    to_stm(board, strong, KNOWN_WIN + bonus)
+                }
+                BitbaseResult::Win(_) => to_stm(board, strong, mate_lone_king(board, strong, params)),
+            });
+        }
+        if wrong_bishop_draw(board, strong) {
+            return Some(0);
+        }
+        let bishop_pair = {
+            let b = board.pieces(Piece::Bishop) & board.color_combined(strong);
+            b.0 & DARK_SQUARES != 0 && b.0 & !DARK_SQUARES != 0
+        };
+        if rooks + queens > 0 || bishop_pair || (knights > 0 && bishops > 0) {
+            return Some(to_stm(board, strong, mate_lone_king(board, strong, params)));
+        }
+    }
+    None
+}
+
+// KB + rook pawns vs K where the bishop does not control the promotion
+// corner and the defending king already sits in front of it.
+fn wrong_bishop_draw(board: &Board, strong: Color) -> bool {
+    let ours = board.color_combined(strong);
+    let pawns = board.pieces(Piece::Pawn) & ours;
+    let bishops = board.pieces(Piece::Bishop) & ours;
+    if pawns == EMPTY || bishops.popcnt() != 1 || (ours ^ pawns ^ bishops).popcnt() != 1 {
+        return false;
+    }
+    let files: Vec<usize> = pawns.into_iter().map(|sq| sq.get_file().to_index()).collect();
+    let file = files[0];
+    if !(file == 0 || file == 7) || files.iter().any(|&f| f != file) {
+        return false;
+    }
+    let promotion = Square::make_square(strong.to_their_backrank(), chess::File::from_index(file));
+    let bishop_sq = bishops.to_square();
+    is_dark(promotion) != is_dark(bishop_sq) && distance(board.king_square(!strong), promotion) <= 1
+}
+
+// Scale factor (out of SCALE_NORMAL) applied to the evaluation when `strong`
+// is ahead, for material balances that are much harder to win than the raw
+// score suggests.
+pub fn scale_factor(board: &Board, strong: Color, params: &EvalParams) -> i32 {
+    let weak = !strong;
+    let strong_pawns = count(board, Piece::Pawn, strong);
+    let strong_npm = non_pawn_material(board, strong, params);
+    let weak_npm = non_pawn_material(board, weak, params);
+    let bishop_value = params.piece_values[Piece::Bishop.to_index()];
+    let rook_value = params.piece_values[Piece::Rook.to_index()];
+
+    if strong_pawns == 0 && strong_npm - weak_npm <= bishop_value {
+        return if strong_npm < rook_value {
+            0
+        } else if weak_npm <= bishop_value {
+            4
+        } else {
+            14
+        };
+    }
+
+    let white_bishops = board.pieces(Piece::Bishop) & board.color_combined(Color::White);
+    let black_bishops = board.pieces(Piece::Bishop) & board.color_combined(Color::Black);
+    if white_bishops.popcnt() == 1
+        && black_bishops.popcnt() == 1
+        && is_dark(white_bishops.to_square()) != is_dark(black_bishops.to_square())
+    {
+        let others: BitBoard = board.pieces(Piece::Knight) | board.pieces(Piece::Rook) | board.pieces(Piece::Queen);
+        return if others == EMPTY { 22 } else { 44 };
+    }
+    SCALE_NORMAL
+}
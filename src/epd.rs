@@ -0,0 +1,227 @@
+use crate::pgn::{parse_san, to_san};
+use crate::uci::parse_fen;
+use crate::{mate_in, search, SearchInfo};
+use chess::{Board, ChessMove};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+
+// One EPD line: the four position fields followed by `opcode operand;`
+// operations, e.g. `bm Nf3 Qd2; id "WAC.001";`.
+#[derive(Clone, Debug)]
+pub struct EpdRecord {
+    pub fen: String,
+    pub board: Board,
+    pub ops: Vec<(String, String)>,
+}
+
+impl EpdRecord {
+    pub fn op(&self, name: &str) -> Option<&str> {
+        self.ops.iter().find(|(n, _)| n == name).map(|(_, v)| v.as_str())
+    }
+
+    pub fn id(&self) -> &str {
+        self.op("id").unwrap_or(&self.fen)
+    }
+}
+
+pub fn parse_line(line: &str) -> Result<EpdRecord, String> {
+    let fields: Vec<&str> = line.split_whitespace().collect();
+    // EPD has four position fields, but lines copied from a FEN often keep
+    // the halfmove and fullmove counters; two numbers there belong to the FEN.
+    let counters = fields.get(4..6).is_some_and(|c| c.iter().all(|f| f.parse::<u32>().is_ok()));
+    let (position, rest) = fields.split_at(fields.len().min(if counters { 6 } else { 4 }));
+    let fen = position.join(" ");
+    let (board, _) = parse_fen(&fen)?;
+    let rest = rest.join(" ");
+    let mut ops = Vec::new();
+    let mut op = String::new();
+    let mut quoted = false;
+    for c in rest.chars() {
+        match c {
+            '"' => quoted = !quoted,
+            ';' if !quoted => {
+                push_op(&mut ops, &op);
+                op.clear();
+            }
+            _ => op.push(c),
+        }
+    }
+    if quoted {
+        return Err(format!("unterminated string in EPD {}", line));
+    }
+    push_op(&mut ops, &op);
+    Ok(EpdRecord { fen, board, ops })
+}
+
+fn push_op(ops: &mut Vec<(String, String)>, op: &str) {
+    let op = op.trim();
+    if !op.is_empty() {
+        let (name, operand) = op.split_once(' ').unwrap_or((op, ""));
+        ops.push((name.to_string(), operand.trim().to_string()));
+    }
+}
+
+// Parses every non-empty, non-comment line; errors carry the line number.
+pub fn parse(text: &str) -> Vec<Result<EpdRecord, String>> {
+    text.lines()
+        .enumerate()
+        .filter(|(_, l)| !l.trim().is_empty() && !l.trim_start().starts_with('#'))
+        .map(|(i, l)| parse_line(l).map_err(|e| format!("line {}: {}", i + 1, e)))
+        .collect()
+}
+
+// Budget for each suite position; any limit that is set ends the search.
+#[derive(Clone, Copy, Debug)]
+pub struct SuiteConfig {
+    pub depth: i32,
+    pub movetime: Option<u64>,
+    pub nodes: Option<u64>,
+    pub threads: usize,
+}
+
+#[derive(Clone, Debug)]
+pub struct EpdResult {
+    pub index: usize,
+    pub id: String,
+    pub fen: String,
+    // SAN of the move the engine chose, "-" without legal moves.
+    pub best_move: String,
+    pub score: i32,
+    pub depth: i32,
+    pub nodes: u64,
+    pub time_ms: u64,
+    // None when the record has no bm, am, dm or c0 to check against.
+    pub solved: Option<bool>,
+    // Milliseconds until the engine settled on a solving move for good.
+    pub solve_time_ms: Option<u64>,
+    // Strategic Test Suite points from c0, with the best available.
+    pub points: u32,
+    pub max_points: u32,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct SuiteSummary {
+    pub positions: usize,
+    pub checked: usize,
+    pub solved: usize,
+    pub points: u32,
+    pub max_points: u32,
+    pub nodes: u64,
+    pub time_ms: u64,
+}
+
+impl SuiteSummary {
+    pub fn new(results: &[EpdResult]) -> Self {
+        let mut summary = SuiteSummary { positions: results.len(), ..Default::default() };
+        for r in results {
+            summary.checked += r.solved.is_some() as usize;
+            summary.solved += (r.solved == Some(true)) as usize;
+            summary.points += r.points;
+            summary.max_points += r.max_points;
+            summary.nodes += r.nodes;
+            summary.time_ms += r.time_ms;
+        }
+        summary
+    }
+}
+
+// Moves listed in a bm/am operand, skipping any that are not legal here.
+fn san_moves(board: &Board, operand: &str) -> Vec<ChessMove> {
+    operand.split_whitespace().filter_map(|san| parse_san(board, san.trim_end_matches(',')).ok()).collect()
+}
+
+// STS-style "c0" operand: "f5=10, Be5+=2, Bf2=3"; the points follow the last
+// "=", so promotions such as "e8=Q=10" keep theirs.
+pub fn sts_points(board: &Board, operand: &str) -> Vec<(ChessMove, u32)> {
+    operand
+        .split(',')
+        .filter_map(|item| {
+            let (san, points) = item.trim().rsplit_once('=')?;
+            Some((parse_san(board, san.trim()).ok()?, points.trim().parse().ok()?))
+        })
+        .collect()
+}
+
+// Whether `mv` with `score` solves the record; None when nothing is checked.
+pub fn check(record: &EpdRecord, mv: Option<ChessMove>, score: i32) -> Option<bool> {
+    let board = &record.board;
+    let mut checks = Vec::new();
+    if let Some(bm) = record.op("bm") {
+        checks.push(mv.is_some_and(|mv| san_moves(board, bm).contains(&mv)));
+    }
+    if let Some(am) = record.op("am") {
+        checks.push(mv.is_some_and(|mv| !san_moves(board, am).contains(&mv)));
+    }
+    if let Some(dm) = record.op("dm") {
+        let moves = mate_in(score).filter(|&moves| moves > 0);
+        checks.push(moves.is_some_and(|moves| dm.trim().parse::<i32>().is_ok_and(|n| moves <= n)));
+    }
+    // Without bm or am, an STS record is solved by its top-scoring move.
+    if checks.is_empty()
+        && let Some(c0) = record.op("c0")
+    {
+        let points = sts_points(board, c0);
+        if let Some(max) = points.iter().map(|&(_, p)| p).max() {
+            checks.push(mv.is_some_and(|mv| points.contains(&(mv, max))));
+        }
+    }
+    (!checks.is_empty()).then(|| checks.iter().all(|&ok| ok))
+}
+
+pub fn run_position(record: &EpdRecord, index: usize, config: &SuiteConfig) -> EpdResult {
+    let mut info = SearchInfo::new(config.movetime.unwrap_or(u64::MAX));
+    info.node_limit = config.nodes;
+    let result = search(&record.board, config.depth, &mut info);
+    let time_ms = info.start.elapsed().as_millis() as u64;
+    let solved = check(record, result.best_move, result.score);
+    // The solution time is the first iteration after which every later one
+    // also solved the position.
+    let mut solve_time_ms = None;
+    if solved == Some(true) {
+        for it in info.iterations.iter().rev() {
+            if check(record, Some(it.best_move), it.score) != Some(true) {
+                break;
+            }
+            solve_time_ms = Some(it.time_ms);
+        }
+    }
+    let sts = record.op("c0").map(|c0| sts_points(&record.board, c0)).unwrap_or_default();
+    let points = sts.iter().find(|&&(m, _)| Some(m) == result.best_move).map_or(0, |&(_, p)| p);
+    EpdResult {
+        index,
+        id: record.id().to_string(),
+        fen: record.fen.clone(),
+        best_move: result.best_move.map_or("-".to_string(), |m| to_san(&record.board, m)),
+        score: result.score,
+        depth: result.depth,
+        nodes: result.nodes,
+        time_ms,
+        solved,
+        solve_time_ms,
+        points,
+        max_points: sts.iter().map(|&(_, p)| p).max().unwrap_or(0),
+    }
+}
+
+// Runs the suite on `config.threads` threads. `report` sees each result as
+// it finishes; the returned results are in file order.
+pub fn run_suite(records: &[EpdRecord], config: &SuiteConfig, report: impl Fn(&EpdResult) + Sync) -> Vec<EpdResult> {
+    let next = AtomicUsize::new(0);
+    let results = Mutex::new(Vec::with_capacity(records.len()));
+    std::thread::scope(|scope| {
+        for _ in 0..config.threads.clamp(1, records.len().max(1)) {
+            scope.spawn(|| {
+                loop {
+                    let index = next.fetch_add(1, Ordering::Relaxed);
+                    let Some(record) = records.get(index) else { break };
+                    let result = run_position(record, index, config);
+                    report(&result);
+                    results.lock().unwrap().push(result);
+                }
+            });
+        }
+    });
+    let mut results = results.into_inner().unwrap();
+    results.sort_by_key(|r| r.index);
+    results
+}
@@ -0,0 +1,131 @@
+use crate::endgame::{self, SCALE_NORMAL};
+use crate::king_safety::{game_phase, king_safety_midgame, MAX_PHASE};
+use crate::params::EvalParams;
+use chess::{Board, Color, Piece, ALL_PIECES};
+use std::fmt;
+
+// The classical eval has no pawn-structure or mobility terms and its PSTs are
+// not tapered; only king safety is weighted by phase. The table lists the
+// missing terms explicitly so a trace is not mistaken for a partial one.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct EvalTrace {
+    pub side_to_move: Color,
+    // Per-side terms, indexed by `Color::to_index()`; each is the score that
+    // side contributes before the two sides are subtracted.
+    pub material: [i32; 2],
+    pub pst: [i32; 2],
+    pub king_safety: [i32; 2],
+    pub phase: i32,
+    // Score from dedicated endgame knowledge, which replaces every other term.
+    pub endgame: Option<i32>,
+    pub scale: i32,
+    // Material plus PST of the piece on each square, from its owner's point of view.
+    pub squares: [Option<(Piece, Color, i32)>; 64],
+    // Final score from the side to move's point of view, as `evaluation()` returns it.
+    pub total: i32,
+}
+
+// Walks exactly the same terms as `evaluate_with`, keeping each one separate.
+pub fn trace_with(board: &Board, params: &EvalParams) -> EvalTrace {
+    let stm = board.side_to_move();
+    let psts = params.psts();
+    let mut trace = EvalTrace {
+        side_to_move: stm,
+        material: [0; 2],
+        pst: [0; 2],
+        king_safety: [0; 2],
+        phase: game_phase(board),
+        endgame: endgame::specialized_eval(board, params),
+        scale: SCALE_NORMAL,
+        squares: [None; 64],
+        total: 0,
+    };
+    for (piece_idx, piece) in ALL_PIECES.iter().enumerate() {
+        for color in [Color::White, Color::Black] {
+            for sq in board.pieces(*piece) & board.color_combined(color) {
+                let idx = if color == stm { sq.to_index() } else { sq.to_index() ^ 56 };
+                let value = params.piece_values[piece_idx];
+                let pst = psts[piece_idx][idx];
+                trace.material[color.to_index()] += value;
+                trace.pst[color.to_index()] += pst;
+                trace.squares[sq.to_index()] = Some((*piece, color, value + pst));
+            }
+        }
+    }
+    for color in [Color::White, Color::Black] {
+        trace.king_safety[color.to_index()] = king_safety_midgame(board, color, params);
+    }
+    let side_total = |c: Color| trace.material[c.to_index()] + trace.pst[c.to_index()] + trace.king_safety[c.to_index()];
+    let raw = side_total(stm) - side_total(!stm);
+    if let Some(score) = trace.endgame {
+        trace.total = score;
+    } else {
+        trace.scale = endgame::scale_factor(board, if raw >= 0 { stm } else { !stm }, params);
+        trace.total = raw * trace.scale / SCALE_NORMAL;
+    }
+    trace
+}
+
+pub fn trace(board: &Board) -> EvalTrace {
+    trace_with(board, &crate::params::active_params())
+}
+
+impl EvalTrace {
+    pub fn white_total(&self) -> i32 {
+        match self.side_to_move {
+            Color::White => self.total,
+            Color::Black => -self.total,
+        }
+    }
+}
+
+impl fmt::Display for EvalTrace {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let w = Color::White.to_index();
+        let b = Color::Black.to_index();
+        writeln!(f, "     Term    |  White |  Black |  Total")?;
+        writeln!(f, " ------------+--------+--------+--------")?;
+        let rows = [
+            ("Material", Some(self.material)),
+            ("PST", Some(self.pst)),
+            ("Pawns", None),
+            ("Mobility", None),
+            ("King safety", Some(self.king_safety)),
+        ];
+        for (name, term) in rows {
+            match term {
+                Some(term) => writeln!(f, " {:<11} | {:>6} | {:>6} | {:>6}", name, term[w], term[b], term[w] - term[b])?,
+                None => writeln!(f, " {:<11} | {:>6} | {:>6} | {:>6} (no such term in the classical eval)", name, "-", "-", "-")?,
+            }
+        }
+        writeln!(f, " ------------+--------+--------+--------")?;
+        writeln!(f, " Phase       | {:>2}/{:<2} (tapers king safety only)", self.phase, MAX_PHASE)?;
+        match self.endgame {
+            Some(score) => writeln!(f, " Endgame     | {:>6} (specialised, side to move)", score)?,
+            None => writeln!(f, " Scale       | {:>2}/{:<2}", self.scale, SCALE_NORMAL)?,
+        }
+        writeln!(f, " Final       | {:>6} (white side), {} (side to move)", self.white_total(), self.total)?;
+        writeln!(f)?;
+        writeln!(f, " +-------+-------+-------+-------+-------+-------+-------+-------+")?;
+        for rank in (0..8).rev() {
+            let mut pieces = String::from(" |");
+            let mut values = String::from(" |");
+            for file in 0..8 {
+                match self.squares[rank * 8 + file] {
+                    Some((piece, color, value)) => {
+                        pieces.push_str(&format!("   {}   |", piece.to_string(color)));
+                        values.push_str(&format!("{:^7}|", value));
+                    }
+                    None => {
+                        pieces.push_str("       |");
+                        values.push_str("       |");
+                    }
+                }
+            }
+            writeln!(f, "{}", pieces)?;
+            writeln!(f, "{}", values)?;
+            writeln!(f, " +-------+-------+-------+-------+-------+-------+-------+-------+")?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,233 @@
+use crate::book::{encode_move, polyglot_key};
+use chess::{Board, BoardStatus, ChessMove, Color};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{LazyLock, Mutex};
+
+// Experience file: what the engine learned about its own root moves in
+// earlier games. Each record is keyed by the Polyglot key of the position and
+// the Polyglot-encoded move, and keeps the deepest search result for that
+// move plus how the games it was played in ended.
+const MAGIC: &[u8; 4] = b"AXEX";
+const VERSION: u8 = 1;
+const RECORD_SIZE: usize = 8 + 2 + 1 + 2 + 3 * 2;
+
+// Centipawns added at the root per net game won with a move, capped.
+const OUTCOME_BONUS: i32 = 10;
+const MAX_BIAS: i32 = 50;
+// Without a decisive final position, a game counts as won or lost once the
+// last search score passes this margin.
+const ADJUDICATION_MARGIN: i32 = 300;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ExpRecord {
+    pub depth: u8,
+    // Search score after playing the move, from the mover's point of view.
+    pub score: i16,
+    pub wins: u16,
+    pub draws: u16,
+    pub losses: u16,
+}
+
+impl ExpRecord {
+    pub fn games(&self) -> u32 {
+        self.wins as u32 + self.draws as u32 + self.losses as u32
+    }
+
+    pub fn bias(&self) -> i32 {
+        (OUTCOME_BONUS * (self.wins as i32 - self.losses as i32)).clamp(-MAX_BIAS, MAX_BIAS)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Outcome {
+    Win,
+    Draw,
+    Loss,
+}
+
+#[derive(Default)]
+pub struct Experience {
+    records: HashMap<(u64, u16), ExpRecord>,
+    // Root moves played in the current game, with the colour that played them.
+    played: Vec<((u64, u16), Color)>,
+    last_score: Option<i32>,
+}
+
+impl Experience {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn len(&self) -> usize {
+        self.records.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.records.is_empty()
+    }
+
+    pub fn get(&self, board: &Board, mv: ChessMove) -> Option<ExpRecord> {
+        self.records.get(&(polyglot_key(board), encode_move(board, mv))).copied()
+    }
+
+    // Stores the result of a completed search that chose `mv`, keeping the
+    // deepest one seen, and remembers the move as played in this game.
+    pub fn record_search(&mut self, board: &Board, mv: ChessMove, depth: i32, score: i32) {
+        let key = (polyglot_key(board), encode_move(board, mv));
+        let record = self.records.entry(key).or_default();
+        let depth = depth.clamp(0, u8::MAX as i32) as u8;
+        if depth >= record.depth {
+            record.depth = depth;
+            record.score = score.clamp(i16::MIN as i32, i16::MAX as i32) as i16;
+        }
+        self.played.push((key, board.side_to_move()));
+        self.last_score = Some(score);
+    }
+
+    // Credits `outcome`, seen from `engine`'s side, to every move played
+    // this game and starts a new one.
+    pub fn record_outcome(&mut self, engine: Color, outcome: Outcome) {
+        for (key, color) in std::mem::take(&mut self.played) {
+            let Some(record) = self.records.get_mut(&key) else { continue };
+            let outcome = match (color == engine, outcome) {
+                (_, Outcome::Draw) => Outcome::Draw,
+                (true, o) => o,
+                (false, Outcome::Win) => Outcome::Loss,
+                (false, _) => Outcome::Win,
+            };
+            let counter = match outcome {
+                Outcome::Win => &mut record.wins,
+                Outcome::Draw => &mut record.draws,
+                Outcome::Loss => &mut record.losses,
+            };
+            *counter = counter.saturating_add(1);
+        }
+        self.last_score = None;
+    }
+
+    // Ends the current game given the last position the GUI sent: decided by
+    // mate or stalemate on the board, otherwise by the last search score.
+    pub fn finish_game(&mut self, board: &Board) {
+        let Some(&(_, engine)) = self.played.last() else { return };
+        let outcome = match board.status() {
+            BoardStatus::Checkmate if board.side_to_move() == engine => Outcome::Loss,
+            BoardStatus::Checkmate => Outcome::Win,
+            BoardStatus::Stalemate => Outcome::Draw,
+            BoardStatus::Ongoing => match self.last_score.unwrap_or(0) {
+                s if s >= ADJUDICATION_MARGIN => Outcome::Win,
+                s if s <= -ADJUDICATION_MARGIN => Outcome::Loss,
+                _ => Outcome::Draw,
+            },
+        };
+        self.record_outcome(engine, outcome);
+    }
+
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let bytes = fs::read(path).map_err(|e| format!("cannot read {}: {}", path.display(), e))?;
+        let corrupt = || format!("corrupt experience file {}", path.display());
+        if bytes.len() < 5 || &bytes[..4] != MAGIC || bytes[4] != VERSION || (bytes.len() - 5) % RECORD_SIZE != 0 {
+            return Err(corrupt());
+        }
+        let mut experience = Experience::new();
+        for r in bytes[5..].chunks_exact(RECORD_SIZE) {
+            let u16_at = |i: usize| u16::from_le_bytes([r[i], r[i + 1]]);
+            let key = u64::from_le_bytes(r[0..8].try_into().unwrap());
+            let record = ExpRecord {
+                depth: r[10],
+                score: i16::from_le_bytes([r[11], r[12]]),
+                wins: u16_at(13),
+                draws: u16_at(15),
+                losses: u16_at(17),
+            };
+            experience.records.insert((key, u16_at(8)), record);
+        }
+        Ok(experience)
+    }
+
+    // Writes at most `limit` records, preferring moves with more finished
+    // games and then deeper searches.
+    pub fn save(&self, path: &Path, limit: usize) -> Result<(), String> {
+        let mut records: Vec<_> = self.records.iter().collect();
+        records.sort_by_key(|&(&key, r)| (std::cmp::Reverse((r.games(), r.depth)), key));
+        let mut bytes = Vec::with_capacity(5 + records.len().min(limit) * RECORD_SIZE);
+        bytes.extend_from_slice(MAGIC);
+        bytes.push(VERSION);
+        for (&(key, mv), r) in records.into_iter().take(limit) {
+            bytes.extend_from_slice(&key.to_le_bytes());
+            bytes.extend_from_slice(&mv.to_le_bytes());
+            bytes.push(r.depth);
+            bytes.extend_from_slice(&r.score.to_le_bytes());
+            for counter in [r.wins, r.draws, r.losses] {
+                bytes.extend_from_slice(&counter.to_le_bytes());
+            }
+        }
+        fs::write(path, bytes).map_err(|e| format!("cannot write {}: {}", path.display(), e))
+    }
+}
+
+pub const DEFAULT_SIZE: usize = 100_000;
+
+static EXPERIENCE: LazyLock<Mutex<Experience>> = LazyLock::new(|| Mutex::new(Experience::new()));
+static FILE: Mutex<Option<PathBuf>> = Mutex::new(None);
+static READ: AtomicBool = AtomicBool::new(true);
+static WRITE: AtomicBool = AtomicBool::new(true);
+static SIZE: AtomicUsize = AtomicUsize::new(DEFAULT_SIZE);
+
+fn experience() -> std::sync::MutexGuard<'static, Experience> {
+    EXPERIENCE.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+// Selects the experience file; with reading enabled its records replace the
+// ones in memory. A missing file is fine, it is created on the first save.
+// Returns the number of records loaded.
+pub fn set_file(path: &str) -> Result<usize, String> {
+    let path = (!path.is_empty() && path != "<empty>").then(|| PathBuf::from(path));
+    let loaded = match &path {
+        Some(p) if READ.load(Ordering::Relaxed) && p.exists() => Experience::load(p)?,
+        _ => Experience::new(),
+    };
+    let count = loaded.len();
+    *experience() = loaded;
+    *FILE.lock().unwrap_or_else(|e| e.into_inner()) = path;
+    Ok(count)
+}
+
+pub fn set_read(enabled: bool) {
+    READ.store(enabled, Ordering::Relaxed);
+}
+
+pub fn set_write(enabled: bool) {
+    WRITE.store(enabled, Ordering::Relaxed);
+}
+
+pub fn set_size(records: usize) {
+    SIZE.store(records, Ordering::Relaxed);
+}
+
+// Learned record for a root move; None when reading experience is off.
+pub fn lookup(board: &Board, mv: ChessMove) -> Option<ExpRecord> {
+    if !READ.load(Ordering::Relaxed) {
+        return None;
+    }
+    experience().get(board, mv)
+}
+
+pub fn record_search(board: &Board, mv: ChessMove, depth: i32, score: i32) {
+    if WRITE.load(Ordering::Relaxed) {
+        experience().record_search(board, mv, depth, score);
+    }
+}
+
+// Closes the current game and saves the file when writing is enabled.
+pub fn finish_game(board: &Board) -> Result<(), String> {
+    let mut experience = experience();
+    experience.finish_game(board);
+    let file = FILE.lock().unwrap_or_else(|e| e.into_inner()).clone();
+    match file {
+        Some(path) if WRITE.load(Ordering::Relaxed) => experience.save(&path, SIZE.load(Ordering::Relaxed)),
+        _ => Ok(()),
+    }
+}
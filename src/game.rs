@@ -0,0 +1,68 @@
+use crate::endgame::insufficient_material;
+use chess::{Board, BoardStatus, ChessMove, Color, Piece};
+
+// A game in progress with the draw rules the board itself does not track:
+// threefold repetition, the fifty-move rule and dead positions.
+#[derive(Clone, Debug)]
+pub struct GameState {
+    pub start: Board,
+    pub board: Board,
+    pub moves: Vec<ChessMove>,
+    // Positions since the last capture or pawn move, for repetitions.
+    positions: Vec<u64>,
+    halfmove_clock: usize,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct GameEnd {
+    // "1-0", "0-1" or "1/2-1/2".
+    pub result: &'static str,
+    pub reason: &'static str,
+}
+
+impl GameState {
+    pub fn new(start: Board) -> Self {
+        GameState { start, board: start, moves: Vec::new(), positions: vec![start.get_hash()], halfmove_clock: 0 }
+    }
+
+    pub fn play(&mut self, mv: ChessMove) {
+        let irreversible =
+            self.board.piece_on(mv.get_source()) == Some(Piece::Pawn) || self.board.piece_on(mv.get_dest()).is_some();
+        self.board = self.board.make_move_new(mv);
+        self.moves.push(mv);
+        if irreversible {
+            self.positions.clear();
+            self.halfmove_clock = 0;
+        } else {
+            self.halfmove_clock += 1;
+        }
+        self.positions.push(self.board.get_hash());
+    }
+
+    pub fn halfmove_clock(&self) -> usize {
+        self.halfmove_clock
+    }
+
+    pub fn end(&self) -> Option<GameEnd> {
+        match self.board.status() {
+            BoardStatus::Checkmate => {
+                let result = if self.board.side_to_move() == Color::White { "0-1" } else { "1-0" };
+                return Some(GameEnd { result, reason: "checkmate" });
+            }
+            BoardStatus::Stalemate => return Some(GameEnd { result: "1/2-1/2", reason: "stalemate" }),
+            BoardStatus::Ongoing => {}
+        }
+        let draw = |reason| Some(GameEnd { result: "1/2-1/2", reason });
+        let current = self.board.get_hash();
+        if self.positions.iter().filter(|&&h| h == current).count() >= 3 {
+            return draw("threefold repetition");
+        }
+        if self.halfmove_clock >= 100 {
+            return draw("fifty-move rule");
+        }
+        if insufficient_material(&self.board) {
+            return draw("insufficient material");
+        }
+        None
+    }
+}
@@ -0,0 +1,138 @@
+use chess::{
+    get_adjacent_files, get_bishop_moves, get_file, get_king_moves, get_knight_moves,
+    get_pawn_attacks, get_rook_moves, BitBoard, Board, Color, Piece, Square, EMPTY,
+};
+
+use crate::params::EvalParams;
+
+pub const MAX_PHASE: i32 = 24;
+
+pub fn game_phase(board: &Board) -> i32 {
+    let phase = board.pieces(Piece::Knight).popcnt() as i32
+        + board.pieces(Piece::Bishop).popcnt() as i32
+        + 2 * board.pieces(Piece::Rook).popcnt() as i32
+        + 4 * board.pieces(Piece::Queen).popcnt() as i32;
+    phase.min(MAX_PHASE)
+}
+
+pub fn piece_attacks(piece: Piece, sq: Square, color: Color, occupied: BitBoard) -> BitBoard {
+    match piece {
+        Piece::Pawn => get_pawn_attacks(sq, color, !EMPTY),
+        Piece::Knight => get_knight_moves(sq),
+        Piece::Bishop => get_bishop_moves(sq, occupied),
+        Piece::Rook => get_rook_moves(sq, occupied),
+        Piece::Queen => get_bishop_moves(sq, occupied) | get_rook_moves(sq, occupied),
+        Piece::King => get_king_moves(sq),
+    }
+}
+
+pub fn attacked_by(board: &Board, color: Color) -> BitBoard {
+    let occupied = *board.combined();
+    let mut attacks = EMPTY;
+    for piece in chess::ALL_PIECES {
+        for sq in board.pieces(piece) & board.color_combined(color) {
+            attacks |= piece_attacks(piece, sq, color, occupied);
+        }
+    }
+    attacks
+}
+
+fn king_zone(king_sq: Square, color: Color) -> BitBoard {
+    let mut zone = get_king_moves(king_sq) | BitBoard::from_square(king_sq);
+    if let Some(front) = king_sq.forward(color)
+        && let Some(front2) = front.forward(color)
+    {
+        zone |= get_king_moves(front2) | BitBoard::from_square(front2);
+    }
+    zone
+}
+
+// Distance in ranks a pawn has travelled from the owning side's back rank.
+fn relative_rank(sq: Square, color: Color) -> usize {
+    match color {
+        Color::White => sq.get_rank().to_index(),
+        Color::Black => 7 - sq.get_rank().to_index(),
+    }
+}
+
+fn pawn_structure_danger(board: &Board, color: Color, king_sq: Square, params: &EvalParams) -> i32 {
+    let our_pawns = board.pieces(Piece::Pawn) & board.color_combined(color);
+    let their_pawns = board.pieces(Piece::Pawn) & board.color_combined(!color);
+    let king_rank = relative_rank(king_sq, color);
+    let files = get_file(king_sq.get_file()) | get_adjacent_files(king_sq.get_file());
+
+    let mut danger = 0;
+    for file_sq in files & chess::get_rank(chess::Rank::First) {
+        let file = get_file(file_sq.get_file());
+        let ours = our_pawns & file;
+        let theirs = their_pawns & file;
+
+        // Closest of our pawns in front of the king shields it.
+        let shield = ours
+            .into_iter()
+            .map(|sq| relative_rank(sq, color))
+            .filter(|&r| r > king_rank)
+            .min();
+        match shield {
+            Some(r) if r == king_rank + 1 => {}
+            Some(r) if r == king_rank + 2 => danger += params.king_shield_advanced,
+            _ => danger += params.king_shield_missing,
+        }
+
+        // Enemy pawns rolling towards the king, weighted by how close they are.
+        if let Some(r) = theirs.into_iter().map(|sq| relative_rank(sq, color)).min()
+            && r > king_rank
+        {
+            let blocked = shield.is_some_and(|s| s + 1 == r);
+            let weight = params.king_storm_weights[(r - king_rank).min(7)];
+            danger += if blocked { weight / 2 } else { weight };
+        }
+
+        if ours == EMPTY {
+            danger += if theirs == EMPTY { params.king_open_file } else { params.king_semi_open_file };
+        }
+    }
+    danger
+}
+
+// Returns a penalty (<= 0) for `color`'s king. Attack weight is only scaled by the
+// number of attackers once at least two pieces take part, and the combined danger
+// grows quadratically so several moderate weaknesses together cost more than each
+// one on its own.
+pub fn king_safety(board: &Board, color: Color, params: &EvalParams) -> i32 {
+    let them = !color;
+    let king_sq = board.king_square(color);
+    let zone = king_zone(king_sq, color);
+    let occupied = *board.combined();
+    let our_attacks = attacked_by(board, color);
+
+    let mut attackers = 0;
+    let mut attack_weight = 0;
+    let mut safe_checks = 0;
+    for piece in [Piece::Knight, Piece::Bishop, Piece::Rook, Piece::Queen] {
+        let check_squares = piece_attacks(piece, king_sq, color, occupied)
+            & !board.color_combined(them)
+            & !our_attacks;
+        for sq in board.pieces(piece) & board.color_combined(them) {
+            let attacks = piece_attacks(piece, sq, them, occupied);
+            let hits = (attacks & zone).popcnt() as i32;
+            if hits > 0 {
+                attackers += 1;
+                attack_weight += params.king_attack_weights[piece.to_index()] * hits;
+            }
+            if attacks & check_squares != EMPTY {
+                safe_checks += params.king_safe_check_weights[piece.to_index()];
+            }
+        }
+    }
+
+    let attack_danger = if attackers >= 2 { attack_weight * attackers / 2 } else { 0 };
+    let danger = attack_danger + safe_checks + pawn_structure_danger(board, color, king_sq, params);
+    -(danger * danger / params.king_danger_scale.max(1)).min(params.king_max_penalty)
+}
+
+// King safety only matters while there is enough material left to mount an
+// attack, so the term fades out linearly with the game phase.
+pub fn king_safety_midgame(board: &Board, color: Color, params: &EvalParams) -> i32 {
+    king_safety(board, color, params) * game_phase(board) / MAX_PHASE
+}
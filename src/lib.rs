@@ -1,9 +1,73 @@
+pub mod annotate;
+pub mod bench;
+pub mod bitbase;
+pub mod book;
+pub mod bookgen;
+pub mod cli;
+pub mod datagen;
+pub mod dtm;
+pub mod endgame;
+pub mod epd;
+pub mod eval_trace;
+pub mod experience;
+pub mod game;
+pub mod king_safety;
+pub mod matches;
+#[cfg(feature = "nnue")]
+pub mod nnue;
+#[cfg(feature = "nnue")]
+pub mod nnue_train;
+pub mod params;
+pub mod perft;
+pub mod pgn;
+pub mod stats;
+pub mod syzygy;
+pub mod tablebase;
+pub mod tune;
+pub mod uci;
+pub mod xboard;
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
 use std::time::{Duration, Instant};
 
+pub const MATE: i32 = 10000;
+
+// Format of the thinking output printed while searching.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Protocol {
+    #[default]
+    Uci,
+    Xboard,
+}
+
 pub struct SearchInfo {
     pub start: Instant,
     pub time_budget: Duration,
     pub stopped: bool,
+    pub nodes: u64,
+    pub node_limit: Option<u64>,
+    pub tb_hits: u64,
+    // Restricts the root to these moves, e.g. after tablebase filtering.
+    pub root_moves: Option<Vec<chess::ChessMove>>,
+    // Print an `info` line (or CECP thinking line) after every completed
+    // iteration.
+    pub report: bool,
+    pub protocol: Protocol,
+    // Set from another thread to end the search early.
+    pub stop: Option<Arc<AtomicBool>>,
+    // Every completed iteration, in order.
+    pub iterations: Vec<Iteration>,
+    // Evaluation weights, loaded once when the search is set up.
+    pub params: Arc<EvalParams>,
+    // Engine options (Hash, MultiPV, Nullmove), copied when the search is set
+    // up.
+    pub config: uci::EngineConfig,
+    // Ignores the experience file, tablebases and NNUE network, see
+    // `SearchInfo::isolated`.
+    pub isolated: bool,
+    #[cfg(feature = "nnue")]
+    pub nnue: Option<nnue::AccumulatorStack>,
 }
 
 impl SearchInfo {
@@ -12,12 +76,68 @@ impl SearchInfo {
             start: Instant::now(),
             time_budget: Duration::from_millis(time_budget_ms),
             stopped: false,
+            nodes: 0,
+            node_limit: None,
+            tb_hits: 0,
+            root_moves: None,
+            report: false,
+            protocol: Protocol::Uci,
+            stop: None,
+            iterations: Vec::new(),
+            params: active_params(),
+            config: uci::config(),
+            isolated: false,
+            #[cfg(feature = "nnue")]
+            nnue: None,
+        }
+    }
+    // A search that gives the same result whatever the engine has loaded:
+    // built-in evaluation and default options, no experience, tablebases or
+    // NNUE network.
+    pub fn isolated(time_budget_ms: u64) -> Self {
+        SearchInfo {
+            params: Arc::new(EvalParams::default()),
+            config: uci::EngineConfig::default(),
+            isolated: true,
+            ..SearchInfo::new(time_budget_ms)
+        }
+    }
+    #[cfg(feature = "nnue")]
+    pub fn init_eval(&mut self, board: &Board) {
+        self.nnue = nnue::active_network().filter(|_| !self.isolated).map(|net| nnue::AccumulatorStack::new(net, board));
+    }
+    #[cfg(not(feature = "nnue"))]
+    pub fn init_eval(&mut self, _board: &Board) {}
+
+    pub fn evaluate(&self, board: &Board) -> i32 {
+        #[cfg(feature = "nnue")]
+        if let Some(stack) = &self.nnue {
+            return stack.evaluate(board);
+        }
+        evaluate_with(board, &self.params)
+    }
+    pub fn make_move(&mut self, _before: &Board, _after: &Board) {
+        #[cfg(feature = "nnue")]
+        if let Some(stack) = &mut self.nnue {
+            stack.push(_before, _after);
+        }
+    }
+    pub fn unmake_move(&mut self) {
+        #[cfg(feature = "nnue")]
+        if let Some(stack) = &mut self.nnue {
+            stack.pop();
         }
     }
     pub fn should_stop(&mut self) -> bool {
         if self.stopped {
             return true;
         }
+        if self.node_limit.is_some_and(|limit| self.nodes >= limit)
+            || self.stop.as_ref().is_some_and(|stop| stop.load(Ordering::Relaxed))
+        {
+            self.stopped = true;
+            return true;
+        }
         if self.start.elapsed() >= self.time_budget {
             self.stopped = true;
             return true;
@@ -25,10 +145,36 @@ impl SearchInfo {
         false
     }
 }
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Iteration {
+    pub depth: i32,
+    pub best_move: chess::ChessMove,
+    pub score: i32,
+    pub nodes: u64,
+    pub time_ms: u64,
+}
+
+pub struct SearchResult {
+    pub best_move: Option<chess::ChessMove>,
+    pub score: i32,
+    pub depth: i32,
+    pub nodes: u64,
+    pub pv: Vec<chess::ChessMove>,
+    // The MultiPV lines of the last completed depth as (score, line), best
+    // first; the first one is `score` and `pv`.
+    pub lines: Vec<(i32, Vec<chess::ChessMove>)>,
+}
 pub struct PvTable {
     pub pv: Vec<chess::ChessMove>,
 }
 
+impl Default for PvTable {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl PvTable {
     pub fn new() -> Self {
         PvTable { pv: Vec::new() }
@@ -39,10 +185,12 @@ impl PvTable {
     }
 }
 
-fn quiesce(board: &mut Board, mut alpha: i32, beta: i32, ply: usize, history: &mut Vec<Board>) -> i32 {
+#[allow(clippy::only_used_in_recursion)]
+fn quiesce(board: &mut Board, mut alpha: i32, beta: i32, ply: usize, history: &mut Vec<Board>, info: &mut SearchInfo) -> i32 {
    
 
-    let mut stand_pat = evaluation(board);
+    info.nodes += 1;
+    let mut stand_pat = info.evaluate(board);
     if stand_pat >= beta {
         return stand_pat;
     }
@@ -67,8 +215,10 @@ fn quiesce(board: &mut Board, mut alpha: i32, beta: i32, ply: usize, history: &m
         history.push(*board);
 
         *board = board.make_move_new(mv);
-        let score = -quiesce(board, -beta, -alpha, ply + 1, history);
+        info.make_move(history.last().unwrap(), board);
+        let score = -quiesce(board, -beta, -alpha, ply + 1, history, info);
 
+        info.unmake_move();
         *board = history.pop().unwrap();
 
         if score >= beta {
@@ -83,7 +233,9 @@ fn quiesce(board: &mut Board, mut alpha: i32, beta: i32, ply: usize, history: &m
     }
     stand_pat
 }
-use chess::{Board, Piece, Color, ALL_PIECES, MoveGen, BoardStatus};
+use chess::{Board, Color, MoveGen};
+use king_safety::king_safety_midgame;
+use params::{active_params, EvalParams};
 use std::collections::HashMap;
 
 #[derive(Copy, Clone, PartialEq, Eq)]
@@ -102,99 +254,53 @@ pub struct TTEntry {
 
 pub struct TranspositionTable {
     pub table: HashMap<u64, TTEntry>,
+    // Entries that fit in the Hash size; once full, only positions already
+    // in the table are updated.
+    capacity: usize,
+}
+
+impl Default for TranspositionTable {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl TranspositionTable {
     pub fn new() -> Self {
-        TranspositionTable { table: HashMap::new() }
+        Self::with_capacity_mb(uci::DEFAULT_HASH_MB)
+    }
+    pub fn with_capacity_mb(mb: usize) -> Self {
+        let capacity = (mb << 20) / std::mem::size_of::<(u64, TTEntry)>();
+        TranspositionTable { table: HashMap::new(), capacity }
     }
     pub fn get(&self, hash: u64, ply: usize) -> Option<&TTEntry> {
         self.table.get(&hash).filter(|entry| entry.ply <= ply)
     }
     pub fn put(&mut self, hash: u64, value: i32, depth: i32, node_type: NodeType, ply: usize) {
+        if self.table.len() >= self.capacity && !self.table.contains_key(&hash) {
+            return;
+        }
         let entry = TTEntry { value, depth, node_type, ply };
         self.table.insert(hash, entry);
     }
 }
 
 pub fn evaluation(board: &Board) -> i32 {
-    let piece_values = [
-        100, 
-        300, 
-        300, 
-        500, 
-        900, 
-        0,   
-    ];
-    let pawn_pst: [i32; 64] = [
-        0, 0, 0, 0, 0, 0, 0, 0,
-        5, 10, 10, -40, -40, 10, 10, 5,
-        5, -5, -10, 0, 0, -10, -5, 5,
-        0, 0, 0, 50, 50, 0, 0, 0,
-        5, 5, 10, 25, 25, 10, 5, 5,
-        10, 10, 20, 30, 30, 20, 10, 10,
-        50, 50, 50, 50, 50, 50, 50, 50,
-        0, 0, 0, 0, 0, 0, 0, 0
-    ];
-    let knight_pst: [i32; 64] = [
-        -50, -40, -30, -30, -30, -30, -40, -50,
-        -40, -20, 0, 5, 5, 0, -20, -40,
-        -30, 5, 10, 15, 15, 10, 5, -30,
-        -30, 0, 15, 20, 20, 15, 0, -30,
-        -30, 5, 15, 20, 20, 15, 5, -30,
-        -30, 0, 10, 15, 15, 10, 0, -30,
-        -40, -20, 0, 0, 0, 0, -20, -40,
-        -50, -40, -30, -30, -30, -30, -40, -50
-    ];
-    let bishop_pst: [i32; 64] = [
-        -20, -10, -40, -10, -10, -40, -10, -20,
-        -10, 5, 0, 0, 0, 0, 5, -10,
-        -10, 10, 10, 10, 10, 10, 10, -10,
-        -10, 0, 20, 10, 10, 20, 0, -10,
-        -10, 5, 5, 10, 10, 5, 5, -10,
-        -10, 0, 5, 10, 10, 5, 0, -10,
-        -10, 0, 0, 0, 0, 0, 0, -10,
-        -20, -10, -40, -10, -10, -40, -10, -20
-    ];
-    let rook_pst: [i32; 64] = [
-        0, 0, 0, 5, 5, 0, 0, 0,
-        -5, 0, 0, 0, 0, 0, 0, -5,
-        -5, 0, 0, 0, 0, 0, 0, -5,
-        -5, 0, 0, 0, 0, 0, 0, -5,
-        -5, 0, 0, 0, 0, 0, 0, -5,
-        -5, 0, 0, 0, 0, 0, 0, -5,
-        5, 10, 10, 10, 10, 10, 10, 5,
-        0, 0, 0, 0, 0, 0, 0, 0
-    ];
-    let queen_pst: [i32; 64] = [
-        -20, -10, -10, -5, -5, -10, -10, -20,
-        -10, 0, 0, 0, 0, 0, 0, -10,
-        -10, 5, 5, 5, 5, 5, 0, -10,
-        0, 0, 5, 5, 5, 5, 0, -5,
-        -5, 0, 5, 5, 5, 5, 0, -5,
-        -10, 0, 5, 5, 5, 5, 0, -10,
-        -10, 0, 0, 0, 0, 0, 0, -10,
-        -20, -10, -10, -5, -5, -10, -10, -20
-    ];
-    let king_pst: [i32; 64] = [
-        20, 30, 10, 0, 0, 10, 30, 20,
-        20, 20, -10, -10, -10, -10, 20, 20,
-        -10, -20, -20, -20, -20, -20, -20, -10,
-        -20, -30, -30, -40, -40, -30, -30, -20,
-        -30, -40, -40, -50, -50, -40, -40, -30,
-        -30, -40, -40, -50, -50, -40, -40, -30,
-        -30, -40, -40, -50, -50, -40, -40, -30,
-        -30, -40, -40, -50, -50, -40, -40, -30
-    ];
+    evaluate_with(board, &active_params())
+}
+
+pub fn evaluate_with(board: &Board, params: &EvalParams) -> i32 {
+    if let Some(score) = endgame::specialized_eval(board, params) {
+        return score;
+    }
+    let piece_values = params.piece_values;
+    let psts = params.psts();
 
     fn mirror_sq(sq: usize) -> usize {
         sq ^ 56
     }
 
     use chess::Piece::*;
-    use chess::Color;
-    use chess::ALL_PIECES;
-    use chess::Square;
 
     let mut total_score = 0;
     for (piece_idx, &piece) in [Pawn, Knight, Bishop, Rook, Queen, King].iter().enumerate() {
@@ -202,14 +308,7 @@ pub fn evaluation(board: &Board) -> i32 {
         let our_bb = board.pieces(piece) & board.color_combined(board.side_to_move());
         let their_bb = board.pieces(piece) & board.color_combined(!board.side_to_move());
 
-        let pst = match piece {
-            Pawn => &pawn_pst,
-            Knight => &knight_pst,
-            Bishop => &bishop_pst,
-            Rook => &rook_pst,
-            Queen => &queen_pst,
-            King => &king_pst,
-        };
+        let pst = psts[piece_idx];
 
         for sq in our_bb {
             let idx = sq.to_index();
@@ -222,7 +321,10 @@ pub fn evaluation(board: &Board) -> i32 {
             total_score -= pst[idx];
         }
     }
-    total_score
+    let stm = board.side_to_move();
+    total_score += king_safety_midgame(board, stm, params) - king_safety_midgame(board, !stm, params);
+    let strong = if total_score >= 0 { stm } else { !stm };
+    total_score * endgame::scale_factor(board, strong, params) / endgame::SCALE_NORMAL
 }
 
 pub fn axelrot(
@@ -245,66 +347,192 @@ pub fn axelrot(
     };
     let move_time = (time_left / 30).max(10) + inc;
     let mut info = SearchInfo::new(move_time);
+    info.report = true;
+    match think(board, max_depth, &mut info).best_move {
+        Some(mv) => mv.to_string(),
+        None => "0000".to_string(),
+    }
+}
+
+// A game move search as both protocol front ends run it: tablebase root
+// filtering first, and the result is recorded in the experience file unless
+// UCI_AnalyseMode is on.
+pub fn think(board: &Board, max_depth: i32, info: &mut SearchInfo) -> SearchResult {
+    // With DTZ tables at the root, only the moves that keep the best
+    // tablebase result are searched.
+    if let Some(ranked) = syzygy::rank_root_moves(board) {
+        info.tb_hits += ranked.len() as u64;
+        info.root_moves = Some(syzygy::best_root_moves(&ranked));
+    }
+    let result = search(board, max_depth, info);
+    if let Some(mv) = result.best_move
+        && !info.config.analyse_mode
+    {
+        experience::record_search(board, mv, result.depth, result.score);
+    }
+    result
+}
+
+// Iterative deepening from the root. Limits come from `info` (time budget and
+// optional node limit); the result holds the last fully searched depth.
+pub fn search(board: &Board, max_depth: i32, info: &mut SearchInfo) -> SearchResult {
+    info.init_eval(board);
 
     let mut best_move: Option<chess::ChessMove> = None;
-    let mut best_value = i32::MIN + 1;
+    let mut best_value = 0;
+    let mut completed_depth = 0;
+    let mut best_lines = Vec::new();
     let mut pv_table = PvTable::new();
     let mut board = *board;
     let mut history = Vec::new();
-    let mut tt = TranspositionTable::new();
+    let mut tt = TranspositionTable::with_capacity_mb(info.config.hash_mb);
+    let multi_pv = info.config.multi_pv.max(1);
+    // Root moves the experience file knows about: tried first, best stored
+    // score first, and their game results shift the move's score.
+    let learned: Vec<_> = MoveGen::new_legal(&board)
+        .filter(|_| !info.isolated)
+        .filter_map(|m| experience::lookup(&board, m).map(|r| (m, r)))
+        .collect();
+    let root_bias = |mv: chess::ChessMove| learned.iter().find(|(m, _)| *m == mv).map_or(0, |(_, r)| r.bias());
     for depth in 1..=max_depth {
         if info.should_stop() { break; }
-        let mut pv = Vec::new();
         let mut pv_temp = Vec::new();
-        let mut alpha = i32::MIN + 1;
         let beta = i32::MAX;
+        // Every searched root move with its score and line, best first. The
+        // window opens below the MultiPV-th best score so each of those lines
+        // gets an exact score.
+        let mut lines: Vec<(i32, Vec<chess::ChessMove>)> = Vec::new();
 
         let mut moves: Vec<_> = MoveGen::new_legal(&board).collect();
-        if let Some(pv_move) = pv_table.pv.get(0) {
-            if let Some(pos) = moves.iter().position(|m| m == pv_move) {
-                let mv = moves.remove(pos);
-                moves.insert(0, mv);
-            }
+        if let Some(root_moves) = &info.root_moves {
+            moves.retain(|m| root_moves.contains(m));
+        }
+        moves.sort_by_key(|m| {
+            std::cmp::Reverse(learned.iter().find(|(l, _)| l == m).map_or(i32::MIN, |(_, r)| r.score as i32))
+        });
+        if let Some(pv_move) = pv_table.pv.first()
+            && let Some(pos) = moves.iter().position(|m| m == pv_move)
+        {
+            let mv = moves.remove(pos);
+            moves.insert(0, mv);
         }
-
-        let mut current_best_move: Option<chess::ChessMove> = None;
-        let mut current_best_value = i32::MIN + 1;
 
         for &mv in &moves {
             if info.should_stop() { break; }
             history.push(board);
             board = board.make_move_new(mv);
+            info.make_move(history.last().unwrap(), &board);
             pv_temp.clear();
-            let value = -negamax(&mut board, -beta, -alpha, depth - 1, 1, &mut history, &mut pv_temp, &mut pv, &mut info, &mut tt);
+            let alpha = lines.get(multi_pv - 1).map_or(i32::MIN + 1, |(value, _)| *value);
+            // The window is shifted by the bias so alpha-beta stays sound;
+            // decided (mate or tablebase) scores are left untouched.
+            let bias = root_bias(mv);
+            let (lo, hi) = (alpha.saturating_sub(bias).max(-i32::MAX), beta.saturating_sub(bias));
+            let value = -negamax(&mut board, -hi, -lo, depth - 1, 1, &mut history, &mut pv_temp, &mut Vec::new(), info, &mut tt);
+            let value = if value.abs() < tablebase::TB_WIN - 1000 { value + bias } else { value };
+            info.unmake_move();
             board = history.pop().unwrap();
 
             if info.should_stop() { break; }
-            if value > current_best_value || current_best_move.is_none() {
-                current_best_value = value;
-                current_best_move = Some(mv);
-                pv.clear();
-                pv.push(mv);
-                pv.extend_from_slice(&pv_temp);
-            }
-            if value > alpha {
-                alpha = value;
-            }
+            let mut line = vec![mv];
+            line.extend_from_slice(&pv_temp);
+            let pos = lines.partition_point(|(v, _)| *v >= value);
+            lines.insert(pos, (value, line));
         }
-        pv_table.set_pv(&pv);
 
-        if !info.should_stop() && current_best_move.is_some() {
-            best_move = current_best_move;
-            best_value = current_best_value;
+        if !info.should_stop() && !lines.is_empty() {
+            let pv = &lines[0].1;
+            best_move = Some(pv[0]);
+            best_value = lines[0].0;
+            completed_depth = depth;
+            pv_table.set_pv(pv);
+            best_lines = lines[..lines.len().min(multi_pv)].to_vec();
+            info.iterations.push(Iteration {
+                depth,
+                best_move: pv[0],
+                score: best_value,
+                nodes: info.nodes,
+                time_ms: info.start.elapsed().as_millis() as u64,
+            });
+            if info.report {
+                let elapsed = info.start.elapsed().as_millis() as u64;
+                let pv: Vec<String> = pv_table.pv.iter().map(|m| m.to_string()).collect();
+                match info.protocol {
+                    // CECP: ply, score, time in centiseconds, nodes, PV.
+                    Protocol::Xboard => println!(
+                        "{} {} {} {} {}",
+                        depth,
+                        xboard::format_score(best_value),
+                        elapsed / 10,
+                        info.nodes,
+                        pv.join(" ")
+                    ),
+                    Protocol::Uci if multi_pv == 1 => println!(
+                        "info depth {} score {} nodes {} nps {} time {} tbhits {} pv {}",
+                        depth,
+                        format_score(best_value),
+                        info.nodes,
+                        info.nodes * 1000 / elapsed.max(1),
+                        elapsed,
+                        info.tb_hits,
+                        pv.join(" ")
+                    ),
+                    Protocol::Uci => {
+                        for (i, (value, line)) in lines.iter().take(multi_pv).enumerate() {
+                            let line: Vec<String> = line.iter().map(|m| m.to_string()).collect();
+                            println!(
+                                "info depth {} multipv {} score {} nodes {} nps {} time {} tbhits {} pv {}",
+                                depth,
+                                i + 1,
+                                format_score(*value),
+                                info.nodes,
+                                info.nodes * 1000 / elapsed.max(1),
+                                elapsed,
+                                info.tb_hits,
+                                line.join(" ")
+                            );
+                        }
+                    }
+                }
+            }
         }
     }
 
-    if let Some(mv) = best_move {
-        mv.to_string()
-    } else {
-        "0000".to_string()
+    SearchResult {
+        best_move,
+        score: best_value,
+        depth: completed_depth,
+        nodes: info.nodes,
+        pv: pv_table.pv,
+        lines: best_lines,
+    }
+}
+
+// Moves to mate for a mate score, negative when the side to move is the one
+// getting mated; None for any other score.
+pub fn mate_in(score: i32) -> Option<i32> {
+    if score.abs() <= MATE - 500 {
+        return None;
+    }
+    let moves = (MATE - score.abs() + 1) / 2;
+    Some(if score > 0 { moves } else { -moves })
+}
+
+// The inverse of `mate_in`: the score of mating in `moves`, or of being mated
+// in `-moves` when it is negative.
+pub fn mate_score(moves: i32) -> i32 {
+    if moves > 0 { MATE - 2 * moves + 1 } else { -MATE - 2 * moves }
+}
+
+// UCI score string: "mate N" (in moves) for forced mates, otherwise "cp N".
+pub fn format_score(score: i32) -> String {
+    match mate_in(score) {
+        Some(moves) => format!("mate {}", moves),
+        None => format!("cp {}", score),
     }
 }
 
+#[allow(clippy::too_many_arguments)]
 pub fn negamax(
     board: &mut Board,
     mut alpha: i32,
@@ -321,39 +549,90 @@ pub fn negamax(
         return 0;
     }
     if depth <= 0 {
-        return quiesce(board, alpha, beta, ply, history);
+        return quiesce(board, alpha, beta, ply, history, info);
     }
+    info.nodes += 1;
     if ply > 0 && history.iter().any(|b| b == board) {
         return 0;
     }
-    let hash = board.get_hash();
-    if let Some(entry) = tt.get(hash, ply) {
-        if entry.depth >= depth {
-            match entry.node_type {
-                NodeType::Exact => return entry.value,
-                NodeType::LowerBound => if entry.value > beta { return entry.value; },
-                NodeType::UpperBound => if entry.value <= alpha { return entry.value; },
+    if ply > 0
+        && !info.isolated
+        && let Some(score) = tablebase::probe(board, ply)
+    {
+        info.tb_hits += 1;
+        return score;
+    }
+    // Bitbase draws are exact. Won pawn endings are cut too, scored by the
+    // endgame eval so the search still prefers advancing the pawn; won
+    // KRK/KQK positions are searched normally so the mate is found.
+    if ply > 0
+        && let Some(result) = bitbase::probe(board)
+    {
+        match result {
+            bitbase::BitbaseResult::Draw => return 0,
+            bitbase::BitbaseResult::Win(_) if *board.pieces(chess::Piece::Pawn) != chess::EMPTY => {
+                return evaluate_with(board, &info.params);
             }
+            bitbase::BitbaseResult::Win(_) => {}
+        }
+    }
+    let hash = board.get_hash();
+    if let Some(entry) = tt.get(hash, ply)
+        && entry.depth >= depth
+    {
+        match entry.node_type {
+            NodeType::Exact => return entry.value,
+            NodeType::LowerBound => if entry.value > beta { return entry.value; },
+            NodeType::UpperBound => if entry.value <= alpha { return entry.value; },
+        }
+    }
+    // Null move: if passing still fails high at reduced depth, the position is
+    // good enough to cut. Not in check, not with only pawns left (zugzwang),
+    // not twice in a row (a real move always changes the occupancy) and not
+    // against mate scores.
+    if info.config.nullmove
+        && ply > 0
+        && depth >= 3
+        && beta.abs() < tablebase::TB_WIN - 1000
+        && *board.checkers() == chess::EMPTY
+        && has_non_pawn_material(board)
+        && history.last().is_none_or(|b| b.combined() != board.combined())
+        && let Some(null) = board.null_move()
+    {
+        history.push(*board);
+        *board = null;
+        info.make_move(history.last().unwrap(), board);
+        let score = -negamax(board, -beta, -beta + 1, depth - 3, ply + 1, history, pv_temp, &mut Vec::new(), info, tt);
+        info.unmake_move();
+        *board = history.pop().unwrap();
+        if info.should_stop() {
+            return 0;
+        }
+        if score >= beta {
+            return beta;
         }
     }
     let moves: Vec<_> = MoveGen::new_legal(board).collect();
     if moves.is_empty() {
         return if board.checkers().popcnt() > 0 {
-            -10000 + ply as i32
+            -MATE + ply as i32
         } else {
             0
         };
     }
-    let mut best_value = -10000;
-    let mut found_pv = false;
+    let mut best_value = -MATE;
     for mv in moves {
         if info.should_stop() {
             break;
         }
         history.push(*board);
         *board = board.make_move_new(mv);
+        info.make_move(history.last().unwrap(), board);
         pv_temp.clear();
-        let score = -negamax(board, -beta, -alpha, depth - 1, ply + 1, history, pv_temp, pv, info, tt);
+        // The child writes its line into `pv_temp` and gets a fresh scratch
+        // buffer for its own children.
+        let score = -negamax(board, -beta, -alpha, depth - 1, ply + 1, history, pv_temp, &mut Vec::new(), info, tt);
+        info.unmake_move();
         *board = history.pop().unwrap();
         if info.should_stop() {
             break;
@@ -369,7 +648,6 @@ pub fn negamax(
                 pv.clear();
                 pv.push(mv);
                 pv.extend_from_slice(pv_temp);
-                found_pv = true;
             }
         }
     }
@@ -377,3 +655,8 @@ pub fn negamax(
     tt.put(hash, best_value, depth, node_type, ply);
     best_value
 }
+
+fn has_non_pawn_material(board: &Board) -> bool {
+    let pieces = *board.combined() ^ *board.pieces(chess::Piece::Pawn) ^ *board.pieces(chess::Piece::King);
+    pieces & *board.color_combined(board.side_to_move()) != chess::EMPTY
+}
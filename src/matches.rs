@@ -0,0 +1,604 @@
+use crate::book::Book;
+use crate::datagen::Rng;
+use crate::game::GameState;
+use crate::pgn::{eval_comment, write_game, Game, Node};
+use crate::stats::{Sprt, SprtStatus};
+use crate::tablebase::{self, TB_WIN};
+use crate::{epd, mate_score};
+use chess::{Board, ChessMove, Color, MoveGen};
+use std::fs::OpenOptions;
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::process::{Child, ChildStdin, Command, Stdio};
+use std::str::FromStr;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+// Engine-vs-engine matches between two UCI engines run as subprocesses,
+// in the spirit of cutechess-cli.
+
+// How long an engine gets to answer `uci` and `isready`.
+const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(10);
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct EngineSpec {
+    pub name: String,
+    pub cmd: PathBuf,
+    pub args: Vec<String>,
+    // Sent as `setoption` after the handshake, in order.
+    pub options: Vec<(String, String)>,
+    // Extra limits sent along with the clock on every `go`.
+    pub depth: Option<u32>,
+    pub nodes: Option<u64>,
+}
+
+// Clock for each side: `base_ms` at the start plus `inc_ms` after every move.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TimeControl {
+    pub base_ms: u64,
+    pub inc_ms: u64,
+}
+
+impl FromStr for TimeControl {
+    type Err = String;
+
+    // "base+inc" in seconds, e.g. "300+0" or "10+0.1".
+    fn from_str(text: &str) -> Result<Self, String> {
+        let (base, inc) = text.split_once('+').unwrap_or((text, "0"));
+        let ms = |v: &str| v.parse::<f64>().ok().filter(|v| *v >= 0.0).map(|v| (v * 1000.0).round() as u64);
+        match (ms(base), ms(inc)) {
+            (Some(base_ms), Some(inc_ms)) if base_ms > 0 => Ok(TimeControl { base_ms, inc_ms }),
+            _ => Err(format!("invalid time control {}", text)),
+        }
+    }
+}
+
+// Draw once both engines have scored within `score` centipawns of zero for
+// `movecount` consecutive moves each, from move `movenumber` on.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct DrawAdjudication {
+    pub movenumber: usize,
+    pub movecount: usize,
+    pub score: i32,
+}
+
+// Resignation once one engine has scored `score` centipawns or worse for
+// `movecount` consecutive moves and its opponent agrees.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ResignAdjudication {
+    pub movecount: usize,
+    pub score: i32,
+}
+
+pub enum Openings {
+    // Start positions, played in file order.
+    Positions(Vec<Board>),
+    // Weighted random Polyglot book moves, up to `plies` deep.
+    Book { book: Book, plies: usize },
+}
+
+impl Openings {
+    // An `.epd` file of positions or a Polyglot `.bin` book.
+    pub fn open(path: &Path, book_plies: usize) -> Result<Self, String> {
+        if path.extension().is_some_and(|e| e == "bin") {
+            return Ok(Openings::Book { book: Book::open(path)?, plies: book_plies });
+        }
+        let text = std::fs::read_to_string(path).map_err(|e| format!("cannot read {}: {}", path.display(), e))?;
+        let boards = epd::parse(&text)
+            .into_iter()
+            .map(|r| r.map(|r| r.board))
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| format!("{}: {}", path.display(), e))?;
+        if boards.is_empty() {
+            return Err(format!("no positions in {}", path.display()));
+        }
+        Ok(Openings::Positions(boards))
+    }
+
+    // Start position and book moves for the `pair`-th opening.
+    fn pick(&self, pair: usize, seed: u64) -> (Board, Vec<ChessMove>) {
+        match self {
+            Openings::Positions(boards) => (boards[pair % boards.len()], Vec::new()),
+            Openings::Book { book, plies } => {
+                let mut rng = Rng::new(seed ^ (pair as u64 + 1).wrapping_mul(0x9e37_79b9_7f4a_7c15));
+                let mut board = Board::default();
+                let mut moves = Vec::new();
+                while moves.len() < *plies
+                    && let Some(mv) = book.pick(&board, crate::book::Selection::Weighted, &mut rng)
+                {
+                    board = board.make_move_new(mv);
+                    moves.push(mv);
+                }
+                (Board::default(), moves)
+            }
+        }
+    }
+}
+
+pub struct MatchConfig {
+    pub engines: [EngineSpec; 2],
+    pub games: usize,
+    pub concurrency: usize,
+    pub tc: TimeControl,
+    // Overstepping the clock by more than this loses on time.
+    pub time_margin_ms: u64,
+    pub openings: Option<Openings>,
+    pub draw: Option<DrawAdjudication>,
+    pub resign: Option<ResignAdjudication>,
+    // Adjudicate positions the loaded tablebases cover.
+    pub tb_adjudication: bool,
+    // Draw after this many full moves; 0 for no limit.
+    pub max_moves: usize,
+    pub pgn_out: Option<PathBuf>,
+    // Stops the match early once the test concludes; `games` is then the
+    // most that will be played.
+    pub sprt: Option<Sprt>,
+    pub event: String,
+    pub seed: u64,
+}
+
+impl MatchConfig {
+    pub fn new(engines: [EngineSpec; 2]) -> Self {
+        MatchConfig {
+            engines,
+            games: 2,
+            concurrency: 1,
+            tc: TimeControl { base_ms: 10_000, inc_ms: 100 },
+            time_margin_ms: 100,
+            openings: None,
+            draw: None,
+            resign: None,
+            tb_adjudication: false,
+            max_moves: 0,
+            pgn_out: None,
+            sprt: None,
+            event: "axelrot match".to_string(),
+            seed: 1,
+        }
+    }
+}
+
+// One played move with what the engine reported for it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PlayedMove {
+    pub mv: ChessMove,
+    // From the mover's point of view; None for book moves.
+    pub score: Option<i32>,
+    pub depth: u32,
+    pub time_ms: u64,
+}
+
+#[derive(Clone, Debug)]
+pub struct GameRecord {
+    // Games come in pairs sharing an opening, with colours swapped.
+    pub round: usize,
+    pub white: String,
+    pub black: String,
+    // Whether the first engine had white.
+    pub first_white: bool,
+    pub start: Board,
+    pub moves: Vec<PlayedMove>,
+    pub result: &'static str,
+    pub reason: String,
+}
+
+impl GameRecord {
+    pub fn pair(&self) -> usize {
+        self.round / 2
+    }
+
+    // 2 for a win of the first engine, 1 for a draw, 0 for a loss.
+    pub fn first_points(&self) -> u32 {
+        match (self.result, self.first_white) {
+            ("1-0", true) | ("0-1", false) => 2,
+            ("1/2-1/2", _) => 1,
+            _ => 0,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Tally {
+    pub wins: u32,
+    pub draws: u32,
+    pub losses: u32,
+    // Completed game pairs by the first engine's pair score in half points.
+    pub pairs: [u32; 5],
+}
+
+impl Tally {
+    pub fn new(games: &[GameRecord]) -> Self {
+        let mut tally = Tally::default();
+        let mut pairs = std::collections::HashMap::new();
+        for game in games {
+            match game.first_points() {
+                2 => tally.wins += 1,
+                1 => tally.draws += 1,
+                _ => tally.losses += 1,
+            }
+            pairs.entry(game.pair()).or_insert_with(Vec::new).push(game.first_points());
+        }
+        for points in pairs.values().filter(|p| p.len() == 2) {
+            tally.pairs[(points[0] + points[1]) as usize] += 1;
+        }
+        tally
+    }
+
+    pub fn wdl(&self) -> [u32; 3] {
+        [self.wins, self.draws, self.losses]
+    }
+
+    pub fn sprt_status(&self, sprt: &Sprt) -> SprtStatus {
+        sprt.status(sprt.llr_pentanomial(&self.pairs))
+    }
+
+    pub fn games(&self) -> u32 {
+        self.wins + self.draws + self.losses
+    }
+}
+
+pub fn to_pgn(game: &GameRecord, event: &str) -> Game {
+    let mut pgn = Game::new(Vec::new());
+    pgn.set_tag("Event", event);
+    pgn.set_tag("Round", &(game.round + 1).to_string());
+    pgn.set_tag("White", &game.white);
+    pgn.set_tag("Black", &game.black);
+    if game.start != Board::default() {
+        pgn.set_tag("SetUp", "1");
+        pgn.set_tag("FEN", &game.start.to_string());
+    }
+    pgn.set_tag("Termination", &game.reason);
+    for played in &game.moves {
+        let mut node = Node::new(played.mv);
+        node.after = Some(match played.score {
+            Some(score) => eval_comment(score, played.depth, played.time_ms),
+            None => "book".to_string(),
+        });
+        pgn.push(node);
+    }
+    pgn.result = game.result.to_string();
+    pgn
+}
+
+pub struct Engine {
+    spec: EngineSpec,
+    child: Child,
+    stdin: ChildStdin,
+    lines: Receiver<String>,
+}
+
+pub struct Reply {
+    pub mv: String,
+    pub score: Option<i32>,
+    pub depth: u32,
+}
+
+impl Engine {
+    pub fn start(spec: &EngineSpec) -> Result<Engine, String> {
+        let mut child = Command::new(&spec.cmd)
+            .args(&spec.args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn()
+            .map_err(|e| format!("cannot start {}: {}", spec.cmd.display(), e))?;
+        let stdin = child.stdin.take().unwrap();
+        let stdout = child.stdout.take().unwrap();
+        let (sender, lines) = mpsc::channel();
+        std::thread::spawn(move || {
+            for line in BufReader::new(stdout).lines().map_while(Result::ok) {
+                if sender.send(line).is_err() {
+                    break;
+                }
+            }
+        });
+        let mut engine = Engine { spec: spec.clone(), child, stdin, lines };
+        engine.send("uci")?;
+        engine.wait_for("uciok", HANDSHAKE_TIMEOUT)?;
+        for (name, value) in &spec.options {
+            engine.send(&format!("setoption name {} value {}", name, value))?;
+        }
+        engine.sync()?;
+        Ok(engine)
+    }
+
+    pub fn name(&self) -> &str {
+        &self.spec.name
+    }
+
+    fn send(&mut self, command: &str) -> Result<(), String> {
+        writeln!(self.stdin, "{}", command).and_then(|_| self.stdin.flush()).map_err(|_| format!("{} disconnects", self.spec.name))
+    }
+
+    fn recv(&self, deadline: Instant) -> Result<String, String> {
+        match self.lines.recv_timeout(deadline.saturating_duration_since(Instant::now())) {
+            Ok(line) => Ok(line),
+            Err(RecvTimeoutError::Timeout) => Err(format!("{} does not respond", self.spec.name)),
+            Err(RecvTimeoutError::Disconnected) => Err(format!("{} disconnects", self.spec.name)),
+        }
+    }
+
+    fn wait_for(&self, token: &str, timeout: Duration) -> Result<(), String> {
+        let deadline = Instant::now() + timeout;
+        while self.recv(deadline)?.trim() != token {}
+        Ok(())
+    }
+
+    fn sync(&mut self) -> Result<(), String> {
+        self.send("isready")?;
+        self.wait_for("readyok", HANDSHAKE_TIMEOUT)
+    }
+
+    pub fn new_game(&mut self) -> Result<(), String> {
+        self.send("ucinewgame")?;
+        self.sync()
+    }
+
+    // Asks for a move; an Err after `timeout` means the engine lost on time.
+    pub fn go(&mut self, position: &str, clock: &str, timeout: Duration) -> Result<Reply, String> {
+        self.send(position)?;
+        let mut go = format!("go {}", clock);
+        if let Some(depth) = self.spec.depth {
+            go += &format!(" depth {}", depth);
+        }
+        if let Some(nodes) = self.spec.nodes {
+            go += &format!(" nodes {}", nodes);
+        }
+        self.send(&go)?;
+        let deadline = Instant::now() + timeout;
+        let (mut score, mut depth) = (None, 0);
+        loop {
+            let line = self.recv(deadline)?;
+            let mut tokens = line.split_whitespace();
+            match tokens.next() {
+                Some("info") => {
+                    while let Some(token) = tokens.next() {
+                        let mut value = || tokens.next().and_then(|v| v.parse::<i32>().ok());
+                        match token {
+                            "depth" => depth = value().unwrap_or(0) as u32,
+                            "cp" => score = value().or(score),
+                            "mate" => score = value().map(mate_score).or(score),
+                            // The principal variation closes the line.
+                            "pv" => break,
+                            _ => {}
+                        }
+                    }
+                }
+                Some("bestmove") => {
+                    let mv = tokens.next().unwrap_or("").to_string();
+                    return Ok(Reply { mv, score, depth });
+                }
+                _ => {}
+            }
+        }
+    }
+
+    pub fn quit(mut self) {
+        let _ = self.send("quit");
+        let deadline = Instant::now() + Duration::from_millis(500);
+        while Instant::now() < deadline {
+            if let Ok(Some(_)) = self.child.try_wait() {
+                return;
+            }
+            std::thread::sleep(Duration::from_millis(10));
+        }
+    }
+}
+
+impl Drop for Engine {
+    fn drop(&mut self) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+// Engines for one worker, restarted after a crash or time loss.
+struct Seats {
+    specs: [EngineSpec; 2],
+    engines: [Option<Engine>; 2],
+}
+
+impl Seats {
+    fn engine(&mut self, index: usize) -> Result<&mut Engine, String> {
+        if self.engines[index].is_none() {
+            self.engines[index] = Some(Engine::start(&self.specs[index])?);
+        }
+        Ok(self.engines[index].as_mut().unwrap())
+    }
+}
+
+fn uci_position(start: &Board, moves: &[PlayedMove]) -> String {
+    let mut position = if *start == Board::default() { "position startpos".to_string() } else { format!("position fen {}", start) };
+    if !moves.is_empty() {
+        position += " moves";
+        for played in moves {
+            position += &format!(" {}", played.mv);
+        }
+    }
+    position
+}
+
+// Adjudication by the rules, the tablebases and the engines' scores.
+fn adjudicate(config: &MatchConfig, state: &GameState, moves: &[PlayedMove]) -> Option<(&'static str, String)> {
+    if let Some(end) = state.end() {
+        return Some((end.result, end.reason.to_string()));
+    }
+    let stm = state.board.side_to_move();
+    let win_for = |color: Color| if color == Color::White { "1-0" } else { "0-1" };
+    if config.tb_adjudication
+        && let Some(score) = tablebase::probe(&state.board, 0)
+    {
+        return Some(match score {
+            s if s >= TB_WIN - 500 => (win_for(stm), "tablebase win".to_string()),
+            s if s <= -(TB_WIN - 500) => (win_for(!stm), "tablebase win".to_string()),
+            _ => ("1/2-1/2", "tablebase draw".to_string()),
+        });
+    }
+    // Scores of the most recent engine moves, newest first; the first is the
+    // side that just moved.
+    let scores: Vec<Option<i32>> = moves.iter().rev().map(|m| m.score).collect();
+    if let Some(draw) = config.draw
+        && moves.len() / 2 + 1 >= draw.movenumber
+        && scores.len() >= 2 * draw.movecount
+        && scores[..2 * draw.movecount].iter().all(|s| s.is_some_and(|s| s.abs() <= draw.score))
+    {
+        return Some(("1/2-1/2", "adjudication".to_string()));
+    }
+    if let Some(resign) = config.resign
+        && scores.len() >= 2 * resign.movecount
+    {
+        // Even entries belong to the side that just moved, odd ones to the
+        // side to move.
+        let window = &scores[..2 * resign.movecount];
+        let all = |skip: usize, f: &dyn Fn(i32) -> bool| window.iter().skip(skip).step_by(2).all(|s| s.is_some_and(f));
+        let lost = |s: i32| s <= -resign.score;
+        let won = |s: i32| s >= resign.score;
+        if all(0, &lost) && all(1, &won) {
+            return Some((win_for(stm), "adjudication".to_string()));
+        }
+        if all(1, &lost) && all(0, &won) {
+            return Some((win_for(!stm), "adjudication".to_string()));
+        }
+    }
+    if config.max_moves > 0 && moves.len() >= 2 * config.max_moves {
+        return Some(("1/2-1/2", "max moves".to_string()));
+    }
+    None
+}
+
+fn play_game(config: &MatchConfig, seats: &mut Seats, round: usize) -> GameRecord {
+    let pair = round / 2;
+    let first_white = round.is_multiple_of(2);
+    let (start, book) = match &config.openings {
+        Some(openings) => openings.pick(pair, config.seed),
+        None => (Board::default(), Vec::new()),
+    };
+    let (white, black) = if first_white { (0, 1) } else { (1, 0) };
+    let mut record = GameRecord {
+        round,
+        white: seats.specs[white].name.clone(),
+        black: seats.specs[black].name.clone(),
+        first_white,
+        start,
+        moves: Vec::new(),
+        result: "*",
+        reason: String::new(),
+    };
+    let mut state = GameState::new(start);
+    for mv in book {
+        state.play(mv);
+        record.moves.push(PlayedMove { mv, score: None, depth: 0, time_ms: 0 });
+    }
+    let win_for = |color: Color| if color == Color::White { "1-0" } else { "0-1" };
+
+    for seat in [0, 1] {
+        if let Err(e) = seats.engine(seat).and_then(|e| e.new_game()) {
+            seats.engines[seat] = None;
+            let loser = if seat == white { Color::White } else { Color::Black };
+            (record.result, record.reason) = (win_for(!loser), e);
+            return record;
+        }
+    }
+
+    let mut clocks = [config.tc.base_ms as i64; 2];
+    let (result, reason) = loop {
+        if let Some(end) = adjudicate(config, &state, &record.moves) {
+            break end;
+        }
+        let stm = state.board.side_to_move();
+        let side = stm.to_index();
+        let seat = if stm == Color::White { white } else { black };
+        let clock = format!(
+            "wtime {} btime {} winc {} binc {}",
+            clocks[Color::White.to_index()].max(1),
+            clocks[Color::Black.to_index()].max(1),
+            config.tc.inc_ms,
+            config.tc.inc_ms
+        );
+        let position = uci_position(&record.start, &record.moves);
+        let timeout = Duration::from_millis(clocks[side].max(0) as u64 + config.time_margin_ms + 1000);
+        let started = Instant::now();
+        let reply = seats.engine(seat).and_then(|e| e.go(&position, &clock, timeout));
+        let elapsed = started.elapsed().as_millis() as u64;
+        let reply = match reply {
+            Ok(reply) => reply,
+            Err(e) => {
+                // A crashed or hung engine is replaced before the next game.
+                seats.engines[seat] = None;
+                let reason = if e.ends_with("does not respond") { format!("{} loses on time", seats.specs[seat].name) } else { e };
+                break (win_for(!stm), reason);
+            }
+        };
+        clocks[side] -= elapsed as i64;
+        if clocks[side] < -(config.time_margin_ms as i64) {
+            break (win_for(!stm), format!("{} loses on time", seats.specs[seat].name));
+        }
+        clocks[side] += config.tc.inc_ms as i64;
+        let legal = ChessMove::from_str(&reply.mv).ok().filter(|mv| MoveGen::new_legal(&state.board).any(|m| m == *mv));
+        let Some(mv) = legal else {
+            break (win_for(!stm), format!("{} makes an illegal move: {}", seats.specs[seat].name, reply.mv));
+        };
+        state.play(mv);
+        record.moves.push(PlayedMove { mv, score: reply.score, depth: reply.depth, time_ms: elapsed });
+    };
+    (record.result, record.reason) = (result, reason);
+    record
+}
+
+// Plays `config.games` games on `config.concurrency` workers, each with its
+// own pair of engine processes. Finished games are appended to the PGN file
+// and passed to `report`; the returned games are in round order. Once the
+// SPRT concludes no new games are started.
+pub fn run(config: &MatchConfig, report: impl Fn(&GameRecord, &Tally) + Sync) -> Result<Vec<GameRecord>, String> {
+    // Fail early on engines that cannot even start.
+    for spec in &config.engines {
+        Engine::start(spec)?.quit();
+    }
+    let pgn = match &config.pgn_out {
+        Some(path) => Some(Mutex::new(
+            OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(path)
+                .map_err(|e| format!("cannot write {}: {}", path.display(), e))?,
+        )),
+        None => None,
+    };
+    let next = AtomicUsize::new(0);
+    let stop = AtomicBool::new(false);
+    let games = Mutex::new(Vec::with_capacity(config.games));
+    std::thread::scope(|scope| {
+        for _ in 0..config.concurrency.clamp(1, config.games.max(1)) {
+            scope.spawn(|| {
+                let mut seats = Seats { specs: config.engines.clone(), engines: [None, None] };
+                loop {
+                    let round = next.fetch_add(1, Ordering::Relaxed);
+                    if round >= config.games || stop.load(Ordering::Relaxed) {
+                        break;
+                    }
+                    let game = play_game(config, &mut seats, round);
+                    if let Some(pgn) = &pgn
+                        && let Ok(text) = write_game(&to_pgn(&game, &config.event))
+                    {
+                        let _ = pgn.lock().unwrap().write_all(text.as_bytes());
+                    }
+                    let mut games = games.lock().unwrap();
+                    games.push(game);
+                    let tally = Tally::new(&games);
+                    if config.sprt.is_some_and(|sprt| tally.sprt_status(&sprt) != SprtStatus::Continue) {
+                        stop.store(true, Ordering::Relaxed);
+                    }
+                    report(games.last().unwrap(), &tally);
+                }
+                for engine in seats.engines.into_iter().flatten() {
+                    engine.quit();
+                }
+            });
+        }
+    });
+    let mut games = games.into_inner().unwrap();
+    games.sort_by_key(|g| g.round);
+    Ok(games)
+}
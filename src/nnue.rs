@@ -0,0 +1,341 @@
+use chess::{Board, Color, Piece, Square, ALL_PIECES};
+use std::fs;
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, LazyLock, RwLock};
+
+// Network layout: HalfKA features (own king square x piece x square) feed one
+// hidden layer per perspective; the side-to-move and opponent halves are
+// concatenated, clipped to [0, QA] and fed into a single output neuron.
+pub const MAGIC: &[u8; 4] = b"AXNN";
+pub const VERSION: u32 = 1;
+pub const NUM_FEATURES: usize = 64 * 12 * 64;
+pub const QA: i32 = 255;
+pub const QB: i32 = 64;
+pub const SCALE: i32 = 400;
+pub const MAX_HIDDEN: usize = 4096;
+
+pub struct Network {
+    pub hidden: usize,
+    pub feature_weights: Vec<i16>,
+    pub feature_bias: Vec<i16>,
+    pub output_weights: Vec<i16>,
+    pub output_bias: i16,
+}
+
+pub fn feature_index(perspective: Color, king_sq: Square, piece: Piece, color: Color, sq: Square) -> usize {
+    let orient = |s: Square| match perspective {
+        Color::White => s.to_index(),
+        Color::Black => s.to_index() ^ 56,
+    };
+    let relative_color = if color == perspective { 0 } else { 1 };
+    ((orient(king_sq) * 12 + relative_color * 6 + piece.to_index()) * 64) + orient(sq)
+}
+
+pub fn active_features(board: &Board, perspective: Color) -> Vec<usize> {
+    let king_sq = board.king_square(perspective);
+    let mut features = Vec::with_capacity(32);
+    for color in [Color::White, Color::Black] {
+        for piece in ALL_PIECES {
+            for sq in board.pieces(piece) & board.color_combined(color) {
+                features.push(feature_index(perspective, king_sq, piece, color, sq));
+            }
+        }
+    }
+    features
+}
+
+impl Network {
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, String> {
+        if bytes.len() < 12 || &bytes[..4] != MAGIC {
+            return Err("not an axelrot NNUE file".to_string());
+        }
+        let word = |i: usize| u32::from_le_bytes([bytes[i], bytes[i + 1], bytes[i + 2], bytes[i + 3]]);
+        let version = word(4);
+        if version != VERSION {
+            return Err(format!("unsupported NNUE version {}", version));
+        }
+        let hidden = word(8) as usize;
+        if hidden == 0 || hidden > MAX_HIDDEN {
+            return Err(format!("invalid hidden layer size {}", hidden));
+        }
+        let count = NUM_FEATURES * hidden + hidden + 2 * hidden + 1;
+        if bytes.len() != 12 + count * 2 {
+            return Err(format!("expected {} bytes, found {}", 12 + count * 2, bytes.len()));
+        }
+        let mut values = bytes[12..].chunks_exact(2).map(|c| i16::from_le_bytes([c[0], c[1]]));
+        let mut take = |n: usize| values.by_ref().take(n).collect::<Vec<i16>>();
+        let feature_weights = take(NUM_FEATURES * hidden);
+        let feature_bias = take(hidden);
+        let output_weights = take(2 * hidden);
+        let output_bias = take(1)[0];
+        Ok(Network { hidden, feature_weights, feature_bias, output_weights, output_bias })
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut out = Vec::with_capacity(12 + 2 * (self.feature_weights.len() + 3 * self.hidden + 1));
+        out.extend_from_slice(MAGIC);
+        out.extend_from_slice(&VERSION.to_le_bytes());
+        out.extend_from_slice(&(self.hidden as u32).to_le_bytes());
+        for v in self
+            .feature_weights
+            .iter()
+            .chain(&self.feature_bias)
+            .chain(&self.output_weights)
+            .chain(std::iter::once(&self.output_bias))
+        {
+            out.extend_from_slice(&v.to_le_bytes());
+        }
+        out
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, String> {
+        let path = path.as_ref();
+        let bytes = fs::read(path).map_err(|e| format!("cannot read {}: {}", path.display(), e))?;
+        Self::from_bytes(&bytes).map_err(|e| format!("{}: {}", path.display(), e))
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), String> {
+        let path = path.as_ref();
+        fs::write(path, self.to_bytes()).map_err(|e| format!("cannot write {}: {}", path.display(), e))
+    }
+
+    fn feature_row(&self, feature: usize) -> &[i16] {
+        &self.feature_weights[feature * self.hidden..(feature + 1) * self.hidden]
+    }
+
+    pub fn refresh(&self, board: &Board, perspective: Color, acc: &mut [i16]) {
+        acc.copy_from_slice(&self.feature_bias);
+        for feature in active_features(board, perspective) {
+            simd::add_assign(acc, self.feature_row(feature));
+        }
+    }
+
+    pub fn output(&self, us: &[i16], them: &[i16]) -> i32 {
+        let (w_us, w_them) = self.output_weights.split_at(self.hidden);
+        let sum = simd::clipped_dot(us, w_us) + simd::clipped_dot(them, w_them) + self.output_bias as i32;
+        sum * SCALE / (QA * QB)
+    }
+
+    pub fn evaluate(&self, board: &Board) -> i32 {
+        let mut white = vec![0; self.hidden];
+        let mut black = vec![0; self.hidden];
+        self.refresh(board, Color::White, &mut white);
+        self.refresh(board, Color::Black, &mut black);
+        match board.side_to_move() {
+            Color::White => self.output(&white, &black),
+            Color::Black => self.output(&black, &white),
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct Accumulator {
+    pub white: Vec<i16>,
+    pub black: Vec<i16>,
+}
+
+// One accumulator per ply, pushed on make and popped on unmake alongside the
+// search's board history.
+pub struct AccumulatorStack {
+    net: Arc<Network>,
+    stack: Vec<Accumulator>,
+    top: usize,
+}
+
+impl AccumulatorStack {
+    pub fn new(net: Arc<Network>, board: &Board) -> Self {
+        let mut acc = Accumulator { white: vec![0; net.hidden], black: vec![0; net.hidden] };
+        net.refresh(board, Color::White, &mut acc.white);
+        net.refresh(board, Color::Black, &mut acc.black);
+        AccumulatorStack { net, stack: vec![acc], top: 0 }
+    }
+
+    pub fn current(&self) -> &Accumulator {
+        &self.stack[self.top]
+    }
+
+    pub fn push(&mut self, before: &Board, after: &Board) {
+        if self.top + 1 == self.stack.len() {
+            let copy = self.stack[self.top].clone();
+            self.stack.push(copy);
+        } else {
+            let (lower, upper) = self.stack.split_at_mut(self.top + 1);
+            upper[0].white.copy_from_slice(&lower[self.top].white);
+            upper[0].black.copy_from_slice(&lower[self.top].black);
+        }
+        self.top += 1;
+
+        for perspective in [Color::White, Color::Black] {
+            let acc = match perspective {
+                Color::White => &mut self.stack[self.top].white,
+                Color::Black => &mut self.stack[self.top].black,
+            };
+            // Every feature is relative to our own king, so a king move means
+            // rebuilding this perspective from scratch.
+            if before.king_square(perspective) != after.king_square(perspective) {
+                self.net.refresh(after, perspective, acc);
+                continue;
+            }
+            let king_sq = after.king_square(perspective);
+            for color in [Color::White, Color::Black] {
+                for piece in ALL_PIECES {
+                    let old = before.pieces(piece) & before.color_combined(color);
+                    let new = after.pieces(piece) & after.color_combined(color);
+                    for sq in old & !new {
+                        let row = self.net.feature_row(feature_index(perspective, king_sq, piece, color, sq));
+                        simd::sub_assign(acc, row);
+                    }
+                    for sq in new & !old {
+                        let row = self.net.feature_row(feature_index(perspective, king_sq, piece, color, sq));
+                        simd::add_assign(acc, row);
+                    }
+                }
+            }
+        }
+    }
+
+    pub fn pop(&mut self) {
+        self.top -= 1;
+    }
+
+    pub fn evaluate(&self, board: &Board) -> i32 {
+        let acc = self.current();
+        match board.side_to_move() {
+            Color::White => self.net.output(&acc.white, &acc.black),
+            Color::Black => self.net.output(&acc.black, &acc.white),
+        }
+    }
+}
+
+static ACTIVE_NETWORK: LazyLock<RwLock<Option<Arc<Network>>>> = LazyLock::new(|| RwLock::new(None));
+static USE_NNUE: AtomicBool = AtomicBool::new(false);
+
+pub fn set_active_network(net: Option<Network>) {
+    *ACTIVE_NETWORK.write().unwrap_or_else(|e| e.into_inner()) = net.map(Arc::new);
+}
+
+pub fn set_use_nnue(enabled: bool) {
+    USE_NNUE.store(enabled, Ordering::Relaxed);
+}
+
+// The network the search should use, if NNUE is switched on and one is loaded.
+pub fn active_network() -> Option<Arc<Network>> {
+    if !USE_NNUE.load(Ordering::Relaxed) {
+        return None;
+    }
+    ACTIVE_NETWORK.read().unwrap_or_else(|e| e.into_inner()).clone()
+}
+
+pub fn is_network_file(path: impl AsRef<Path>) -> bool {
+    let mut magic = [0u8; 4];
+    fs::File::open(path)
+        .and_then(|mut f| std::io::Read::read_exact(&mut f, &mut magic))
+        .is_ok_and(|_| &magic == MAGIC)
+}
+
+mod simd {
+    use super::QA;
+
+    pub fn add_assign(acc: &mut [i16], row: &[i16]) {
+        #[cfg(target_arch = "x86_64")]
+        if is_x86_feature_detected!("avx2") {
+            // SAFETY: AVX2 support was just checked at runtime.
+            unsafe { avx2::add_assign(acc, row) };
+            return;
+        }
+        for (a, &r) in acc.iter_mut().zip(row) {
+            *a = a.wrapping_add(r);
+        }
+    }
+
+    pub fn sub_assign(acc: &mut [i16], row: &[i16]) {
+        #[cfg(target_arch = "x86_64")]
+        if is_x86_feature_detected!("avx2") {
+            // SAFETY: AVX2 support was just checked at runtime.
+            unsafe { avx2::sub_assign(acc, row) };
+            return;
+        }
+        for (a, &r) in acc.iter_mut().zip(row) {
+            *a = a.wrapping_sub(r);
+        }
+    }
+
+    pub fn clipped_dot(acc: &[i16], weights: &[i16]) -> i32 {
+        #[cfg(target_arch = "x86_64")]
+        if is_x86_feature_detected!("avx2") {
+            // SAFETY: AVX2 support was just checked at runtime.
+            return unsafe { avx2::clipped_dot(acc, weights) };
+        }
+        acc.iter()
+            .zip(weights)
+            .map(|(&a, &w)| (a as i32).clamp(0, QA) * w as i32)
+            .sum()
+    }
+
+    #[cfg(target_arch = "x86_64")]
+    mod avx2 {
+        use super::QA;
+        use std::arch::x86_64::*;
+
+        #[target_feature(enable = "avx2")]
+        pub unsafe fn add_assign(acc: &mut [i16], row: &[i16]) {
+            let n = acc.len().min(row.len());
+            let chunks = n / 16;
+            for i in 0..chunks {
+                // SAFETY: i * 16 + 16 <= n, so both loads and the store stay in bounds.
+                unsafe {
+                    let a = _mm256_loadu_si256(acc.as_ptr().add(i * 16) as *const __m256i);
+                    let r = _mm256_loadu_si256(row.as_ptr().add(i * 16) as *const __m256i);
+                    _mm256_storeu_si256(acc.as_mut_ptr().add(i * 16) as *mut __m256i, _mm256_add_epi16(a, r));
+                }
+            }
+            for i in chunks * 16..n {
+                acc[i] = acc[i].wrapping_add(row[i]);
+            }
+        }
+
+        #[target_feature(enable = "avx2")]
+        pub unsafe fn sub_assign(acc: &mut [i16], row: &[i16]) {
+            let n = acc.len().min(row.len());
+            let chunks = n / 16;
+            for i in 0..chunks {
+                // SAFETY: i * 16 + 16 <= n, so both loads and the store stay in bounds.
+                unsafe {
+                    let a = _mm256_loadu_si256(acc.as_ptr().add(i * 16) as *const __m256i);
+                    let r = _mm256_loadu_si256(row.as_ptr().add(i * 16) as *const __m256i);
+                    _mm256_storeu_si256(acc.as_mut_ptr().add(i * 16) as *mut __m256i, _mm256_sub_epi16(a, r));
+                }
+            }
+            for i in chunks * 16..n {
+                acc[i] = acc[i].wrapping_sub(row[i]);
+            }
+        }
+
+        #[target_feature(enable = "avx2")]
+        pub unsafe fn clipped_dot(acc: &[i16], weights: &[i16]) -> i32 {
+            let n = acc.len().min(weights.len());
+            let chunks = n / 16;
+            let zero = _mm256_setzero_si256();
+            let max = _mm256_set1_epi16(QA as i16);
+            let mut sum = _mm256_setzero_si256();
+            for i in 0..chunks {
+                // SAFETY: i * 16 + 16 <= n, so both loads stay in bounds.
+                unsafe {
+                    let a = _mm256_loadu_si256(acc.as_ptr().add(i * 16) as *const __m256i);
+                    let w = _mm256_loadu_si256(weights.as_ptr().add(i * 16) as *const __m256i);
+                    let clipped = _mm256_min_epi16(_mm256_max_epi16(a, zero), max);
+                    sum = _mm256_add_epi32(sum, _mm256_madd_epi16(clipped, w));
+                }
+            }
+            let mut lanes = [0i32; 8];
+            // SAFETY: `lanes` is exactly 256 bits wide.
+            unsafe { _mm256_storeu_si256(lanes.as_mut_ptr() as *mut __m256i, sum) };
+            let mut total: i32 = lanes.iter().sum();
+            for i in chunks * 16..n {
+                total += (acc[i] as i32).clamp(0, QA) * weights[i] as i32;
+            }
+            total
+        }
+    }
+}
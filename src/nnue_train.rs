@@ -0,0 +1,345 @@
+use crate::datagen::{Record, Rng};
+use crate::nnue::{active_features, Network, NUM_FEATURES, QA, QB, SCALE};
+use chess::Color;
+use std::fs;
+use std::path::Path;
+
+const CHECKPOINT_MAGIC: &[u8; 4] = b"AXC2";
+
+// Float shadow of `nnue::Network`. Activations are clipped to [0, 1] here and
+// to [0, QA] after quantisation, so `quantise` only has to rescale weights.
+#[derive(Clone)]
+pub struct FloatNetwork {
+    pub hidden: usize,
+    pub feature_weights: Vec<f32>,
+    pub feature_bias: Vec<f32>,
+    pub output_weights: Vec<f32>,
+    pub output_bias: f32,
+}
+
+pub struct Sample {
+    pub us: Vec<usize>,
+    pub them: Vec<usize>,
+    // Target win probability for the side to move.
+    pub target: f32,
+}
+
+#[derive(Clone, Copy, Debug)]
+pub enum LrSchedule {
+    Constant,
+    // Multiply the rate by `gamma` every `every` epochs.
+    Step { every: usize, gamma: f32 },
+    // Cosine decay from the initial rate to `min_lr` over `epochs`.
+    Cosine { epochs: usize, min_lr: f32 },
+}
+
+impl LrSchedule {
+    pub fn rate(&self, base: f32, epoch: usize) -> f32 {
+        match *self {
+            LrSchedule::Constant => base,
+            LrSchedule::Step { every, gamma } => base * gamma.powi((epoch / every.max(1)) as i32),
+            LrSchedule::Cosine { epochs, min_lr } => {
+                let t = (epoch as f32 / epochs.max(1) as f32).min(1.0);
+                min_lr + (base - min_lr) * 0.5 * (1.0 + (std::f32::consts::PI * t).cos())
+            }
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct TrainConfig {
+    pub hidden: usize,
+    pub epochs: usize,
+    pub batch_size: usize,
+    pub lr: f32,
+    pub schedule: LrSchedule,
+    // 1.0 trains on game results only, 0.0 on search scores only.
+    pub wdl: f32,
+    pub seed: u64,
+}
+
+impl Default for TrainConfig {
+    fn default() -> Self {
+        TrainConfig {
+            hidden: 128,
+            epochs: 20,
+            batch_size: 1024,
+            lr: 0.001,
+            schedule: LrSchedule::Step { every: 8, gamma: 0.3 },
+            wdl: 0.5,
+            seed: 1,
+        }
+    }
+}
+
+fn sigmoid(x: f32) -> f32 {
+    1.0 / (1.0 + (-x).exp())
+}
+
+pub fn sample_from_record(record: &Record, wdl: f32) -> Sample {
+    let stm = record.board.side_to_move();
+    let (score, result) = match stm {
+        Color::White => (record.score as f32, record.result_f64() as f32),
+        Color::Black => (-(record.score as f32), 1.0 - record.result_f64() as f32),
+    };
+    Sample {
+        us: active_features(&record.board, stm),
+        them: active_features(&record.board, !stm),
+        target: wdl * result + (1.0 - wdl) * sigmoid(score / SCALE as f32),
+    }
+}
+
+impl FloatNetwork {
+    pub fn random(hidden: usize, seed: u64) -> Self {
+        let mut rng = Rng::new(seed);
+        let mut uniform = |scale: f32| ((rng.next_u64() >> 40) as f32 / (1u64 << 24) as f32 * 2.0 - 1.0) * scale;
+        let ft_scale = 1.0 / 32f32.sqrt();
+        let out_scale = 1.0 / (2.0 * hidden as f32).sqrt();
+        FloatNetwork {
+            hidden,
+            feature_weights: (0..NUM_FEATURES * hidden).map(|_| uniform(ft_scale)).collect(),
+            feature_bias: vec![0.0; hidden],
+            output_weights: (0..2 * hidden).map(|_| uniform(out_scale)).collect(),
+            output_bias: 0.0,
+        }
+    }
+
+    fn accumulate(&self, features: &[usize]) -> Vec<f32> {
+        let mut acc = self.feature_bias.clone();
+        for &f in features {
+            let row = &self.feature_weights[f * self.hidden..(f + 1) * self.hidden];
+            for (a, w) in acc.iter_mut().zip(row) {
+                *a += w;
+            }
+        }
+        acc
+    }
+
+    // Raw output in "sigmoid units"; multiply by SCALE for centipawns.
+    pub fn forward(&self, sample: &Sample) -> f32 {
+        let us = self.accumulate(&sample.us);
+        let them = self.accumulate(&sample.them);
+        let (w_us, w_them) = self.output_weights.split_at(self.hidden);
+        let dot = |acc: &[f32], w: &[f32]| acc.iter().zip(w).map(|(a, w)| a.clamp(0.0, 1.0) * w).sum::<f32>();
+        self.output_bias + dot(&us, w_us) + dot(&them, w_them)
+    }
+
+    pub fn loss(&self, samples: &[Sample]) -> f32 {
+        if samples.is_empty() {
+            return 0.0;
+        }
+        let total: f32 = samples
+            .iter()
+            .map(|s| {
+                let diff = sigmoid(self.forward(s)) - s.target;
+                diff * diff
+            })
+            .sum();
+        total / samples.len() as f32
+    }
+
+    pub fn quantise(&self) -> Network {
+        let q = |v: f32, scale: i32| (v * scale as f32).round().clamp(i16::MIN as f32, i16::MAX as f32) as i16;
+        Network {
+            hidden: self.hidden,
+            feature_weights: self.feature_weights.iter().map(|&w| q(w, QA)).collect(),
+            feature_bias: self.feature_bias.iter().map(|&b| q(b, QA)).collect(),
+            output_weights: self.output_weights.iter().map(|&w| q(w, QB)).collect(),
+            output_bias: q(self.output_bias, QA * QB),
+        }
+    }
+}
+
+struct Adam {
+    m: Vec<f32>,
+    v: Vec<f32>,
+    t: i32,
+}
+
+impl Adam {
+    const BETA1: f32 = 0.9;
+    const BETA2: f32 = 0.999;
+    const EPS: f32 = 1e-8;
+
+    fn new(len: usize) -> Self {
+        Adam { m: vec![0.0; len], v: vec![0.0; len], t: 0 }
+    }
+
+    fn update(&mut self, index: usize, weight: &mut f32, grad: f32, lr: f32) {
+        let m = &mut self.m[index];
+        let v = &mut self.v[index];
+        *m = Self::BETA1 * *m + (1.0 - Self::BETA1) * grad;
+        *v = Self::BETA2 * *v + (1.0 - Self::BETA2) * grad * grad;
+        let m_hat = *m / (1.0 - Self::BETA1.powi(self.t));
+        let v_hat = *v / (1.0 - Self::BETA2.powi(self.t));
+        *weight -= lr * m_hat / (v_hat.sqrt() + Self::EPS);
+    }
+}
+
+// Mini-batch trainer. Feature-weight gradients are kept dense but only the
+// rows touched by the current batch are updated and cleared (lazy Adam), which
+// keeps an epoch proportional to the data rather than the input layer size.
+pub struct Trainer {
+    pub net: FloatNetwork,
+    pub config: TrainConfig,
+    ft_adam: Adam,
+    bias_adam: Adam,
+    out_adam: Adam,
+    ft_grad: Vec<f32>,
+    touched: Vec<bool>,
+}
+
+impl Trainer {
+    pub fn new(net: FloatNetwork, config: TrainConfig) -> Self {
+        let hidden = net.hidden;
+        Trainer {
+            ft_adam: Adam::new(NUM_FEATURES * hidden),
+            bias_adam: Adam::new(hidden),
+            out_adam: Adam::new(2 * hidden + 1),
+            ft_grad: vec![0.0; NUM_FEATURES * hidden],
+            touched: vec![false; NUM_FEATURES],
+            net,
+            config,
+        }
+    }
+
+    fn train_batch(&mut self, batch: &[&Sample], lr: f32) {
+        let hidden = self.net.hidden;
+        let mut bias_grad = vec![0.0f32; hidden];
+        let mut out_grad = vec![0.0f32; 2 * hidden + 1];
+        let mut touched_list = Vec::new();
+        let scale = 1.0 / batch.len() as f32;
+
+        for sample in batch {
+            let us = self.net.accumulate(&sample.us);
+            let them = self.net.accumulate(&sample.them);
+            let (w_us, w_them) = self.net.output_weights.split_at(hidden);
+            let out = self.net.output_bias
+                + us.iter().zip(w_us).map(|(a, w)| a.clamp(0.0, 1.0) * w).sum::<f32>()
+                + them.iter().zip(w_them).map(|(a, w)| a.clamp(0.0, 1.0) * w).sum::<f32>();
+            let p = sigmoid(out);
+            let g = 2.0 * (p - sample.target) * p * (1.0 - p) * scale;
+
+            out_grad[2 * hidden] += g;
+            for (side, (acc, weights, features)) in
+                [(&us, w_us, &sample.us), (&them, w_them, &sample.them)].into_iter().enumerate()
+            {
+                for i in 0..hidden {
+                    let a = acc[i];
+                    out_grad[side * hidden + i] += g * a.clamp(0.0, 1.0);
+                    if a > 0.0 && a < 1.0 {
+                        let d = g * weights[i];
+                        bias_grad[i] += d;
+                        for &f in features.iter() {
+                            self.ft_grad[f * hidden + i] += d;
+                        }
+                    }
+                }
+                for &f in features.iter() {
+                    if !self.touched[f] {
+                        self.touched[f] = true;
+                        touched_list.push(f);
+                    }
+                }
+            }
+        }
+
+        for adam in [&mut self.ft_adam, &mut self.bias_adam, &mut self.out_adam] {
+            adam.t += 1;
+        }
+        for f in touched_list {
+            self.touched[f] = false;
+            for i in f * hidden..(f + 1) * hidden {
+                let grad = std::mem::take(&mut self.ft_grad[i]);
+                self.ft_adam.update(i, &mut self.net.feature_weights[i], grad, lr);
+            }
+        }
+        for (i, grad) in bias_grad.into_iter().enumerate() {
+            self.bias_adam.update(i, &mut self.net.feature_bias[i], grad, lr);
+        }
+        for (i, grad) in out_grad.into_iter().enumerate() {
+            let weight = if i < 2 * hidden { &mut self.net.output_weights[i] } else { &mut self.net.output_bias };
+            self.out_adam.update(i, weight, grad, lr);
+        }
+    }
+
+    // Runs one shuffled pass over `samples` and returns the training loss
+    // measured after the pass.
+    pub fn epoch(&mut self, samples: &[Sample], epoch: usize) -> f32 {
+        let mut order: Vec<usize> = (0..samples.len()).collect();
+        let mut rng = Rng::new(self.config.seed.wrapping_add(epoch as u64));
+        for i in (1..order.len()).rev() {
+            order.swap(i, rng.below(i + 1));
+        }
+        let lr = self.config.schedule.rate(self.config.lr, epoch);
+        for chunk in order.chunks(self.config.batch_size.max(1)) {
+            let batch: Vec<&Sample> = chunk.iter().map(|&i| &samples[i]).collect();
+            self.train_batch(&batch, lr);
+        }
+        self.net.loss(samples)
+    }
+
+    // Checkpoints hold the float network, the Adam moments and step counts,
+    // and how many epochs are finished, so a resumed run continues exactly
+    // where the interrupted one stopped.
+    pub fn save_checkpoint(&self, path: impl AsRef<Path>, epochs_done: usize) -> Result<(), String> {
+        let path = path.as_ref();
+        let adams = [&self.ft_adam, &self.bias_adam, &self.out_adam];
+        let net = &self.net;
+        let mut out = Vec::new();
+        out.extend_from_slice(CHECKPOINT_MAGIC);
+        out.extend_from_slice(&(net.hidden as u32).to_le_bytes());
+        out.extend_from_slice(&(epochs_done as u32).to_le_bytes());
+        for adam in adams {
+            out.extend_from_slice(&adam.t.to_le_bytes());
+        }
+        let floats = net
+            .feature_weights
+            .iter()
+            .chain(&net.feature_bias)
+            .chain(&net.output_weights)
+            .chain(std::iter::once(&net.output_bias))
+            .chain(adams.into_iter().flat_map(|adam| adam.m.iter().chain(&adam.v)));
+        for v in floats {
+            out.extend_from_slice(&v.to_le_bytes());
+        }
+        fs::write(path, out).map_err(|e| format!("cannot write {}: {}", path.display(), e))
+    }
+
+    // Returns the restored trainer and the number of epochs already done.
+    pub fn load_checkpoint(path: impl AsRef<Path>, config: TrainConfig) -> Result<(Self, usize), String> {
+        let path = path.as_ref();
+        let bytes = fs::read(path).map_err(|e| format!("cannot read {}: {}", path.display(), e))?;
+        if bytes.len() < 24 || &bytes[..4] != CHECKPOINT_MAGIC {
+            return Err(format!("{}: not a trainer checkpoint", path.display()));
+        }
+        let word = |i: usize| u32::from_le_bytes(bytes[4 * i..4 * i + 4].try_into().unwrap());
+        let hidden = word(1) as usize;
+        let epochs_done = word(2) as usize;
+        let steps = [word(3) as i32, word(4) as i32, word(5) as i32];
+        let (ft, out) = (NUM_FEATURES * hidden, 2 * hidden + 1);
+        let count = ft + hidden + out + 2 * (ft + hidden + out);
+        if bytes.len() != 24 + 4 * count {
+            return Err(format!("{}: checkpoint has the wrong size", path.display()));
+        }
+        let mut values = bytes[24..].chunks_exact(4).map(|c| f32::from_le_bytes(c.try_into().unwrap()));
+        let mut take = |n: usize| values.by_ref().take(n).collect::<Vec<f32>>();
+        let net = FloatNetwork {
+            hidden,
+            feature_weights: take(ft),
+            feature_bias: take(hidden),
+            output_weights: take(2 * hidden),
+            output_bias: take(1)[0],
+        };
+        let mut trainer = Trainer::new(net, config);
+        for (adam, (len, t)) in [&mut trainer.ft_adam, &mut trainer.bias_adam, &mut trainer.out_adam]
+            .into_iter()
+            .zip([ft, hidden, out].into_iter().zip(steps))
+        {
+            adam.m = take(len);
+            adam.v = take(len);
+            adam.t = t;
+        }
+        Ok((trainer, epochs_done))
+    }
+}
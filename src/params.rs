@@ -0,0 +1,312 @@
+use std::fs;
+use std::path::Path;
+use std::sync::{Arc, LazyLock, RwLock};
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct EvalParams {
+    pub piece_values: [i32; 6],
+    pub pawn_pst: [i32; 64],
+    pub knight_pst: [i32; 64],
+    pub bishop_pst: [i32; 64],
+    pub rook_pst: [i32; 64],
+    pub queen_pst: [i32; 64],
+    pub king_pst: [i32; 64],
+    pub king_attack_weights: [i32; 6],
+    pub king_safe_check_weights: [i32; 6],
+    pub king_shield_missing: i32,
+    pub king_shield_advanced: i32,
+    pub king_storm_weights: [i32; 8],
+    pub king_semi_open_file: i32,
+    pub king_open_file: i32,
+    pub king_danger_scale: i32,
+    pub king_max_penalty: i32,
+}
+
+impl Default for EvalParams {
+    #[rustfmt::skip]
+    fn default() -> Self {
+        EvalParams {
+            piece_values: [100, 300, 300, 500, 900, 0],
+            pawn_pst: [
+                0, 0, 0, 0, 0, 0, 0, 0,
+                5, 10, 10, -40, -40, 10, 10, 5,
+                5, -5, -10, 0, 0, -10, -5, 5,
+                0, 0, 0, 50, 50, 0, 0, 0,
+                5, 5, 10, 25, 25, 10, 5, 5,
+                10, 10, 20, 30, 30, 20, 10, 10,
+                50, 50, 50, 50, 50, 50, 50, 50,
+                0, 0, 0, 0, 0, 0, 0, 0,
+            ],
+            knight_pst: [
+                -50, -40, -30, -30, -30, -30, -40, -50,
+                -40, -20, 0, 5, 5, 0, -20, -40,
+                -30, 5, 10, 15, 15, 10, 5, -30,
+                -30, 0, 15, 20, 20, 15, 0, -30,
+                -30, 5, 15, 20, 20, 15, 5, -30,
+                -30, 0, 10, 15, 15, 10, 0, -30,
+                -40, -20, 0, 0, 0, 0, -20, -40,
+                -50, -40, -30, -30, -30, -30, -40, -50,
+            ],
+            bishop_pst: [
+                -20, -10, -40, -10, -10, -40, -10, -20,
+                -10, 5, 0, 0, 0, 0, 5, -10,
+                -10, 10, 10, 10, 10, 10, 10, -10,
+                -10, 0, 20, 10, 10, 20, 0, -10,
+                -10, 5, 5, 10, 10, 5, 5, -10,
+                -10, 0, 5, 10, 10, 5, 0, -10,
+                -10, 0, 0, 0, 0, 0, 0, -10,
+                -20, -10, -40, -10, -10, -40, -10, -20,
+            ],
+            rook_pst: [
+                0, 0, 0, 5, 5, 0, 0, 0,
+                -5, 0, 0, 0, 0, 0, 0, -5,
+                -5, 0, 0, 0, 0, 0, 0, -5,
+                -5, 0, 0, 0, 0, 0, 0, -5,
+                -5, 0, 0, 0, 0, 0, 0, -5,
+                -5, 0, 0, 0, 0, 0, 0, -5,
+                5, 10, 10, 10, 10, 10, 10, 5,
+                0, 0, 0, 0, 0, 0, 0, 0,
+            ],
+            queen_pst: [
+                -20, -10, -10, -5, -5, -10, -10, -20,
+                -10, 0, 0, 0, 0, 0, 0, -10,
+                -10, 5, 5, 5, 5, 5, 0, -10,
+                0, 0, 5, 5, 5, 5, 0, -5,
+                -5, 0, 5, 5, 5, 5, 0, -5,
+                -10, 0, 5, 5, 5, 5, 0, -10,
+                -10, 0, 0, 0, 0, 0, 0, -10,
+                -20, -10, -10, -5, -5, -10, -10, -20,
+            ],
+            king_pst: [
+                20, 30, 10, 0, 0, 10, 30, 20,
+                20, 20, -10, -10, -10, -10, 20, 20,
+                -10, -20, -20, -20, -20, -20, -20, -10,
+                -20, -30, -30, -40, -40, -30, -30, -20,
+                -30, -40, -40, -50, -50, -40, -40, -30,
+                -30, -40, -40, -50, -50, -40, -40, -30,
+                -30, -40, -40, -50, -50, -40, -40, -30,
+                -30, -40, -40, -50, -50, -40, -40, -30,
+            ],
+            king_attack_weights: [0, 20, 20, 40, 80, 0],
+            king_safe_check_weights: [0, 60, 45, 80, 70, 0],
+            king_shield_missing: 25,
+            king_shield_advanced: 10,
+            king_storm_weights: [0, 10, 30, 20, 10, 5, 0, 0],
+            king_semi_open_file: 20,
+            king_open_file: 35,
+            king_danger_scale: 1024,
+            king_max_penalty: 500,
+        }
+    }
+}
+
+impl EvalParams {
+    pub fn psts(&self) -> [&[i32; 64]; 6] {
+        [
+            &self.pawn_pst,
+            &self.knight_pst,
+            &self.bishop_pst,
+            &self.rook_pst,
+            &self.queen_pst,
+            &self.king_pst,
+        ]
+    }
+
+    // Every weight by name, in file order. Serialisation and tuning both walk
+    // this list, so new parameters only need to be added here and in the struct.
+    pub fn fields(&self) -> Vec<(&'static str, &[i32])> {
+        vec![
+            ("piece_values", &self.piece_values[..]),
+            ("pawn_pst", &self.pawn_pst[..]),
+            ("knight_pst", &self.knight_pst[..]),
+            ("bishop_pst", &self.bishop_pst[..]),
+            ("rook_pst", &self.rook_pst[..]),
+            ("queen_pst", &self.queen_pst[..]),
+            ("king_pst", &self.king_pst[..]),
+            ("king_attack_weights", &self.king_attack_weights[..]),
+            ("king_safe_check_weights", &self.king_safe_check_weights[..]),
+            ("king_shield_missing", std::slice::from_ref(&self.king_shield_missing)),
+            ("king_shield_advanced", std::slice::from_ref(&self.king_shield_advanced)),
+            ("king_storm_weights", &self.king_storm_weights[..]),
+            ("king_semi_open_file", std::slice::from_ref(&self.king_semi_open_file)),
+            ("king_open_file", std::slice::from_ref(&self.king_open_file)),
+            ("king_danger_scale", std::slice::from_ref(&self.king_danger_scale)),
+            ("king_max_penalty", std::slice::from_ref(&self.king_max_penalty)),
+        ]
+    }
+
+    pub fn fields_mut(&mut self) -> Vec<(&'static str, &mut [i32])> {
+        vec![
+            ("piece_values", &mut self.piece_values[..]),
+            ("pawn_pst", &mut self.pawn_pst[..]),
+            ("knight_pst", &mut self.knight_pst[..]),
+            ("bishop_pst", &mut self.bishop_pst[..]),
+            ("rook_pst", &mut self.rook_pst[..]),
+            ("queen_pst", &mut self.queen_pst[..]),
+            ("king_pst", &mut self.king_pst[..]),
+            ("king_attack_weights", &mut self.king_attack_weights[..]),
+            ("king_safe_check_weights", &mut self.king_safe_check_weights[..]),
+            ("king_shield_missing", std::slice::from_mut(&mut self.king_shield_missing)),
+            ("king_shield_advanced", std::slice::from_mut(&mut self.king_shield_advanced)),
+            ("king_storm_weights", &mut self.king_storm_weights[..]),
+            ("king_semi_open_file", std::slice::from_mut(&mut self.king_semi_open_file)),
+            ("king_open_file", std::slice::from_mut(&mut self.king_open_file)),
+            ("king_danger_scale", std::slice::from_mut(&mut self.king_danger_scale)),
+            ("king_max_penalty", std::slice::from_mut(&mut self.king_max_penalty)),
+        ]
+    }
+
+    pub fn to_json(&self) -> String {
+        let fields = self.fields();
+        let mut out = String::from("{\n");
+        for (i, (name, values)) in fields.iter().enumerate() {
+            out.push_str(&format!("  \"{}\": ", name));
+            if values.len() == 1 {
+                out.push_str(&values[0].to_string());
+            } else {
+                let rows: Vec<String> = values
+                    .chunks(8)
+                    .map(|row| row.iter().map(|v| v.to_string()).collect::<Vec<_>>().join(", "))
+                    .collect();
+                if rows.len() == 1 {
+                    out.push_str(&format!("[{}]", rows[0]));
+                } else {
+                    out.push_str(&format!("[\n    {}\n  ]", rows.join(",\n    ")));
+                }
+            }
+            out.push_str(if i + 1 < fields.len() { ",\n" } else { "\n" });
+        }
+        out.push_str("}\n");
+        out
+    }
+
+    // Keys missing from the file keep their compiled-in defaults, so a
+    // parameter file only has to list the weights it changes.
+    pub fn from_json(text: &str) -> Result<Self, String> {
+        let mut params = EvalParams::default();
+        for (key, values) in parse_json_object(text)? {
+            let mut fields = params.fields_mut();
+            let (_, slot) = fields
+                .iter_mut()
+                .find(|(name, _)| *name == key)
+                .ok_or_else(|| format!("unknown parameter '{}'", key))?;
+            if slot.len() != values.len() {
+                return Err(format!(
+                    "parameter '{}' expects {} value(s), got {}",
+                    key,
+                    slot.len(),
+                    values.len()
+                ));
+            }
+            slot.copy_from_slice(&values);
+        }
+        Ok(params)
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, String> {
+        let path = path.as_ref();
+        let text = fs::read_to_string(path)
+            .map_err(|e| format!("cannot read {}: {}", path.display(), e))?;
+        Self::from_json(&text).map_err(|e| format!("{}: {}", path.display(), e))
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), String> {
+        let path = path.as_ref();
+        fs::write(path, self.to_json()).map_err(|e| format!("cannot write {}: {}", path.display(), e))
+    }
+}
+
+static ACTIVE_PARAMS: LazyLock<RwLock<Arc<EvalParams>>> =
+    LazyLock::new(|| RwLock::new(Arc::new(EvalParams::default())));
+
+// Searches take a snapshot once and evaluate from it, so the lock is never
+// touched per node.
+pub fn active_params() -> Arc<EvalParams> {
+    ACTIVE_PARAMS.read().unwrap_or_else(|e| e.into_inner()).clone()
+}
+
+pub fn set_active_params(params: EvalParams) {
+    *ACTIVE_PARAMS.write().unwrap_or_else(|e| e.into_inner()) = Arc::new(params);
+}
+
+// A minimal reader for the subset of JSON the parameter files use: a single
+// object whose values are numbers or flat arrays of numbers.
+fn parse_json_object(text: &str) -> Result<Vec<(String, Vec<i32>)>, String> {
+    let mut chars = text.chars().peekable();
+    let mut entries = Vec::new();
+
+    fn skip_ws(chars: &mut std::iter::Peekable<std::str::Chars>) {
+        while chars.peek().is_some_and(|c| c.is_whitespace()) {
+            chars.next();
+        }
+    }
+    fn expect(chars: &mut std::iter::Peekable<std::str::Chars>, want: char) -> Result<(), String> {
+        skip_ws(chars);
+        match chars.next() {
+            Some(c) if c == want => Ok(()),
+            Some(c) => Err(format!("expected '{}', found '{}'", want, c)),
+            None => Err(format!("expected '{}', found end of file", want)),
+        }
+    }
+    fn number(chars: &mut std::iter::Peekable<std::str::Chars>) -> Result<i32, String> {
+        skip_ws(chars);
+        let mut s = String::new();
+        while let Some(&c) = chars.peek() {
+            if c.is_ascii_digit() || matches!(c, '-' | '+' | '.' | 'e' | 'E') {
+                s.push(c);
+                chars.next();
+            } else {
+                break;
+            }
+        }
+        s.parse::<i32>()
+            .or_else(|_| s.parse::<f64>().map(|v| v.round() as i32))
+            .map_err(|_| format!("invalid number '{}'", s))
+    }
+
+    expect(&mut chars, '{')?;
+    skip_ws(&mut chars);
+    if chars.peek() == Some(&'}') {
+        chars.next();
+        return Ok(entries);
+    }
+    loop {
+        expect(&mut chars, '"')?;
+        let key: String = chars.by_ref().take_while(|&c| c != '"').collect();
+        expect(&mut chars, ':')?;
+        skip_ws(&mut chars);
+        let values = if chars.peek() == Some(&'[') {
+            chars.next();
+            let mut values = Vec::new();
+            skip_ws(&mut chars);
+            if chars.peek() == Some(&']') {
+                chars.next();
+            } else {
+                loop {
+                    values.push(number(&mut chars)?);
+                    skip_ws(&mut chars);
+                    match chars.next() {
+                        Some(',') => continue,
+                        Some(']') => break,
+                        _ => return Err(format!("malformed array for '{}'", key)),
+                    }
+                }
+            }
+            values
+        } else {
+            vec![number(&mut chars)?]
+        };
+        entries.push((key, values));
+        skip_ws(&mut chars);
+        match chars.next() {
+            Some(',') => continue,
+            Some('}') => break,
+            _ => return Err("expected ',' or '}' after value".to_string()),
+        }
+    }
+    skip_ws(&mut chars);
+    if chars.next().is_some() {
+        return Err("trailing characters after parameter object".to_string());
+    }
+    Ok(entries)
+}
@@ -0,0 +1,101 @@
+use chess::{Board, ChessMove, MoveGen};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+
+// Move generation check: counts the leaf nodes of the legal move tree.
+// Root moves can be shared between threads, and each thread may keep a
+// table of subtree counts so transpositions are only walked once.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PerftOptions {
+    // Size of each thread's subtree table in megabytes, 0 for none.
+    pub hash_mb: usize,
+    pub threads: usize,
+}
+
+impl Default for PerftOptions {
+    fn default() -> Self {
+        PerftOptions { hash_mb: 0, threads: 1 }
+    }
+}
+
+#[derive(Clone, Copy, Default)]
+struct Entry {
+    key: u64,
+    depth: u32,
+    nodes: u64,
+}
+
+struct PerftTable {
+    entries: Vec<Entry>,
+}
+
+impl PerftTable {
+    fn new(mb: usize) -> Self {
+        let len = (mb << 20) / std::mem::size_of::<Entry>();
+        PerftTable { entries: vec![Entry::default(); len] }
+    }
+
+    fn get(&self, key: u64, depth: u32) -> Option<u64> {
+        let entry = self.entries.get(key as usize % self.entries.len().max(1))?;
+        (entry.key == key && entry.depth == depth).then_some(entry.nodes)
+    }
+
+    fn put(&mut self, key: u64, depth: u32, nodes: u64) {
+        let len = self.entries.len();
+        if len > 0 {
+            self.entries[key as usize % len] = Entry { key, depth, nodes };
+        }
+    }
+}
+
+pub fn perft(board: &Board, depth: u32) -> u64 {
+    perft_with(board, depth, PerftOptions::default())
+}
+
+pub fn perft_with(board: &Board, depth: u32, options: PerftOptions) -> u64 {
+    if depth == 0 {
+        return 1;
+    }
+    divide(board, depth, options).iter().map(|(_, nodes)| nodes).sum()
+}
+
+// Leaf counts below each root move, in move generation order.
+pub fn divide(board: &Board, depth: u32, options: PerftOptions) -> Vec<(ChessMove, u64)> {
+    let moves: Vec<ChessMove> = MoveGen::new_legal(board).collect();
+    if depth == 0 {
+        return Vec::new();
+    }
+    let counts = Mutex::new(vec![0; moves.len()]);
+    let next = AtomicUsize::new(0);
+    std::thread::scope(|scope| {
+        for _ in 0..options.threads.clamp(1, moves.len().max(1)) {
+            scope.spawn(|| {
+                let mut table = PerftTable::new(options.hash_mb);
+                loop {
+                    let i = next.fetch_add(1, Ordering::Relaxed);
+                    let Some(&mv) = moves.get(i) else { break };
+                    let nodes = count(&board.make_move_new(mv), depth - 1, &mut table);
+                    counts.lock().unwrap()[i] = nodes;
+                }
+            });
+        }
+    });
+    moves.into_iter().zip(counts.into_inner().unwrap()).collect()
+}
+
+fn count(board: &Board, depth: u32, table: &mut PerftTable) -> u64 {
+    if depth == 0 {
+        return 1;
+    }
+    let moves = MoveGen::new_legal(board);
+    if depth == 1 {
+        return moves.len() as u64;
+    }
+    let key = board.get_hash();
+    if let Some(nodes) = table.get(key, depth) {
+        return nodes;
+    }
+    let nodes = moves.map(|mv| count(&board.make_move_new(mv), depth - 1, table)).sum();
+    table.put(key, depth, nodes);
+    nodes
+}
@@ -0,0 +1,479 @@
+use crate::{mate_in, mate_score};
+use chess::{Board, BoardStatus, ChessMove, Color, File, MoveGen, Piece, Square, EMPTY};
+use std::str::FromStr;
+
+// PGN game collections. `moves` is the main line; `tree` holds the same
+// moves with their comments, NAGs and variations.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Game {
+    pub tags: Vec<(String, String)>,
+    pub moves: Vec<ChessMove>,
+    pub tree: Vec<Node>,
+    pub result: String,
+}
+
+// A move with the annotations around it. Variations are alternatives to this
+// move, played from the position before it.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Node {
+    pub mv: ChessMove,
+    pub before: Option<String>,
+    pub nags: Vec<u8>,
+    pub after: Option<String>,
+    pub variations: Vec<Vec<Node>>,
+}
+
+impl Node {
+    pub fn new(mv: ChessMove) -> Self {
+        Node { mv, before: None, nags: Vec::new(), after: None, variations: Vec::new() }
+    }
+}
+
+// Tags every exported game starts with, in this order.
+const SEVEN_TAG_ROSTER: [&str; 7] = ["Event", "Site", "Date", "Round", "White", "Black", "Result"];
+
+impl Game {
+    pub fn new(tags: Vec<(String, String)>) -> Self {
+        Game { tags, moves: Vec::new(), tree: Vec::new(), result: "*".to_string() }
+    }
+
+    pub fn tag(&self, name: &str) -> Option<&str> {
+        self.tags.iter().find(|(n, _)| n == name).map(|(_, v)| v.as_str())
+    }
+
+    pub fn set_tag(&mut self, name: &str, value: &str) {
+        match self.tags.iter_mut().find(|(n, _)| n == name) {
+            Some(tag) => tag.1 = value.to_string(),
+            None => self.tags.push((name.to_string(), value.to_string())),
+        }
+    }
+
+    pub fn start_board(&self) -> Result<Board, String> {
+        match self.tag("FEN") {
+            Some(fen) => Board::from_str(fen).map_err(|_| format!("invalid FEN tag {}", fen)),
+            None => Ok(Board::default()),
+        }
+    }
+
+    // Appends a main line move.
+    pub fn push(&mut self, node: Node) {
+        self.moves.push(node.mv);
+        self.tree.push(node);
+    }
+
+    // The annotated main line; bare nodes when only `moves` was filled in.
+    pub fn main_line(&self) -> Vec<Node> {
+        if self.tree.len() == self.moves.len() && self.tree.iter().zip(&self.moves).all(|(n, &mv)| n.mv == mv) {
+            self.tree.clone()
+        } else {
+            self.moves.iter().map(|&mv| Node::new(mv)).collect()
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum Token {
+    Tag(String, String),
+    Comment(String),
+    Nag(u8),
+    Open,
+    Close,
+    Result(String),
+    San(String),
+}
+
+const RESULTS: [&str; 4] = ["1-0", "0-1", "1/2-1/2", "*"];
+
+// Move suffix annotations and the NAGs they stand for.
+const GLYPHS: [(&str, u8); 6] = [("!!", 3), ("??", 4), ("!?", 5), ("?!", 6), ("!", 1), ("?", 2)];
+
+fn tokenize(text: &str) -> Vec<Token> {
+    let mut tokens = Vec::new();
+    let mut chars = text.chars().peekable();
+    while let Some(&c) = chars.peek() {
+        match c {
+            '[' => {
+                chars.next();
+                let inner: String = chars.by_ref().take_while(|&c| c != ']').collect();
+                let inner = inner.trim();
+                let (name, value) = inner.split_once(char::is_whitespace).unwrap_or((inner, ""));
+                let value = value.trim();
+                let value = value.strip_prefix('"').unwrap_or(value);
+                let value = value.strip_suffix('"').unwrap_or(value).replace("\\\"", "\"").replace("\\\\", "\\");
+                tokens.push(Token::Tag(name.to_string(), value));
+            }
+            '{' => {
+                chars.next();
+                let comment: String = chars.by_ref().take_while(|&c| c != '}').collect();
+                tokens.push(Token::Comment(comment.trim().to_string()));
+            }
+            ';' => {
+                chars.next();
+                let comment: String = chars.by_ref().take_while(|&c| c != '\n').collect();
+                tokens.push(Token::Comment(comment.trim().to_string()));
+            }
+            '(' => {
+                chars.next();
+                tokens.push(Token::Open);
+            }
+            ')' => {
+                chars.next();
+                tokens.push(Token::Close);
+            }
+            c if c.is_whitespace() => {
+                chars.next();
+            }
+            _ => {
+                let mut word = String::new();
+                while let Some(&c) = chars.peek() {
+                    if c.is_whitespace() || "[]{}();".contains(c) {
+                        break;
+                    }
+                    word.push(c);
+                    chars.next();
+                }
+                if let Some(nag) = word.strip_prefix('$') {
+                    if let Ok(nag) = nag.parse() {
+                        tokens.push(Token::Nag(nag));
+                    }
+                } else if RESULTS.contains(&word.as_str()) {
+                    tokens.push(Token::Result(word));
+                } else {
+                    // Move numbers ("12." or "12...") may be glued to the move,
+                    // and glyphs such as "?!" to its end.
+                    let san = word.trim_start_matches(|c: char| c.is_ascii_digit() || c == '.');
+                    let glyph = GLYPHS.iter().find(|(g, _)| san.ends_with(g));
+                    let san = glyph.map_or(san, |(g, _)| &san[..san.len() - g.len()]);
+                    if !san.is_empty() {
+                        tokens.push(Token::San(san.to_string()));
+                    }
+                    if let Some(&(_, nag)) = glyph {
+                        tokens.push(Token::Nag(nag));
+                    }
+                }
+            }
+        }
+    }
+    tokens
+}
+
+// Splits `text` into games. A game that cannot be replayed is returned as an
+// error naming the game and the offending move, so callers can skip it.
+pub fn parse_games(text: &str) -> Vec<Result<Game, String>> {
+    let mut games = Vec::new();
+    let mut tags = Vec::new();
+    let mut movetext = Vec::new();
+    let mut depth = 0;
+    let mut finish = |tags: &mut Vec<(String, String)>, movetext: &mut Vec<Token>, result: String| {
+        let game = replay(std::mem::take(tags), std::mem::take(movetext), result);
+        let number = games.len() + 1;
+        games.push(game.map_err(|e| format!("game {}: {}", number, e)));
+    };
+    for token in tokenize(text) {
+        match token {
+            Token::Tag(name, value) => {
+                if !movetext.is_empty() {
+                    finish(&mut tags, &mut movetext, "*".to_string());
+                    depth = 0;
+                }
+                tags.push((name, value));
+            }
+            Token::Result(result) if depth == 0 => {
+                finish(&mut tags, &mut movetext, result);
+            }
+            Token::Result(_) => {}
+            token => {
+                match token {
+                    Token::Open => depth += 1,
+                    Token::Close => depth = (depth - 1).max(0),
+                    _ => {}
+                }
+                movetext.push(token);
+            }
+        }
+    }
+    if !movetext.is_empty() || !tags.is_empty() {
+        finish(&mut tags, &mut movetext, "*".to_string());
+    }
+    games
+}
+
+fn append(comment: &mut Option<String>, text: String) {
+    *comment = Some(match comment.take() {
+        Some(old) => format!("{} {}", old, text),
+        None => text,
+    });
+}
+
+// Reads one line of moves from `board` up to its closing parenthesis.
+fn parse_line(tokens: &mut std::vec::IntoIter<Token>, mut board: Board) -> Result<Vec<Node>, String> {
+    let mut line: Vec<Node> = Vec::new();
+    let mut before = None;
+    let mut previous = board;
+    while let Some(token) = tokens.next() {
+        match token {
+            Token::San(san) => {
+                let mv = parse_san(&board, &san)?;
+                let mut node = Node::new(mv);
+                node.before = before.take();
+                line.push(node);
+                previous = board;
+                board = board.make_move_new(mv);
+            }
+            Token::Comment(text) => match line.last_mut() {
+                Some(node) if before.is_none() => append(&mut node.after, text),
+                _ => append(&mut before, text),
+            },
+            Token::Nag(nag) => {
+                if let Some(node) = line.last_mut() {
+                    node.nags.push(nag);
+                }
+            }
+            Token::Open => {
+                let variation = parse_line(tokens, previous)?;
+                if let Some(node) = line.last_mut()
+                    && !variation.is_empty()
+                {
+                    node.variations.push(variation);
+                }
+            }
+            Token::Close => break,
+            Token::Tag(..) | Token::Result(_) => {}
+        }
+    }
+    Ok(line)
+}
+
+fn replay(tags: Vec<(String, String)>, movetext: Vec<Token>, result: String) -> Result<Game, String> {
+    let mut game = Game { result, ..Game::new(tags) };
+    if let Some(tag) = game.tag("Result")
+        && game.result == "*"
+    {
+        game.result = tag.to_string();
+    }
+    let board = game.start_board()?;
+    for node in parse_line(&mut movetext.into_iter(), board)? {
+        game.push(node);
+    }
+    Ok(game)
+}
+
+// Engine score in the "+1.23/12 0.512s" comment form cutechess writes, with
+// "+M5" for mates; `score` is from the mover's point of view.
+pub fn format_eval(score: i32) -> String {
+    match mate_in(score) {
+        Some(moves) if moves > 0 => format!("+M{}", moves),
+        Some(moves) => format!("-M{}", -moves),
+        None => format!("{:+.2}", score as f64 / 100.0),
+    }
+}
+
+pub fn eval_comment(score: i32, depth: u32, time_ms: u64) -> String {
+    format!("{}/{} {:.3}s", format_eval(score), depth, time_ms as f64 / 1000.0)
+}
+
+// Score and depth back from a comment written by `eval_comment`.
+pub fn parse_eval_comment(comment: &str) -> Option<(i32, u32)> {
+    let (score, rest) = comment.split_whitespace().next()?.split_once('/')?;
+    let depth = rest.parse().ok()?;
+    let score = match score.split_once('M') {
+        Some((sign, moves)) => {
+            let moves = moves.parse::<i32>().ok()?;
+            mate_score(if sign == "-" { -moves } else { moves })
+        }
+        None => (score.parse::<f64>().ok()? * 100.0).round() as i32,
+    };
+    Some((score, depth))
+}
+
+fn write_line(words: &mut Vec<String>, board: Board, fullmove: u32, line: &[Node]) {
+    let mut board = board;
+    let mut fullmove = fullmove;
+    // Black moves need their number after anything that interrupts the line.
+    let mut number_black = true;
+    for node in line {
+        if let Some(before) = &node.before {
+            words.push(format!("{{{}}}", before));
+            number_black = true;
+        }
+        match board.side_to_move() {
+            Color::White => words.push(format!("{}.", fullmove)),
+            Color::Black if number_black => words.push(format!("{}...", fullmove)),
+            Color::Black => {}
+        }
+        words.push(to_san(&board, node.mv));
+        words.extend(node.nags.iter().map(|nag| format!("${}", nag)));
+        number_black = false;
+        if let Some(after) = &node.after {
+            words.push(format!("{{{}}}", after));
+            number_black = true;
+        }
+        for variation in &node.variations {
+            words.push("(".to_string());
+            write_line(words, board, fullmove, variation);
+            words.push(")".to_string());
+            number_black = true;
+        }
+        if board.side_to_move() == Color::Black {
+            fullmove += 1;
+        }
+        board = board.make_move_new(node.mv);
+    }
+}
+
+// Export format: the seven tag roster first, then the other tags, and the
+// movetext wrapped at 80 columns.
+pub fn write_game(game: &Game) -> Result<String, String> {
+    let mut text = String::new();
+    let mut tag = |name: &str, value: &str| {
+        text += &format!("[{} \"{}\"]\n", name, value.replace('\\', "\\\\").replace('"', "\\\""))
+    };
+    for name in SEVEN_TAG_ROSTER {
+        let value = if name == "Result" { Some(game.result.as_str()) } else { game.tag(name) };
+        tag(name, value.unwrap_or("?"));
+    }
+    for (name, value) in game.tags.iter().filter(|(n, _)| !SEVEN_TAG_ROSTER.contains(&n.as_str())) {
+        tag(name, value);
+    }
+    text.push('\n');
+
+    let board = game.start_board()?;
+    let fullmove = game.tag("FEN").and_then(|fen| fen.split_whitespace().nth(5)?.parse().ok()).unwrap_or(1);
+    let mut words = Vec::new();
+    write_line(&mut words, board, fullmove, &game.main_line());
+    words.push(game.result.clone());
+    let mut width = 0;
+    for word in words {
+        if width > 0 && width + 1 + word.len() > 80 {
+            text.push('\n');
+            width = 0;
+        } else if width > 0 && !word.starts_with(')') && !text.ends_with('(') {
+            text.push(' ');
+            width += 1;
+        }
+        width += word.len();
+        text += &word;
+    }
+    Ok(text + "\n\n")
+}
+
+fn piece_from_char(c: char) -> Option<Piece> {
+    match c {
+        'N' => Some(Piece::Knight),
+        'B' => Some(Piece::Bishop),
+        'R' => Some(Piece::Rook),
+        'Q' => Some(Piece::Queen),
+        'K' => Some(Piece::King),
+        _ => None,
+    }
+}
+
+fn piece_char(piece: Piece) -> &'static str {
+    match piece {
+        Piece::Knight => "N",
+        Piece::Bishop => "B",
+        Piece::Rook => "R",
+        Piece::Queen => "Q",
+        Piece::King => "K",
+        Piece::Pawn => "",
+    }
+}
+
+// Finds the legal move written as `san`, accepting check marks, annotation
+// glyphs, "0-0" castling and promotions with or without '='.
+pub fn parse_san(board: &Board, san: &str) -> Result<ChessMove, String> {
+    let invalid = || format!("illegal move {} in {}", san, board);
+    let text = san.trim_end_matches(['+', '#', '!', '?']).replace('0', "O");
+    if text == "O-O" || text == "O-O-O" {
+        let king = board.king_square(board.side_to_move());
+        let file = if text == "O-O" { File::G } else { File::C };
+        let target = Square::make_square(king.get_rank(), file);
+        return MoveGen::new_legal(board)
+            .find(|m| m.get_source() == king && m.get_dest() == target)
+            .ok_or_else(invalid);
+    }
+    let mut text = text.as_str();
+    let piece = text.chars().next().and_then(piece_from_char).unwrap_or(Piece::Pawn);
+    if piece != Piece::Pawn {
+        text = &text[1..];
+    }
+    let mut promotion = None;
+    if let Some(last) = text.chars().last()
+        && let Some(p) = piece_from_char(last)
+    {
+        promotion = Some(p);
+        text = text[..text.len() - 1].trim_end_matches('=');
+    }
+    if text.len() < 2 || !text.is_ascii() {
+        return Err(invalid());
+    }
+    let dest = Square::from_str(&text[text.len() - 2..]).map_err(|_| invalid())?;
+    let hint: String = text[..text.len() - 2].chars().filter(|&c| c != 'x' && c != '-').collect();
+    let matches: Vec<ChessMove> = MoveGen::new_legal(board)
+        .filter(|m| {
+            let from = m.get_source();
+            m.get_dest() == dest
+                && board.piece_on(from) == Some(piece)
+                && m.get_promotion() == promotion
+                && hint.chars().all(|c| match c {
+                    'a'..='h' => from.get_file().to_index() == c as usize - 'a' as usize,
+                    '1'..='8' => from.get_rank().to_index() == c as usize - '1' as usize,
+                    _ => false,
+                })
+        })
+        .collect();
+    match matches.as_slice() {
+        [mv] => Ok(*mv),
+        _ => Err(invalid()),
+    }
+}
+
+pub fn to_san(board: &Board, mv: ChessMove) -> String {
+    let from = mv.get_source();
+    let to = mv.get_dest();
+    let piece = board.piece_on(from).unwrap_or(Piece::Pawn);
+    let mut san = String::new();
+    if piece == Piece::King && (from.get_file().to_index() as i32 - to.get_file().to_index() as i32).abs() == 2 {
+        san.push_str(if to.get_file() == File::G { "O-O" } else { "O-O-O" });
+    } else {
+        let capture = board.piece_on(to).is_some() || (piece == Piece::Pawn && from.get_file() != to.get_file());
+        san.push_str(piece_char(piece));
+        if piece == Piece::Pawn {
+            if capture {
+                san.push((b'a' + from.get_file().to_index() as u8) as char);
+            }
+        } else {
+            let rivals: Vec<ChessMove> = MoveGen::new_legal(board)
+                .filter(|m| m.get_dest() == to && m.get_source() != from && board.piece_on(m.get_source()) == Some(piece))
+                .collect();
+            if !rivals.is_empty() {
+                let same_file = rivals.iter().any(|m| m.get_source().get_file() == from.get_file());
+                let same_rank = rivals.iter().any(|m| m.get_source().get_rank() == from.get_rank());
+                let square = from.to_string();
+                if !same_file {
+                    san.push_str(&square[..1]);
+                } else if !same_rank {
+                    san.push_str(&square[1..]);
+                } else {
+                    san.push_str(&square);
+                }
+            }
+        }
+        if capture {
+            san.push('x');
+        }
+        san.push_str(&to.to_string());
+        if let Some(p) = mv.get_promotion() {
+            san.push('=');
+            san.push_str(piece_char(p));
+        }
+    }
+    let after = board.make_move_new(mv);
+    if after.status() == BoardStatus::Checkmate {
+        san.push('#');
+    } else if *after.checkers() != EMPTY {
+        san.push('+');
+    }
+    san
+}
+
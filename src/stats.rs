@@ -0,0 +1,179 @@
+// Match statistics: Elo differences with confidence intervals, likelihood of
+// superiority and the sequential probability ratio test. Game results are
+// trinomial win/draw/loss counts; game pairs played from the same opening
+// with colours reversed are pentanomial counts of the pair score 0, 1/2, 1,
+// 3/2 and 2.
+
+// Two-sided 95% quantile of the normal distribution.
+const Z95: f64 = 1.959_964;
+
+pub fn elo(score: f64) -> f64 {
+    let score = score.clamp(1e-6, 1.0 - 1e-6);
+    400.0 * (score / (1.0 - score)).log10()
+}
+
+pub fn expected_score(elo: f64) -> f64 {
+    1.0 / (1.0 + 10f64.powf(-elo / 400.0))
+}
+
+// Abramowitz and Stegun 7.1.26, accurate to 1.5e-7.
+fn erf(x: f64) -> f64 {
+    let t = 1.0 / (1.0 + 0.327_591_1 * x.abs());
+    let poly = t * (0.254_829_592 + t * (-0.284_496_736 + t * (1.421_413_741 + t * (-1.453_152_027 + t * 1.061_405_429))));
+    (1.0 - poly * (-x * x).exp()).copysign(x)
+}
+
+fn normal_cdf(x: f64) -> f64 {
+    0.5 * (1.0 + erf(x / std::f64::consts::SQRT_2))
+}
+
+// Count, mean and variance of a sample given as (score, count) pairs, with
+// `prior` added to every count.
+fn moments(outcomes: &[(f64, u32)], prior: f64) -> Option<(f64, f64, f64)> {
+    let count = |c: u32| c as f64 + prior;
+    let n: f64 = outcomes.iter().map(|&(_, c)| c as f64).sum();
+    if n == 0.0 {
+        return None;
+    }
+    let n = n + prior * outcomes.len() as f64;
+    let mean = outcomes.iter().map(|&(s, c)| s * count(c)).sum::<f64>() / n;
+    let variance = outcomes.iter().map(|&(s, c)| (s - mean).powi(2) * count(c)).sum::<f64>() / n;
+    Some((n, mean, variance))
+}
+
+fn trinomial(wins: u32, draws: u32, losses: u32) -> [(f64, u32); 3] {
+    [(1.0, wins), (0.5, draws), (0.0, losses)]
+}
+
+fn pentanomial(pairs: &[u32; 5]) -> [(f64, u32); 5] {
+    std::array::from_fn(|i| (i as f64 / 4.0, pairs[i]))
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Estimate {
+    pub elo: f64,
+    // Half the width of the 95% confidence interval, in Elo.
+    pub error: f64,
+    // Likelihood of superiority: the probability that the first engine is
+    // the stronger one.
+    pub los: f64,
+}
+
+fn estimate(outcomes: &[(f64, u32)]) -> Option<Estimate> {
+    let (n, mean, variance) = moments(outcomes, 0.0)?;
+    let stderr = (variance / n).sqrt();
+    let los = match stderr > 0.0 {
+        true => normal_cdf((mean - 0.5) / stderr),
+        false if mean == 0.5 => 0.5,
+        false => (mean > 0.5) as u8 as f64,
+    };
+    Some(Estimate {
+        elo: elo(mean),
+        error: (elo(mean + Z95 * stderr) - elo(mean - Z95 * stderr)) / 2.0,
+        los,
+    })
+}
+
+pub fn estimate_wdl(wins: u32, draws: u32, losses: u32) -> Option<Estimate> {
+    estimate(&trinomial(wins, draws, losses))
+}
+
+pub fn estimate_pentanomial(pairs: &[u32; 5]) -> Option<Estimate> {
+    estimate(&pentanomial(pairs))
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SprtStatus {
+    Continue,
+    // elo0 holds: the change is not an improvement of elo1.
+    AcceptH0,
+    AcceptH1,
+}
+
+// Tests H0: elo = elo0 against H1: elo = elo1 with false positive rate
+// `alpha` and false negative rate `beta`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Sprt {
+    pub elo0: f64,
+    pub elo1: f64,
+    pub alpha: f64,
+    pub beta: f64,
+}
+
+impl Default for Sprt {
+    fn default() -> Self {
+        Sprt { elo0: 0.0, elo1: 5.0, alpha: 0.05, beta: 0.05 }
+    }
+}
+
+impl Sprt {
+    pub fn bounds(&self) -> (f64, f64) {
+        ((self.beta / (1.0 - self.alpha)).ln(), ((1.0 - self.beta) / self.alpha).ln())
+    }
+
+    // Log-likelihood ratio under the normal approximation used by fishtest,
+    // which also regularizes the counts so one-sided results have a variance.
+    fn llr(&self, outcomes: &[(f64, u32)]) -> f64 {
+        let Some((n, mean, variance)) = moments(outcomes, 1e-3) else { return 0.0 };
+        let (s0, s1) = (expected_score(self.elo0), expected_score(self.elo1));
+        (s1 - s0) * (2.0 * mean - s0 - s1) / (2.0 * variance / n)
+    }
+
+    pub fn llr_wdl(&self, wins: u32, draws: u32, losses: u32) -> f64 {
+        self.llr(&trinomial(wins, draws, losses))
+    }
+
+    pub fn llr_pentanomial(&self, pairs: &[u32; 5]) -> f64 {
+        self.llr(&pentanomial(pairs))
+    }
+
+    pub fn status(&self, llr: f64) -> SprtStatus {
+        let (lower, upper) = self.bounds();
+        if llr >= upper {
+            SprtStatus::AcceptH1
+        } else if llr <= lower {
+            SprtStatus::AcceptH0
+        } else {
+            SprtStatus::Continue
+        }
+    }
+}
+
+// Multi-line report in the style of cutechess and fastchess.
+pub fn summary(wdl: [u32; 3], pairs: &[u32; 5], sprt: Option<&Sprt>) -> String {
+    let [wins, draws, losses] = wdl;
+    let games = wins + draws + losses;
+    let mut text = String::new();
+    if let Some(e) = estimate_wdl(wins, draws, losses) {
+        text += &format!(
+            "Elo difference: {:.1} +/- {:.1}, LOS: {:.1} %, DrawRatio: {:.1} %\n",
+            e.elo,
+            e.error,
+            100.0 * e.los,
+            100.0 * draws as f64 / games as f64
+        );
+    }
+    if let Some(e) = estimate_pentanomial(pairs) {
+        text += &format!("Pentanomial {:?}: Elo {:.1} +/- {:.1}, LOS: {:.1} %\n", pairs, e.elo, e.error, 100.0 * e.los);
+    }
+    if let Some(sprt) = sprt {
+        let llr = sprt.llr_pentanomial(pairs);
+        let (lower, upper) = sprt.bounds();
+        let status = match sprt.status(llr) {
+            SprtStatus::Continue => "",
+            SprtStatus::AcceptH0 => " - H0 was accepted",
+            SprtStatus::AcceptH1 => " - H1 was accepted",
+        };
+        text += &format!(
+            "SPRT: llr {:.2} ({:.1}%), lbound {:.2}, ubound {:.2}, elo0 {} elo1 {}{}\n",
+            llr,
+            100.0 * llr / upper,
+            lower,
+            upper,
+            sprt.elo0,
+            sprt.elo1,
+            status
+        );
+    }
+    text
+}
@@ -0,0 +1,99 @@
+use crate::{dtm, syzygy, MATE};
+use chess::Board;
+
+// Search score for a tablebase win without a known distance to mate, kept
+// below real mate scores so a mate found by the search is still preferred.
+pub const TB_WIN: i32 = MATE - 1000;
+
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub enum Wdl {
+    Loss = -2,
+    BlessedLoss = -1,
+    Draw = 0,
+    CursedWin = 1,
+    Win = 2,
+}
+
+impl Wdl {
+    pub(crate) fn from_value(value: i32) -> Wdl {
+        match value {
+            i32::MIN..=-2 => Wdl::Loss,
+            -1 => Wdl::BlessedLoss,
+            0 => Wdl::Draw,
+            1 => Wdl::CursedWin,
+            _ => Wdl::Win,
+        }
+    }
+}
+
+// Distance to mate in plies from the side to move's point of view.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Dtm {
+    Win(u32),
+    Loss(u32),
+    Draw,
+}
+
+impl Dtm {
+    pub fn wdl(self) -> Wdl {
+        match self {
+            Dtm::Win(_) => Wdl::Win,
+            Dtm::Loss(_) => Wdl::Loss,
+            Dtm::Draw => Wdl::Draw,
+        }
+    }
+}
+
+// A set of endgame tables the search can consult. Probes return None for
+// positions the tables do not cover.
+pub trait Tablebase: Send + Sync {
+    fn max_pieces(&self) -> usize;
+
+    fn probe_wdl(&self, board: &Board) -> Option<Wdl>;
+
+    fn probe_dtm(&self, _board: &Board) -> Option<Dtm> {
+        None
+    }
+}
+
+// Score used inside the search for a probed position at `ply`.
+pub fn wdl_score(wdl: Wdl, ply: usize) -> i32 {
+    match wdl {
+        Wdl::Win => TB_WIN - ply as i32,
+        Wdl::Loss => -TB_WIN + ply as i32,
+        other => 2 * other as i32,
+    }
+}
+
+pub fn dtm_score(dtm: Dtm, ply: usize) -> i32 {
+    match dtm {
+        Dtm::Win(plies) => MATE - ply as i32 - plies as i32,
+        Dtm::Loss(plies) => -MATE + ply as i32 + plies as i32,
+        Dtm::Draw => 0,
+    }
+}
+
+// Exact mate distances are preferred; WDL-only tables give a score that
+// still needs the search to find the mate.
+pub fn score(tb: &dyn Tablebase, board: &Board, ply: usize) -> Option<i32> {
+    if let Some(dtm) = tb.probe_dtm(board) {
+        return Some(dtm_score(dtm, ply));
+    }
+    tb.probe_wdl(board).map(|wdl| wdl_score(wdl, ply))
+}
+
+// Probes every loaded set of tables in turn: generated DTM tables first,
+// then Syzygy.
+pub fn probe(board: &Board, ply: usize) -> Option<i32> {
+    let pieces = board.combined().popcnt() as usize;
+    if pieces <= dtm::cardinality()
+        && let Some(tb) = dtm::active()
+        && let Some(score) = score(&*tb, board, ply)
+    {
+        return Some(score);
+    }
+    if pieces <= syzygy::cardinality() {
+        return score(&*syzygy::active()?, board, ply);
+    }
+    None
+}
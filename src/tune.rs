@@ -0,0 +1,224 @@
+use crate::evaluate_with;
+use crate::params::EvalParams;
+use chess::{Board, ChessMove, Color, MoveGen};
+use std::fs;
+use std::path::Path;
+use std::str::FromStr;
+use std::thread;
+
+pub struct TuningPosition {
+    pub board: Board,
+    // Game result from White's point of view: 1.0 win, 0.5 draw, 0.0 loss.
+    pub result: f64,
+}
+
+// Accepts the usual Texel data layouts: "<fen> [1.0]", "<fen> [1-0]",
+// "<fen> | 0.5" and EPD lines ending in `c9 "1/2-1/2";`.
+pub fn parse_position_line(line: &str) -> Option<TuningPosition> {
+    let line = line.trim();
+    if line.is_empty() || line.starts_with('#') {
+        return None;
+    }
+    let (result, fen_end) = [
+        ("1-0", 1.0),
+        ("0-1", 0.0),
+        ("1/2-1/2", 0.5),
+        ("1.0", 1.0),
+        ("0.0", 0.0),
+        ("0.5", 0.5),
+    ]
+    .iter()
+    .filter_map(|&(token, value)| line.rfind(token).map(|pos| (value, pos)))
+    .max_by_key(|&(_, pos)| pos)?;
+
+    let fen: String = line[..fen_end]
+        .trim_end_matches(|c: char| c.is_whitespace() || matches!(c, '[' | '|' | '"' | ';'))
+        .trim_end_matches("c9")
+        .trim()
+        .to_string();
+    let fields: Vec<&str> = fen.split_whitespace().collect();
+    let board = match fields.len() {
+        4 => Board::from_str(&format!("{} 0 1", fields.join(" "))).ok()?,
+        n if n >= 6 => Board::from_str(&fields[..6].join(" ")).ok()?,
+        _ => return None,
+    };
+    Some(TuningPosition { board, result })
+}
+
+pub fn load_positions(path: impl AsRef<Path>) -> Result<Vec<TuningPosition>, String> {
+    let path = path.as_ref();
+    let text = fs::read_to_string(path).map_err(|e| format!("cannot read {}: {}", path.display(), e))?;
+    Ok(text.lines().filter_map(parse_position_line).collect())
+}
+
+fn white_eval(board: &Board, params: &EvalParams) -> i32 {
+    let score = evaluate_with(board, params);
+    match board.side_to_move() {
+        Color::White => score,
+        Color::Black => -score,
+    }
+}
+
+fn quiet_search(board: &Board, mut alpha: i32, beta: i32, params: &EvalParams) -> (i32, Board) {
+    let stand_pat = evaluate_with(board, params);
+    let mut best = (stand_pat, *board);
+    if stand_pat >= beta {
+        return best;
+    }
+    if stand_pat > alpha {
+        alpha = stand_pat;
+    }
+    let captures: Vec<ChessMove> = MoveGen::new_legal(board)
+        .filter(|m| board.piece_on(m.get_dest()).is_some())
+        .collect();
+    for mv in captures {
+        let (score, leaf) = quiet_search(&board.make_move_new(mv), -beta, -alpha, params);
+        let score = -score;
+        if score > best.0 {
+            best = (score, leaf);
+            if score > alpha {
+                alpha = score;
+            }
+        }
+        if score >= beta {
+            break;
+        }
+    }
+    best
+}
+
+// Follows the capture sequence the quiescence search prefers, so the tuner
+// evaluates positions where the static eval is meaningful.
+pub fn quiet_leaf(board: &Board, params: &EvalParams) -> Board {
+    quiet_search(board, -30000, 30000, params).1
+}
+
+pub fn resolve_positions(positions: &mut [TuningPosition], params: &EvalParams) {
+    for pos in positions.iter_mut() {
+        pos.board = quiet_leaf(&pos.board, params);
+    }
+}
+
+pub fn sigmoid(eval: f64, k: f64) -> f64 {
+    1.0 / (1.0 + 10f64.powf(-k * eval / 400.0))
+}
+
+pub fn mean_squared_error(positions: &[TuningPosition], params: &EvalParams, k: f64, threads: usize) -> f64 {
+    if positions.is_empty() {
+        return 0.0;
+    }
+    let chunk = positions.len().div_ceil(threads.max(1));
+    let total: f64 = thread::scope(|s| {
+        let handles: Vec<_> = positions
+            .chunks(chunk)
+            .map(|part| {
+                s.spawn(move || {
+                    part.iter()
+                        .map(|p| {
+                            let diff = p.result - sigmoid(white_eval(&p.board, params) as f64, k);
+                            diff * diff
+                        })
+                        .sum::<f64>()
+                })
+            })
+            .collect();
+        handles.into_iter().map(|h| h.join().unwrap()).sum()
+    });
+    total / positions.len() as f64
+}
+
+// Scaling constant K that best maps the current eval onto results; found by
+// narrowing a grid search around the best value.
+pub fn find_k(positions: &[TuningPosition], params: &EvalParams, threads: usize) -> f64 {
+    let mut best_k = 1.0;
+    let mut best_err = mean_squared_error(positions, params, best_k, threads);
+    let mut step = 0.5;
+    for _ in 0..6 {
+        let center = best_k;
+        for i in -5..=5 {
+            let k = center + i as f64 * step;
+            if k <= 0.0 {
+                continue;
+            }
+            let err = mean_squared_error(positions, params, k, threads);
+            if err < best_err {
+                best_err = err;
+                best_k = k;
+            }
+        }
+        step /= 5.0;
+    }
+    best_k
+}
+
+pub struct Tuner {
+    pub params: EvalParams,
+    pub k: f64,
+    pub threads: usize,
+    pub step: i32,
+    pub best_error: f64,
+}
+
+impl Tuner {
+    pub fn new(params: EvalParams, positions: &[TuningPosition], threads: usize) -> Self {
+        let k = find_k(positions, &params, threads);
+        let best_error = mean_squared_error(positions, &params, k, threads);
+        Tuner { params, k, threads, step: 1, best_error }
+    }
+
+    // One pass of Texel local search: nudge every weight up, then down, and
+    // keep whichever change lowers the error. Returns how many weights moved.
+    pub fn epoch(&mut self, positions: &[TuningPosition]) -> usize {
+        let mut improved = 0;
+        for index in tunable_indices(&self.params) {
+            for delta in [self.step, -self.step] {
+                let mut candidate = self.params.clone();
+                *param_at(&mut candidate, index) += delta;
+                let err = mean_squared_error(positions, &candidate, self.k, self.threads);
+                if err < self.best_error {
+                    self.best_error = err;
+                    self.params = candidate;
+                    improved += 1;
+                    break;
+                }
+            }
+        }
+        improved
+    }
+}
+
+// Slots that only exist so the arrays can be indexed by piece or distance.
+// The eval never reads them (the king's value cancels out), so tuning them
+// would only burn error evaluations.
+const UNUSED_WEIGHTS: &[(&str, usize)] = &[
+    ("piece_values", 5),
+    ("king_attack_weights", 0),
+    ("king_attack_weights", 5),
+    ("king_safe_check_weights", 0),
+    ("king_safe_check_weights", 5),
+    ("king_storm_weights", 0),
+];
+
+fn tunable_indices(params: &EvalParams) -> Vec<usize> {
+    let mut indices = Vec::new();
+    let mut offset = 0;
+    for (name, values) in params.fields() {
+        for i in 0..values.len() {
+            if !UNUSED_WEIGHTS.contains(&(name, i)) {
+                indices.push(offset + i);
+            }
+        }
+        offset += values.len();
+    }
+    indices
+}
+
+fn param_at(params: &mut EvalParams, mut index: usize) -> &mut i32 {
+    for (_, values) in params.fields_mut() {
+        if index < values.len() {
+            return &mut values[index];
+        }
+        index -= values.len();
+    }
+    panic!("parameter index out of range");
+}
@@ -0,0 +1,568 @@
+#[cfg(feature = "nnue")]
+use crate::nnue;
+use crate::params::{set_active_params, EvalParams};
+use crate::{axelrot, bench, book, dtm, eval_trace, evaluation, experience, perft, syzygy};
+use chess::{Board, ChessMove, Color, MoveGen, Piece, Square, EMPTY};
+use std::fmt;
+use std::io::BufRead;
+use std::str::FromStr;
+use std::sync::{LazyLock, RwLock};
+use std::time::Instant;
+
+// Registry of the engine's UCI options: each one has a type, a default and,
+// for spins and combos, the values it accepts. The `uci` listing is generated
+// from it and `setoption` is validated against it.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum OptionKind {
+    Check { default: bool },
+    Spin { default: i64, min: i64, max: i64 },
+    Combo { default: &'static str, vars: &'static [&'static str] },
+    String { default: &'static str },
+    Button,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum OptionValue {
+    Check(bool),
+    Spin(i64),
+    String(String),
+    Button,
+}
+
+#[derive(Clone, Debug)]
+pub struct UciOption {
+    pub name: &'static str,
+    pub kind: OptionKind,
+    pub value: OptionValue,
+}
+
+impl UciOption {
+    fn new(name: &'static str, kind: OptionKind) -> Self {
+        let value = match &kind {
+            OptionKind::Check { default } => OptionValue::Check(*default),
+            OptionKind::Spin { default, .. } => OptionValue::Spin(*default),
+            OptionKind::String { default: "<empty>" } => OptionValue::String(String::new()),
+            OptionKind::Combo { default, .. } | OptionKind::String { default } => OptionValue::String(default.to_string()),
+            OptionKind::Button => OptionValue::Button,
+        };
+        UciOption { name, kind, value }
+    }
+
+    // Checks `value` against the option's type and bounds.
+    fn parse(&self, value: Option<&str>) -> Result<OptionValue, String> {
+        let value = value.map(str::trim);
+        let invalid = |v: &str| format!("invalid value {} for option {}", v, self.name);
+        match (&self.kind, value) {
+            (OptionKind::Button, _) => Ok(OptionValue::Button),
+            (OptionKind::String { .. }, v) => Ok(OptionValue::String(v.filter(|v| *v != "<empty>").unwrap_or("").to_string())),
+            (_, None | Some("")) => Err(format!("missing value for option {}", self.name)),
+            (OptionKind::Check { .. }, Some(v)) if v.eq_ignore_ascii_case("true") => Ok(OptionValue::Check(true)),
+            (OptionKind::Check { .. }, Some(v)) if v.eq_ignore_ascii_case("false") => Ok(OptionValue::Check(false)),
+            (OptionKind::Check { .. }, Some(v)) => Err(invalid(v)),
+            (OptionKind::Spin { min, max, .. }, Some(v)) => match v.parse::<i64>() {
+                Ok(n) if (*min..=*max).contains(&n) => Ok(OptionValue::Spin(n)),
+                Ok(_) => Err(format!("value {} for option {} is outside {}..{}", v, self.name, min, max)),
+                Err(_) => Err(invalid(v)),
+            },
+            (OptionKind::Combo { vars, .. }, Some(v)) => vars
+                .iter()
+                .find(|var| var.eq_ignore_ascii_case(v))
+                .map(|var| OptionValue::String(var.to_string()))
+                .ok_or_else(|| invalid(v)),
+        }
+    }
+}
+
+impl fmt::Display for UciOption {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "option name {} type ", self.name)?;
+        match &self.kind {
+            OptionKind::Check { default } => write!(f, "check default {}", default),
+            OptionKind::Spin { default, min, max } => write!(f, "spin default {} min {} max {}", default, min, max),
+            OptionKind::Combo { default, vars } => {
+                write!(f, "combo default {}", default)?;
+                vars.iter().try_for_each(|var| write!(f, " var {}", var))
+            }
+            OptionKind::String { default } => write!(f, "string default {}", default),
+            OptionKind::Button => write!(f, "button"),
+        }
+    }
+}
+
+// Splits "setoption name <id> [value <x>]"; both parts may contain spaces.
+pub fn parse_setoption(line: &str) -> Result<(&str, Option<&str>), String> {
+    let rest = line.strip_prefix("setoption").map(str::trim_start).unwrap_or(line);
+    let Some(rest) = rest.strip_prefix("name ") else {
+        return Err(format!("malformed setoption: {}", line));
+    };
+    let (name, value) = match rest.find(" value") {
+        Some(i) if rest[i + 6..].is_empty() || rest[i + 6..].starts_with(' ') => (&rest[..i], Some(rest[i + 6..].trim())),
+        _ => (rest, None),
+    };
+    match name.trim() {
+        "" => Err(format!("malformed setoption: {}", line)),
+        name => Ok((name, value)),
+    }
+}
+
+pub const DEFAULT_HASH_MB: usize = 16;
+
+// The option values search and evaluation read. `SearchInfo::new` takes a
+// copy, so a search sees the values set before it started.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct EngineConfig {
+    pub hash_mb: usize,
+    // Only `go perft` runs on several threads; the search is single-threaded.
+    pub threads: usize,
+    pub multi_pv: usize,
+    pub nullmove: bool,
+    pub analyse_mode: bool,
+}
+
+impl Default for EngineConfig {
+    fn default() -> Self {
+        Options::new().config()
+    }
+}
+
+pub struct Options {
+    options: Vec<UciOption>,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Options {
+    pub fn new() -> Self {
+        use OptionKind::*;
+        let mut options = vec![
+            UciOption::new("Hash", Spin { default: DEFAULT_HASH_MB as i64, min: 1, max: 65536 }),
+            UciOption::new("Threads", Spin { default: 1, min: 1, max: 256 }),
+            UciOption::new("MultiPV", Spin { default: 1, min: 1, max: 256 }),
+            UciOption::new("Nullmove", Check { default: true }),
+            UciOption::new("UCI_AnalyseMode", Check { default: false }),
+            UciOption::new("UCI_Opponent", String { default: "none none computer Unknown" }),
+            UciOption::new(
+                "UCI_EngineAbout",
+                String { default: "Axelrot by felipelangoni, see github.com/felipelangoni/axelrot" },
+            ),
+            UciOption::new("SyzygyPath", String { default: "<empty>" }),
+            UciOption::new("SyzygyProbeLimit", Spin { default: 7, min: 0, max: 7 }),
+            UciOption::new("DTMPath", String { default: "<empty>" }),
+            UciOption::new("OwnBook", Check { default: false }),
+            UciOption::new("BookFile", String { default: "<empty>" }),
+            UciOption::new("BookBestMove", Check { default: false }),
+            UciOption::new("BookDepth", Spin { default: 255, min: 0, max: 255 }),
+            UciOption::new("ExperienceFile", String { default: "<empty>" }),
+            UciOption::new("ExperienceRead", Check { default: true }),
+            UciOption::new("ExperienceWrite", Check { default: true }),
+            UciOption::new("ExperienceSize", Spin { default: 100_000, min: 0, max: 10_000_000 }),
+            UciOption::new("EvalFile", String { default: "<empty>" }),
+        ];
+        if cfg!(feature = "nnue") {
+            options.push(UciOption::new("UseNNUE", Check { default: false }));
+        }
+        Options { options }
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &UciOption> {
+        self.options.iter()
+    }
+
+    // Option names are case-insensitive, as the UCI protocol asks.
+    pub fn get(&self, name: &str) -> Option<&UciOption> {
+        self.options.iter().find(|o| o.name.eq_ignore_ascii_case(name))
+    }
+
+    // Validates and stores a new value; returns the option's canonical name.
+    pub fn set(&mut self, name: &str, value: Option<&str>) -> Result<&'static str, String> {
+        let option = self
+            .options
+            .iter_mut()
+            .find(|o| o.name.eq_ignore_ascii_case(name))
+            .ok_or_else(|| format!("unknown option {}", name))?;
+        option.value = option.parse(value)?;
+        Ok(option.name)
+    }
+
+    pub fn check(&self, name: &str) -> bool {
+        matches!(self.get(name).map(|o| &o.value), Some(OptionValue::Check(true)))
+    }
+
+    pub fn spin(&self, name: &str) -> i64 {
+        match self.get(name).map(|o| &o.value) {
+            Some(OptionValue::Spin(n)) => *n,
+            _ => 0,
+        }
+    }
+
+    pub fn string(&self, name: &str) -> &str {
+        match self.get(name).map(|o| &o.value) {
+            Some(OptionValue::String(s)) => s,
+            _ => "",
+        }
+    }
+
+    pub fn config(&self) -> EngineConfig {
+        EngineConfig {
+            hash_mb: self.spin("Hash") as usize,
+            threads: self.spin("Threads") as usize,
+            multi_pv: self.spin("MultiPV") as usize,
+            nullmove: self.check("Nullmove"),
+            analyse_mode: self.check("UCI_AnalyseMode"),
+        }
+    }
+}
+
+static CONFIG: LazyLock<RwLock<EngineConfig>> = LazyLock::new(|| RwLock::new(EngineConfig::default()));
+
+pub fn config() -> EngineConfig {
+    CONFIG.read().unwrap_or_else(|e| e.into_inner()).clone()
+}
+
+pub fn set_config(config: EngineConfig) {
+    *CONFIG.write().unwrap_or_else(|e| e.into_inner()) = config;
+}
+
+// A position set by the `position` command; `error` holds the first illegal
+// move when the move list had to be cut short.
+#[derive(Clone, Debug)]
+pub struct Position {
+    pub board: Board,
+    // Plies since the start of the game, from the FEN move counters.
+    pub game_ply: usize,
+    pub error: Option<String>,
+}
+
+// Parses the arguments of `position`. A bad FEN or a malformed command is an
+// error; moves are checked one by one and the position stops before the
+// first one that is not legal.
+pub fn parse_position(args: &str) -> Result<Position, String> {
+    let args = args.trim();
+    let (setup, moves) = match args.split_once(" moves") {
+        Some((setup, moves)) if moves.is_empty() || moves.starts_with(' ') => (setup.trim(), Some(moves)),
+        _ => (args.strip_suffix("moves").unwrap_or(args).trim(), None),
+    };
+    let (mut board, mut game_ply) = if setup == "startpos" {
+        (Board::default(), 0)
+    } else if let Some(fen) = setup.strip_prefix("fen ") {
+        let (board, fullmove) = parse_fen(fen)?;
+        (board, 2 * (fullmove - 1) + (board.side_to_move() == Color::Black) as usize)
+    } else {
+        return Err(format!("malformed position command: position {}", args));
+    };
+    for (played, text) in moves.unwrap_or("").split_whitespace().enumerate() {
+        let legal = ChessMove::from_str(text).ok().filter(|mv| MoveGen::new_legal(&board).any(|m| m == *mv));
+        let Some(mv) = legal else {
+            let error = format!("illegal move {} in {}, stopped after {} moves", text, board, played);
+            return Ok(Position { board, game_ply, error: Some(error) });
+        };
+        board = board.make_move_new(mv);
+        game_ply += 1;
+    }
+    Ok(Position { board, game_ply, error: None })
+}
+
+// Validates a FEN, also accepting the 4-field EPD form without move
+// counters, and returns the board with its fullmove number.
+pub fn parse_fen(fen: &str) -> Result<(Board, usize), String> {
+    let invalid = |why: &str| format!("invalid FEN {}: {}", fen, why);
+    let fields: Vec<&str> = fen.split_whitespace().collect();
+    if fields.len() != 4 && fields.len() != 6 {
+        return Err(invalid("expected 4 or 6 fields"));
+    }
+    let ranks: Vec<&str> = fields[0].split('/').collect();
+    if ranks.len() != 8 {
+        return Err(invalid("expected 8 ranks"));
+    }
+    for rank in &ranks {
+        let mut files = 0;
+        for c in rank.chars() {
+            match c {
+                '1'..='8' => files += c as usize - '0' as usize,
+                'p' | 'n' | 'b' | 'r' | 'q' | 'k' | 'P' | 'N' | 'B' | 'R' | 'Q' | 'K' => files += 1,
+                _ => return Err(invalid(&format!("unexpected character {}", c))),
+            }
+        }
+        if files != 8 {
+            return Err(invalid(&format!("rank {} does not have 8 squares", rank)));
+        }
+    }
+    let count = |c: char| fields[0].matches(c).count();
+    if count('K') != 1 || count('k') != 1 {
+        return Err(invalid("each side needs exactly one king"));
+    }
+    if count('P') > 8 || count('p') > 8 {
+        return Err(invalid("too many pawns"));
+    }
+    if ranks[0].contains(['P', 'p']) || ranks[7].contains(['P', 'p']) {
+        return Err(invalid("pawns on the first or last rank"));
+    }
+    if fields[1] != "w" && fields[1] != "b" {
+        return Err(invalid("side to move must be w or b"));
+    }
+    if fields[2] != "-" && (fields[2].is_empty() || !fields[2].chars().all(|c| "KQkq".contains(c))) {
+        return Err(invalid("bad castling field"));
+    }
+    if fields[3] != "-" {
+        let rank = if fields[1] == "w" { '6' } else { '3' };
+        if Square::from_str(fields[3]).is_err() || !fields[3].ends_with(rank) {
+            return Err(invalid("bad en passant square"));
+        }
+    }
+    let fullmove = match fields.get(4..6) {
+        Some([halfmove, fullmove]) => match (halfmove.parse::<usize>(), fullmove.parse::<usize>()) {
+            (Ok(_), Ok(n)) if n >= 1 => n,
+            _ => return Err(invalid("bad move counters")),
+        },
+        _ => 1,
+    };
+    let full_fen = format!("{} {} {} {} {}", fields[0], fields[1], fields[2], fields[3], fields.get(4..6).map_or("0 1".to_string(), |c| c.join(" ")));
+    let board = Board::from_str(&full_fen).map_err(|_| invalid("illegal position"))?;
+    if chess::get_king_moves(board.king_square(Color::White)) & board.pieces(Piece::King) != EMPTY {
+        return Err(invalid("kings are adjacent"));
+    }
+    if let Ok(target) = Square::from_str(fields[3]) {
+        let pawn = Square::make_square(chess::Rank::from_index(if fields[1] == "w" { 4 } else { 3 }), target.get_file());
+        if board.piece_on(target).is_some() || board.piece_on(pawn) != Some(Piece::Pawn) || board.color_on(pawn) == Some(board.side_to_move()) {
+            return Err(invalid("en passant square without a pawn that just moved"));
+        }
+    }
+    for (flag, color, king, rook) in [('K', Color::White, "e1", "h1"), ('Q', Color::White, "e1", "a1"), ('k', Color::Black, "e8", "h8"), ('q', Color::Black, "e8", "a8")] {
+        let on = |square: &str, piece| {
+            let square = Square::from_str(square).unwrap();
+            board.piece_on(square) == Some(piece) && board.color_on(square) == Some(color)
+        };
+        if fields[2].contains(flag) && !(on(king, Piece::King) && on(rook, Piece::Rook)) {
+            return Err(invalid(&format!("castling right {} without king and rook at home", flag)));
+        }
+    }
+    Ok((board, fullmove))
+}
+
+// UCI front end, the counterpart of `xboard::run`: one command per line,
+// each handled by `Engine::command`, searches run to completion in between.
+pub fn run<R: BufRead>(input: R) {
+    let mut engine = Engine::new();
+    for line in input.lines() {
+        let Ok(line) = line else { break };
+        if !engine.command(line.trim()) {
+            break;
+        }
+    }
+    if let Err(e) = experience::finish_game(&engine.board) {
+        println!("info string error: {}", e);
+    }
+}
+
+struct Engine {
+    board: Board,
+    // Plies since the start of the game, for the book depth limit.
+    game_ply: usize,
+    debug_mode: bool,
+    options: Options,
+    sent_registration: bool,
+    sent_copyprotection: bool,
+}
+
+impl Engine {
+    fn new() -> Self {
+        Engine {
+            board: Board::default(),
+            game_ply: 0,
+            debug_mode: false,
+            options: Options::new(),
+            sent_registration: false,
+            sent_copyprotection: false,
+        }
+    }
+
+    // Handles one command; false ends the session.
+    fn command(&mut self, input: &str) -> bool {
+        if self.debug_mode {
+            println!("info string received: {}", input);
+        }
+        let (cmd, args) = input.split_once(' ').unwrap_or((input, ""));
+        let args = args.trim();
+        match cmd {
+            "d" | "D" => println!("{}", self.board),
+            "uci" => {
+                println!("id name axelrot");
+                println!("id author felipelangoni");
+                for opt in self.options.iter() {
+                    println!("{}", opt);
+                }
+                if !self.sent_copyprotection {
+                    println!("copyprotection ok");
+                    self.sent_copyprotection = true;
+                }
+                if !self.sent_registration {
+                    println!("registration ok");
+                    self.sent_registration = true;
+                }
+                println!("uciok");
+            }
+            "debug" => {
+                self.debug_mode = args.eq_ignore_ascii_case("on");
+                println!("info string debug mode {}", if self.debug_mode { "on" } else { "off" });
+            }
+            "isready" => println!("readyok"),
+            "setoption" => match parse_setoption(input).and_then(|(name, value)| self.options.set(name, value)) {
+                Ok(name) => {
+                    apply_option(name, &self.options);
+                    set_config(self.options.config());
+                }
+                Err(e) => println!("info string error: {}", e),
+            },
+            "register" => {
+                println!("registration checking");
+                println!("registration ok");
+            }
+            "ucinewgame" => {
+                if let Err(e) = experience::finish_game(&self.board) {
+                    println!("info string error: {}", e);
+                }
+                self.board = Board::default();
+                self.game_ply = 0;
+                println!("info string ucinewgame received");
+            }
+            "position" => match parse_position(args) {
+                Ok(position) => {
+                    self.board = position.board;
+                    self.game_ply = position.game_ply;
+                    if let Some(e) = position.error {
+                        println!("info string error: {}", e);
+                    }
+                }
+                Err(e) => println!("info string error: {}", e),
+            },
+            "eval" => self.eval(),
+            "bench" => match args {
+                "" => {
+                    bench::print(bench::BENCH_DEPTH);
+                }
+                depth => match depth.parse() {
+                    Ok(depth) => {
+                        bench::print(depth);
+                    }
+                    Err(_) => println!("info string error: invalid bench depth {}", depth),
+                },
+            },
+            "go" => match args.strip_prefix("perft") {
+                Some(depth) if depth.is_empty() || depth.starts_with(' ') => self.perft(depth.trim()),
+                _ => self.go(args),
+            },
+            "stop" => println!("info string stop received"),
+            "ponderhit" => println!("info string ponderhit received"),
+            "quit" => return false,
+            _ => {}
+        }
+        true
+    }
+
+    fn eval(&self) {
+        let stm = match self.board.side_to_move() {
+            Color::White => "White",
+            Color::Black => "Black",
+        };
+        print!("{}", eval_trace::trace(&self.board));
+        println!("info string eval: side to move: {}, score: {}", stm, evaluation(&self.board));
+        #[cfg(feature = "nnue")]
+        if let Some(net) = nnue::active_network() {
+            println!("info string nnue eval: side to move: {}, score: {}", stm, net.evaluate(&self.board));
+        }
+    }
+
+    fn perft(&self, depth: &str) {
+        let Ok(depth) = depth.parse::<u32>() else {
+            println!("info string error: invalid perft depth {}", depth);
+            return;
+        };
+        let config = config();
+        let options = perft::PerftOptions { hash_mb: config.hash_mb, threads: config.threads };
+        let start = Instant::now();
+        let counts = perft::divide(&self.board, depth, options);
+        for (mv, nodes) in &counts {
+            println!("{}: {}", mv, nodes);
+        }
+        let nodes: u64 = if depth == 0 { 1 } else { counts.iter().map(|(_, n)| n).sum() };
+        let elapsed = start.elapsed().as_millis() as u64;
+        println!();
+        println!("Nodes searched: {}", nodes);
+        println!("info string perft time {} ms nps {}", elapsed, nodes * 1000 / elapsed.max(1));
+    }
+
+    fn go(&self, args: &str) {
+        let value = |name: &str, default: u64| {
+            args.split_whitespace().skip_while(|&t| t != name).nth(1).and_then(|v| v.parse().ok()).unwrap_or(default)
+        };
+        let (wtime, btime) = (value("wtime", 300_000), value("btime", 300_000));
+        let (winc, binc) = (value("winc", 0), value("binc", 0));
+        let depth = value("depth", 20) as i32;
+        if let Some(mv) = book::probe(&self.board, self.game_ply) {
+            println!("info string book move {}", mv);
+            println!("bestmove {}", mv);
+            return;
+        }
+        println!("bestmove {}", axelrot(&self.board, depth, wtime, btime, winc, binc));
+    }
+}
+
+// Side effects of a newly set option; values were validated by the registry.
+fn apply_option(name: &str, options: &Options) {
+    let loaded = |result: Result<usize, String>, what: &str| match result {
+        Ok(count) => println!("info string {}", what.replace("{}", &count.to_string())),
+        Err(e) => println!("info string error: {}", e),
+    };
+    let value = options.string(name);
+    match name {
+        "EvalFile" => load_eval_file(value),
+        #[cfg(feature = "nnue")]
+        "UseNNUE" => nnue::set_use_nnue(options.check(name)),
+        "SyzygyPath" => loaded(syzygy::set_path(value), "found {} tablebases"),
+        "SyzygyProbeLimit" => syzygy::set_probe_limit(options.spin(name) as usize),
+        "DTMPath" => loaded(dtm::set_path(value), "found {} DTM tables"),
+        "OwnBook" => book::set_own_book(options.check(name)),
+        "BookFile" => loaded(book::set_book_file(value), "loaded {} book entries"),
+        "BookBestMove" => book::set_best_move(options.check(name)),
+        "BookDepth" => book::set_book_depth(options.spin(name) as usize),
+        "ExperienceFile" => loaded(experience::set_file(value), "loaded {} experience entries"),
+        "ExperienceRead" => experience::set_read(options.check(name)),
+        "ExperienceWrite" => experience::set_write(options.check(name)),
+        "ExperienceSize" => experience::set_size(options.spin(name) as usize),
+        _ => {}
+    }
+}
+
+// EvalFile accepts either an NNUE network or a JSON parameter set; the file's
+// header decides which one is loaded.
+fn load_eval_file(path: &str) {
+    if path.is_empty() || path == "<empty>" {
+        set_active_params(EvalParams::default());
+        #[cfg(feature = "nnue")]
+        nnue::set_active_network(None);
+        println!("info string using built-in evaluation parameters");
+        return;
+    }
+    #[cfg(feature = "nnue")]
+    if nnue::is_network_file(path) {
+        match nnue::Network::load(path) {
+            Ok(net) => {
+                println!("info string loaded NNUE network from {} ({} hidden)", path, net.hidden);
+                nnue::set_active_network(Some(net));
+            }
+            Err(e) => println!("info string error: {}", e),
+        }
+        return;
+    }
+    match EvalParams::load(path) {
+        Ok(params) => {
+            set_active_params(params);
+            println!("info string loaded evaluation parameters from {}", path);
+        }
+        Err(e) => println!("info string error: {}", e),
+    }
+}
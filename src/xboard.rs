@@ -0,0 +1,370 @@
+use crate::{book, experience, mate_in, pgn, search, think, uci, Protocol, SearchInfo, SearchResult};
+use chess::{Board, BoardStatus, ChessMove, Color, MoveGen};
+use std::io::BufRead;
+use std::str::FromStr;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Sender};
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+
+// XBoard/CECP front end. Input is read on its own thread so the engine can
+// take commands ("?", "exit", "force", ...) while a search is running; the
+// search reports back through the same channel.
+const FEATURES: &str = "feature myname=\"axelrot\" ping=1 setboard=1 usermove=1 playother=1 san=0 time=1 draw=1 \
+                        analyze=1 colors=0 reuse=1 sigint=0 sigterm=0 done=1";
+const MAX_DEPTH: i32 = 64;
+// Draw offers are accepted once the last search saw us this far behind.
+const DRAW_ACCEPT_SCORE: i32 = -50;
+
+// CECP thinking score: centipawns, or 100000 + N for a mate in N moves.
+pub fn format_score(score: i32) -> String {
+    match mate_in(score) {
+        Some(moves) if moves > 0 => (100_000 + moves).to_string(),
+        Some(moves) => (-100_000 + moves).to_string(),
+        None => score.to_string(),
+    }
+}
+
+enum Event {
+    Line(String),
+    Done(u64, SearchResult),
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Mode {
+    Think,
+    Analyze,
+}
+
+struct Search {
+    id: u64,
+    mode: Mode,
+    stop: Arc<AtomicBool>,
+    handle: JoinHandle<()>,
+}
+
+struct Engine {
+    events: Sender<Event>,
+    board: Board,
+    // Positions before each move of the game, for undo/remove.
+    history: Vec<Board>,
+    start_ply: usize,
+    force: bool,
+    engine_color: Color,
+    post: bool,
+    analyzing: bool,
+    // Time control from `level`: moves per session (0 = whole game), base
+    // and increment in milliseconds.
+    moves_per_session: u64,
+    increment: u64,
+    move_time: Option<u64>,
+    max_depth: i32,
+    time_left: u64,
+    last_score: Option<i32>,
+    search: Option<Search>,
+    next_id: u64,
+}
+
+pub fn run<R: BufRead + Send + 'static>(input: R) {
+    let (events, receiver) = mpsc::channel();
+    let lines = events.clone();
+    thread::spawn(move || {
+        for line in input.lines() {
+            let Ok(line) = line else { break };
+            if lines.send(Event::Line(line)).is_err() {
+                break;
+            }
+        }
+        let _ = lines.send(Event::Line("quit".to_string()));
+    });
+    let mut engine = Engine::new(events);
+    for event in receiver {
+        match event {
+            Event::Line(line) => {
+                if !engine.command(line.trim()) {
+                    break;
+                }
+            }
+            Event::Done(id, result) => engine.search_done(id, result),
+        }
+    }
+    engine.stop();
+    if let Err(e) = experience::finish_game(&engine.board) {
+        println!("telluser error: {}", e);
+    }
+}
+
+impl Engine {
+    fn new(events: Sender<Event>) -> Self {
+        Engine {
+            events,
+            board: Board::default(),
+            history: Vec::new(),
+            start_ply: 0,
+            force: false,
+            engine_color: Color::Black,
+            post: false,
+            analyzing: false,
+            moves_per_session: 0,
+            increment: 0,
+            move_time: None,
+            max_depth: MAX_DEPTH,
+            time_left: 300_000,
+            last_score: None,
+            search: None,
+            next_id: 0,
+        }
+    }
+
+    // Handles one command; false ends the session.
+    fn command(&mut self, input: &str) -> bool {
+        let (cmd, args) = input.split_once(' ').unwrap_or((input, ""));
+        let args = args.trim();
+        match cmd {
+            "" | "xboard" | "accepted" | "rejected" | "random" | "hard" | "easy" | "computer" | "name" | "rating"
+            | "ics" | "." | "hint" | "bk" | "otim" => {}
+            "protover" => println!("{}", FEATURES),
+            "quit" => return false,
+            "ping" => println!("pong {}", args),
+            "new" => {
+                self.stop();
+                if let Err(e) = experience::finish_game(&self.board) {
+                    println!("telluser error: {}", e);
+                }
+                self.set_board(Board::default(), 0);
+                self.force = false;
+                self.engine_color = Color::Black;
+                self.move_time = None;
+                self.max_depth = MAX_DEPTH;
+            }
+            "variant" if args != "normal" => println!("Error (unsupported variant): {}", args),
+            "variant" => {}
+            "force" => {
+                self.stop();
+                self.force = true;
+            }
+            "go" => {
+                self.stop();
+                self.force = false;
+                self.engine_color = self.board.side_to_move();
+                self.start(Mode::Think);
+            }
+            "playother" => {
+                self.stop();
+                self.force = false;
+                self.engine_color = !self.board.side_to_move();
+            }
+            "level" => match parse_level(args) {
+                Some((moves, base, inc)) => {
+                    self.moves_per_session = moves;
+                    self.time_left = base;
+                    self.increment = inc;
+                    self.move_time = None;
+                }
+                None => println!("Error (bad level): {}", args),
+            },
+            "st" => match args.parse::<f64>() {
+                Ok(secs) if secs > 0.0 => self.move_time = Some((secs * 1000.0) as u64),
+                _ => println!("Error (bad st): {}", args),
+            },
+            "sd" => match args.parse::<i32>() {
+                Ok(depth) if depth > 0 => self.max_depth = depth,
+                _ => println!("Error (bad sd): {}", args),
+            },
+            "time" => match args.parse::<u64>() {
+                Ok(cs) => self.time_left = cs * 10,
+                Err(_) => println!("Error (bad time): {}", args),
+            },
+            "post" => self.post = true,
+            "nopost" => self.post = false,
+            "?" => {
+                if let Some(search) = self.search.as_ref().filter(|s| s.mode == Mode::Think) {
+                    search.stop.store(true, Ordering::Relaxed);
+                }
+            }
+            "draw" => {
+                if self.last_score.is_some_and(|s| s <= DRAW_ACCEPT_SCORE) {
+                    println!("offer draw");
+                }
+            }
+            "result" => {
+                self.stop();
+                self.force = true;
+                if let Err(e) = experience::finish_game(&self.board) {
+                    println!("telluser error: {}", e);
+                }
+            }
+            "setboard" => match uci::parse_fen(args) {
+                Ok((board, fullmove)) => {
+                    self.stop();
+                    let ply = 2 * (fullmove - 1) + (board.side_to_move() == Color::Black) as usize;
+                    self.set_board(board, ply);
+                    self.restart_analysis();
+                }
+                Err(e) => println!("tellusererror Illegal position: {}", e),
+            },
+            "undo" | "remove" => {
+                let plies = if cmd == "undo" { 1 } else { 2 };
+                if self.history.len() < plies {
+                    println!("Error (no moves to undo): {}", cmd);
+                } else {
+                    self.stop();
+                    self.history.truncate(self.history.len() + 1 - plies);
+                    self.board = self.history.pop().unwrap();
+                    self.restart_analysis();
+                }
+            }
+            "analyze" => {
+                self.stop();
+                self.analyzing = true;
+                self.start(Mode::Analyze);
+            }
+            "exit" => {
+                self.stop();
+                self.analyzing = false;
+            }
+            "usermove" => self.user_move(args),
+            _ if self.parse_move(input).is_some() => self.user_move(input),
+            _ => println!("Error (unknown command): {}", input),
+        }
+        true
+    }
+
+    // Coordinate notation, with SAN accepted as well.
+    fn parse_move(&self, text: &str) -> Option<ChessMove> {
+        ChessMove::from_str(text)
+            .ok()
+            .filter(|mv| MoveGen::new_legal(&self.board).any(|m| m == *mv))
+            .or_else(|| pgn::parse_san(&self.board, text).ok())
+    }
+
+    fn user_move(&mut self, text: &str) {
+        let Some(mv) = self.parse_move(text) else {
+            println!("Illegal move: {}", text);
+            return;
+        };
+        self.stop();
+        self.play(mv);
+        if self.analyzing {
+            self.start(Mode::Analyze);
+        } else if !self.force && self.board.side_to_move() == self.engine_color && !self.game_over() {
+            self.start(Mode::Think);
+        }
+    }
+
+    fn set_board(&mut self, board: Board, ply: usize) {
+        self.board = board;
+        self.history.clear();
+        self.start_ply = ply;
+        self.last_score = None;
+    }
+
+    fn play(&mut self, mv: ChessMove) {
+        self.history.push(self.board);
+        self.board = self.board.make_move_new(mv);
+    }
+
+    // Prints the result when the game is over on the board.
+    fn game_over(&self) -> bool {
+        match self.board.status() {
+            BoardStatus::Ongoing => false,
+            BoardStatus::Checkmate if self.board.side_to_move() == Color::White => {
+                println!("0-1 {{Black mates}}");
+                true
+            }
+            BoardStatus::Checkmate => {
+                println!("1-0 {{White mates}}");
+                true
+            }
+            BoardStatus::Stalemate => {
+                println!("1/2-1/2 {{Stalemate}}");
+                true
+            }
+        }
+    }
+
+    fn restart_analysis(&mut self) {
+        if self.analyzing {
+            self.start(Mode::Analyze);
+        }
+    }
+
+    fn budget(&self) -> u64 {
+        if let Some(ms) = self.move_time {
+            return ms;
+        }
+        let played = (self.history.len() as u64).div_ceil(2);
+        let moves_to_go = match self.moves_per_session {
+            0 => 30,
+            n => n - played % n,
+        };
+        (self.time_left / moves_to_go).max(10) + self.increment
+    }
+
+    fn start(&mut self, mode: Mode) {
+        if mode == Mode::Think
+            && let Some(mv) = book::probe(&self.board, self.start_ply + self.history.len())
+        {
+            self.play(mv);
+            println!("move {}", mv);
+            self.game_over();
+            return;
+        }
+        let budget = if mode == Mode::Analyze { u64::MAX } else { self.budget() };
+        let max_depth = if mode == Mode::Analyze { MAX_DEPTH } else { self.max_depth };
+        let stop = Arc::new(AtomicBool::new(false));
+        let mut info = SearchInfo::new(budget);
+        info.report = self.post || mode == Mode::Analyze;
+        info.protocol = Protocol::Xboard;
+        info.stop = Some(stop.clone());
+        self.next_id += 1;
+        let id = self.next_id;
+        let board = self.board;
+        let events = self.events.clone();
+        let handle = thread::spawn(move || {
+            // Analysis is not a game move, so it stays out of the experience
+            // file.
+            let result = match mode {
+                Mode::Think => think(&board, max_depth, &mut info),
+                Mode::Analyze => search(&board, max_depth, &mut info),
+            };
+            let _ = events.send(Event::Done(id, result));
+        });
+        self.search = Some(Search { id, mode, stop, handle });
+    }
+
+    // Ends a running search; its result is dropped.
+    fn stop(&mut self) {
+        if let Some(search) = self.search.take() {
+            search.stop.store(true, Ordering::Relaxed);
+            let _ = search.handle.join();
+        }
+    }
+
+    fn search_done(&mut self, id: u64, result: SearchResult) {
+        let Some(search) = self.search.take_if(|s| s.id == id) else { return };
+        let _ = search.handle.join();
+        if search.mode == Mode::Analyze {
+            return;
+        }
+        self.last_score = Some(result.score);
+        // A "?" before the first iteration finished still has to move.
+        if let Some(mv) = result.best_move.or_else(|| MoveGen::new_legal(&self.board).next()) {
+            self.play(mv);
+            println!("move {}", mv);
+            self.game_over();
+        }
+    }
+}
+
+// "level MPS BASE INC" with BASE in minutes or minutes:seconds and INC in
+// seconds; returns moves per session, base and increment in milliseconds.
+fn parse_level(args: &str) -> Option<(u64, u64, u64)> {
+    let fields: Vec<&str> = args.split_whitespace().collect();
+    let [moves, base, inc] = fields.as_slice() else { return None };
+    let base = match base.split_once(':') {
+        Some((min, sec)) => min.parse::<u64>().ok()? * 60_000 + sec.parse::<u64>().ok()? * 1000,
+        None => (base.parse::<f64>().ok()? * 60_000.0) as u64,
+    };
+    Some((moves.parse().ok()?, base, (inc.parse::<f64>().ok()? * 1000.0) as u64))
+}
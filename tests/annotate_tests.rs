@@ -0,0 +1,49 @@
+use axelrot::annotate::{self, AnnotateConfig, NAG_BLUNDER};
+use axelrot::pgn::{parse_games, write_game};
+use std::process::Command;
+
+const PGN: &str = "[White \"Alice\"]\n[Black \"Bob\"]\n\n1. e4 e5 2. Qh5 Nc6 3. Bc4 Nf6 4. Qxf7# 1-0\n";
+
+#[test]
+fn test_annotate_marks_blunder_with_best_line() {
+    let game = parse_games(PGN).pop().unwrap().unwrap();
+    let config = AnnotateConfig { depth: 2, ..AnnotateConfig::default() };
+    let (annotated, reviews) = annotate::annotate(&game, &config, |_, _| {}).unwrap();
+    assert_eq!(annotated.moves, game.moves);
+    assert_eq!(reviews.len(), 7);
+    let blunder = &annotated.tree[5];
+    assert_eq!(blunder.nags, [NAG_BLUNDER]);
+    assert!(blunder.after.as_deref().unwrap().starts_with("-M1/2 "), "{:?}", blunder.after);
+    assert!(!blunder.variations[0].is_empty() && blunder.variations[0][0].mv != game.moves[5]);
+    assert!(annotated.tree[6].after.as_deref().unwrap().starts_with("+M1/"));
+    assert!(reviews[5].loss >= config.blunder && reviews[6].loss == 0);
+    assert!(write_game(&annotated).unwrap().contains("[Annotator \"axelrot\"]"));
+
+    let mut players = Vec::new();
+    annotate::summarize(&mut players, &game, &reviews);
+    let names: Vec<&str> = players.iter().map(|p| p.name.as_str()).collect();
+    assert_eq!(names, ["Alice", "Bob"]);
+    assert_eq!((players[0].moves, players[0].blunders, players[1].moves, players[1].blunders), (4, 0, 3, 1));
+    assert!(players[0].accuracy() > players[1].accuracy());
+    assert!((annotate::move_accuracy(50, 50) - 100.0).abs() < 0.01 && annotate::move_accuracy(300, -300) < 20.0);
+}
+
+#[test]
+fn test_annotate_command_writes_pgn_and_summary() {
+    let dir = std::env::temp_dir();
+    let input = dir.join(format!("axelrot_annotate_{}.pgn", std::process::id()));
+    let output = dir.join(format!("axelrot_annotate_{}.out.pgn", std::process::id()));
+    std::fs::write(&input, format!("{}\n[Event \"Broken\"]\n\n1. e4 Ke7 2. Ke3 *\n", PGN)).unwrap();
+    let result = Command::new(env!("CARGO_BIN_EXE_annotate"))
+        .args([input.to_str().unwrap(), "--depth", "2", "--output", output.to_str().unwrap()])
+        .output()
+        .unwrap();
+    let written = std::fs::read_to_string(&output).unwrap();
+    std::fs::remove_file(&input).unwrap();
+    std::fs::remove_file(&output).unwrap();
+    let stdout = String::from_utf8_lossy(&result.stdout);
+    assert!(result.status.success(), "{}", String::from_utf8_lossy(&result.stderr));
+    assert!(stdout.contains("(1 games skipped)") && stdout.contains("\nBob "), "{}", stdout);
+    assert!(written.contains("3... Nf6 $4 {-M1/2 "), "{}", written);
+    assert_eq!(parse_games(&written).len(), 1);
+}
@@ -0,0 +1,62 @@
+use axelrot::bench::{self, BENCH_POSITIONS};
+use axelrot::params::EvalParams;
+use std::io::Write;
+use std::process::{Command, Stdio};
+
+#[test]
+fn test_bench_node_count_is_deterministic() {
+    let mut reported = 0;
+    let first = bench::run(1, |_, _, result| {
+        assert!(result.best_move.is_some());
+        reported += 1;
+    });
+    assert_eq!(reported, BENCH_POSITIONS.len());
+    assert!(first.nodes > 0);
+    assert_eq!(bench::run(1, |_, _, _| {}).nodes, first.nodes);
+}
+
+#[test]
+fn test_bench_command_line_and_uci() {
+    let expected = format!("Nodes searched  : {}\n", bench::run(1, |_, _, _| {}).nodes);
+    let output = Command::new(env!("CARGO_BIN_EXE_axelrot")).args(["bench", "1"]).output().unwrap();
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(output.status.success());
+    assert!(stdout.contains(&expected), "{}", stdout);
+    assert!(stdout.contains("Nodes/second    : "));
+
+    let mut child = Command::new(env!("CARGO_BIN_EXE_axelrot"))
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child.stdin.as_mut().unwrap().write_all(b"bench 1\nbench x\nquit\n").unwrap();
+    let stdout = String::from_utf8_lossy(&child.wait_with_output().unwrap().stdout).into_owned();
+    assert!(stdout.contains(&expected), "{}", stdout);
+    assert!(stdout.contains("info string error: invalid bench depth x"), "{}", stdout);
+}
+
+#[test]
+fn test_bench_ignores_loaded_files_and_options() {
+    let expected = format!("Nodes searched  : {}\n", bench::run(2, |_, _, _| {}).nodes);
+    let dir = std::env::temp_dir().join(format!("axelrot_bench_{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let mut params = EvalParams::default();
+    params.piece_values[1] += 60;
+    params.save(dir.join("params.json")).unwrap();
+    let commands = format!(
+        "setoption name EvalFile value {}\nsetoption name SyzygyPath value {}/tests/syzygy\nsetoption name ExperienceFile value {}\nsetoption name Nullmove value false\nsetoption name Hash value 1\nbench 2\nquit\n",
+        dir.join("params.json").display(),
+        env!("CARGO_MANIFEST_DIR"),
+        dir.join("experience.bin").display()
+    );
+    let mut child = Command::new(env!("CARGO_BIN_EXE_axelrot"))
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child.stdin.as_mut().unwrap().write_all(commands.as_bytes()).unwrap();
+    let stdout = String::from_utf8_lossy(&child.wait_with_output().unwrap().stdout).into_owned();
+    assert!(stdout.contains("info string loaded evaluation parameters"), "{}", stdout);
+    assert!(stdout.contains(&expected), "{}", stdout);
+    std::fs::remove_dir_all(&dir).unwrap();
+}
@@ -0,0 +1,30 @@
+use axelrot::bitbase::{probe, BitbaseResult};
+use chess::{Board, Color};
+use std::str::FromStr;
+
+fn result(fen: &str) -> Option<BitbaseResult> {
+    probe(&Board::from_str(fen).unwrap())
+}
+
+#[test]
+fn test_kpk_bitbase_known_positions() {
+    // King on the sixth in front of its pawn wins whoever moves.
+    assert_eq!(result("4k3/8/4K3/4P3/8/8/8/8 b - - 0 1"), Some(BitbaseResult::Win(Color::White)));
+    // Defender holds the opposition in front of the pawn.
+    assert_eq!(result("8/8/8/8/8/4k3/4P3/4K3 w - - 0 1"), Some(BitbaseResult::Draw));
+    // Rook pawn with the defending king in the corner.
+    assert_eq!(result("k7/8/K7/P7/8/8/8/8 w - - 0 1"), Some(BitbaseResult::Draw));
+    // Same ideas with colours reversed.
+    assert_eq!(result("8/8/8/8/4p3/4k3/8/4K3 w - - 0 1"), Some(BitbaseResult::Win(Color::Black)));
+    // Outside the square of the pawn.
+    assert_eq!(result("8/k7/8/8/8/8/6P1/6K1 w - - 0 1"), Some(BitbaseResult::Win(Color::White)));
+}
+
+#[test]
+fn test_major_piece_bitbases_spot_lost_material() {
+    assert_eq!(result("8/8/8/4k3/8/8/1K6/7R w - - 0 1"), Some(BitbaseResult::Win(Color::White)));
+    assert_eq!(result("8/8/8/8/8/8/1k6/1R2K3 b - - 0 1"), Some(BitbaseResult::Draw));
+    // Stalemate.
+    assert_eq!(result("k7/2Q5/1K6/8/8/8/8/8 b - - 0 1"), Some(BitbaseResult::Draw));
+    assert_eq!(result("8/8/8/4k3/8/8/1K6/5BN1 w - - 0 1"), None);
+}
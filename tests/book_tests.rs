@@ -0,0 +1,99 @@
+use axelrot::book::{decode_move, encode_move, polyglot_key, Book, BookEntry, Selection};
+use axelrot::datagen::Rng;
+use chess::{Board, ChessMove};
+use std::io::Write;
+use std::path::PathBuf;
+use std::process::{Command, Stdio};
+use std::str::FromStr;
+
+fn play(moves: &str) -> Board {
+    moves.split_whitespace().fold(Board::default(), |b, m| b.make_move_new(ChessMove::from_str(m).unwrap()))
+}
+
+fn entry(board: &Board, mv: &str, weight: u16) -> BookEntry {
+    let mv = encode_move(board, ChessMove::from_str(mv).unwrap());
+    BookEntry { key: polyglot_key(board), mv, weight, learn: 0 }
+}
+
+fn temp_book(name: &str, book: &Book) -> PathBuf {
+    let path = std::env::temp_dir().join(format!("axelrot_book_{}_{}.bin", name, std::process::id()));
+    book.save(&path).unwrap();
+    path
+}
+
+// Reference keys from the Polyglot book format description.
+#[test]
+fn test_polyglot_keys_match_reference() {
+    let cases = [
+        ("", 0x463b96181691fc9c),
+        ("e2e4", 0x823c9b50fd114196),
+        ("e2e4 d7d5", 0x0756b94461c50fb0),
+        ("e2e4 d7d5 e4e5", 0x662fafb965db29d4),
+        ("e2e4 d7d5 e4e5 f7f5", 0x22a48b5a8e47ff78),
+        ("e2e4 d7d5 e4e5 f7f5 e1e2", 0x652a607ca3f242c1),
+        ("e2e4 d7d5 e4e5 f7f5 e1e2 e8f7", 0x00fdd303c946bdd9),
+        ("a2a4 b7b5 h2h4 b5b4 c2c4", 0x3c8123ea7b067637),
+        ("a2a4 b7b5 h2h4 b5b4 c2c4 b4c3 a1a3", 0x5c3f9b829b279560),
+    ];
+    for (moves, key) in cases {
+        assert_eq!(polyglot_key(&play(moves)), key, "{}", moves);
+    }
+}
+
+#[test]
+fn test_book_round_trip_and_selection() {
+    let start = Board::default();
+    let castle = Board::from_str("r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1").unwrap();
+    let book = Book::from_entries(vec![
+        entry(&start, "d2d4", 10),
+        entry(&start, "e2e4", 30),
+        entry(&start, "g1f3", 0),
+        entry(&castle, "e1g1", 1),
+    ]);
+    let path = temp_book("round_trip", &book);
+    let book = Book::open(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!(book.len(), 4);
+
+    // Castling is stored as king takes rook.
+    assert_eq!(book.entries().iter().find(|e| e.key == polyglot_key(&castle)).unwrap().mv & 0x3f, 7);
+    assert_eq!(decode_move(&castle, 0x0107), Some(ChessMove::from_str("e1g1").unwrap()));
+
+    let mut rng = Rng::new(1);
+    assert_eq!(book.pick(&start, Selection::Best, &mut rng), Some(ChessMove::from_str("e2e4").unwrap()));
+    let e4 = (0..400).filter(|_| book.pick(&start, Selection::Weighted, &mut rng).unwrap().to_string() == "e2e4").count();
+    assert!((240..360).contains(&e4), "{}", e4);
+    assert_eq!(book.pick(&play("e2e4"), Selection::Best, &mut rng), None);
+
+    std::fs::write(&path, [0u8; 15]).unwrap();
+    assert!(Book::open(&path).is_err());
+    std::fs::remove_file(&path).unwrap();
+}
+
+fn run_engine(commands: &str) -> String {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_axelrot"))
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child.stdin.as_mut().unwrap().write_all(commands.as_bytes()).unwrap();
+    String::from_utf8_lossy(&child.wait_with_output().unwrap().stdout).into_owned()
+}
+
+#[test]
+fn test_uci_plays_book_moves_within_depth() {
+    let after_e4 = play("e2e4");
+    let book = Book::from_entries(vec![entry(&Board::default(), "b1c3", 5), entry(&after_e4, "a7a6", 5)]);
+    let path = temp_book("uci", &book);
+    let setup = format!("setoption name OwnBook value true\nsetoption name BookFile value {}\n", path.display());
+
+    let stdout = run_engine(&format!("{}position startpos\ngo depth 3\nposition startpos moves e2e4\ngo depth 3\nquit\n", setup));
+    assert!(stdout.contains("info string loaded 2 book entries"), "{}", stdout);
+    let best: Vec<&str> = stdout.lines().filter_map(|l| l.strip_prefix("bestmove ")).collect();
+    assert_eq!(best, ["b1c3", "a7a6"]);
+
+    let stdout = run_engine(&format!("{}setoption name BookDepth value 1\nposition startpos moves e2e4\ngo depth 2\nquit\n", setup));
+    assert!(!stdout.contains("book move"), "{}", stdout);
+    assert!(stdout.contains("info depth 2"), "{}", stdout);
+    std::fs::remove_file(&path).unwrap();
+}
@@ -0,0 +1,73 @@
+use axelrot::book::Book;
+use axelrot::bookgen::{merge, BookBuilder, BookgenConfig};
+use axelrot::pgn::parse_games;
+use chess::{Board, ChessMove};
+use std::process::Command;
+use std::str::FromStr;
+
+const GAMES: &str = r#"[WhiteElo "2400"] [BlackElo "2000"] [Result "1-0"]
+1. e4 e5 2. Nf3 1-0
+[Result "1/2-1/2"]
+1. e4 c5 1/2-1/2
+[Result "0-1"]
+1. d4 d5 0-1
+[Result "*"]
+1. c4 *
+"#;
+
+fn weights(book: &Book, board: &Board) -> Vec<(String, u16)> {
+    book.moves(board).into_iter().map(|(mv, w)| (mv.to_string(), w)).collect()
+}
+
+fn build(config: BookgenConfig) -> Book {
+    let mut builder = BookBuilder::new(config);
+    let added: Vec<bool> = parse_games(GAMES).into_iter().map(|g| builder.add_game(&g.unwrap()).unwrap()).collect();
+    assert_eq!(added, [true, true, true, false]);
+    builder.build()
+}
+
+#[test]
+fn test_weights_filters_and_merge() {
+    let start = Board::default();
+    let book = build(BookgenConfig::default());
+    // e4: a win by a 2400 player (2 * 1.2) plus an unrated draw (1); d4 lost.
+    assert_eq!(weights(&book, &start), [("e2e4".to_string(), 3)]);
+    let after_e4 = start.make_move_new(ChessMove::from_str("e2e4").unwrap());
+    assert_eq!(weights(&book, &after_e4), [("c7c5".to_string(), 1)]);
+
+    let book = build(BookgenConfig { min_count: 2, ..BookgenConfig::default() });
+    assert_eq!(book.len(), 1);
+    let book = build(BookgenConfig { max_ply: 1, ..BookgenConfig::default() });
+    assert!(weights(&book, &after_e4).is_empty());
+    let book = build(BookgenConfig { min_elo: 2100, ..BookgenConfig::default() });
+    assert_eq!(weights(&book, &start), [("e2e4".to_string(), 2)]);
+
+    let merged = merge(&[book, build(BookgenConfig::default())]);
+    assert_eq!(weights(&merged, &start), [("e2e4".to_string(), 5)]);
+}
+
+#[test]
+fn test_bookgen_cli_build_and_dump() {
+    let dir = std::env::temp_dir().join(format!("axelrot_bookgen_{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let pgn = dir.join("games.pgn");
+    let bin = dir.join("book.bin");
+    std::fs::write(&pgn, GAMES).unwrap();
+
+    let output = Command::new(env!("CARGO_BIN_EXE_bookgen"))
+        .args(["build", pgn.to_str().unwrap(), "--output", bin.to_str().unwrap()])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    assert!(String::from_utf8_lossy(&output.stdout).starts_with("3 games (1 skipped)"));
+
+    let fen = "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1";
+    let output = Command::new(env!("CARGO_BIN_EXE_bookgen"))
+        .args(["dump", bin.to_str().unwrap(), "--fen", fen])
+        .output()
+        .unwrap();
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.starts_with("c5"), "{}", stdout);
+    assert!(stdout.contains("(100.0%)"), "{}", stdout);
+    std::fs::remove_dir_all(&dir).unwrap();
+}
@@ -0,0 +1,71 @@
+use axelrot::game::GameState;
+use chess::{Board, ChessMove};
+use std::process::{Command, Output};
+use std::str::FromStr;
+
+fn axelrot(args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_axelrot")).args(args).output().unwrap()
+}
+
+fn stdout(output: &Output) -> String {
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    String::from_utf8_lossy(&output.stdout).into_owned()
+}
+
+#[test]
+fn test_cli_perft_analyze_and_errors() {
+    let text = stdout(&axelrot(&["perft", "startpos", "3", "--divide"]));
+    assert!(text.contains("e2e4: 600\n") && text.contains("Nodes: 8902\n"), "{}", text);
+    let json = stdout(&axelrot(&["perft", "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1", "3", "--json", "--threads", "2"]));
+    assert!(json.starts_with("{\"fen\":\"8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1\",\"depth\":3,\"nodes\":2812,"), "{}", json);
+
+    let json = stdout(&axelrot(&["analyze", "6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1", "--depth", "2", "--multipv", "2", "--json"]));
+    assert!(json.contains("{\"multipv\":1,\"depth\":2,\"score\":{\"mate\":1},"), "{}", json);
+    assert!(json.contains("\"multipv\":2,\"depth\":2,"), "{}", json);
+    let text = stdout(&axelrot(&["analyze", "6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1", "--depth", "2"]));
+    assert!(text.starts_with("1. mate 1 depth 2 nodes ") && text.trim_end().ends_with("1. Ra8#"), "{}", text);
+
+    for args in [&["analyze"][..], &["perft", "startpos", "x"], &["analyze", "startpos", "--bogus"], &["frobnicate"]] {
+        let output = axelrot(args);
+        assert_eq!(output.status.code(), Some(1), "{:?}", args);
+        assert!(String::from_utf8_lossy(&output.stderr).contains("usage: axelrot"), "{:?}", args);
+    }
+}
+
+#[test]
+fn test_cli_epd_and_selfplay() {
+    let path = std::env::temp_dir().join(format!("axelrot_cli_{}.epd", std::process::id()));
+    std::fs::write(&path, "6k1/5ppp/8/8/8/8/8/R5K1 w - - bm Ra8#; id \"back rank\";\n").unwrap();
+    let text = stdout(&axelrot(&["epd", path.to_str().unwrap(), "--depth", "2"]));
+    std::fs::remove_file(&path).unwrap();
+    assert!(text.starts_with("back rank: Ra8# solved in ") && text.contains(" ms (mate 1 depth 2)\nSolved 1/1\n"), "{}", text);
+
+    let json = stdout(&axelrot(&["selfplay", "--fen", "6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1", "--depth", "2", "--json"]));
+    assert!(json.contains("\"games\":[{\"result\":\"1-0\",\"reason\":\"checkmate\",\"plies\":1,\"moves\":[\"a1a8\"]}]"), "{}", json);
+    let text = stdout(&axelrot(&["selfplay", "--depth", "1", "--max-plies", "4", "--games", "2", "--random-plies", "2"]));
+    assert_eq!(text.matches("(max plies, 4 plies)").count(), 2, "{}", text);
+}
+
+#[test]
+fn test_game_state_draw_rules() {
+    let mut game = GameState::new(Board::default());
+    for mv in ["g1f3", "g8f6", "f3g1", "f6g8", "g1f3", "g8f6", "f3g1"] {
+        assert_eq!(game.end(), None);
+        game.play(ChessMove::from_str(mv).unwrap());
+    }
+    game.play(ChessMove::from_str("f6g8").unwrap());
+    assert_eq!(game.end().unwrap().reason, "threefold repetition");
+
+    let game = GameState::new(Board::from_str("8/8/4k3/8/8/3BK3/8/8 w - - 0 1").unwrap());
+    assert_eq!(game.end().unwrap().reason, "insufficient material");
+    // Bishops on one square colour cannot mate either.
+    let game = GameState::new(Board::from_str("8/7b/4k3/8/8/3BK3/8/8 w - - 0 1").unwrap());
+    assert_eq!(game.end().unwrap().reason, "insufficient material");
+    let mut game = GameState::new(Board::from_str("8/8/4k3/8/8/3RK3/8/8 w - - 0 1").unwrap());
+    for _ in 0..25 {
+        for mv in ["d3d1", "e6e7", "d1d3", "e7e6"] {
+            game.play(ChessMove::from_str(mv).unwrap());
+        }
+    }
+    assert_eq!(game.halfmove_clock(), 100);
+}
@@ -0,0 +1,28 @@
+use axelrot::datagen::{play_game, read_records, run, DatagenConfig, Record};
+use chess::Board;
+use std::str::FromStr;
+
+#[test]
+fn test_record_binary_round_trip() {
+    let board = Board::from_str("r3k2r/pP3ppp/8/3pP3/8/8/PPP2PPP/R3K2R w Kq d6 0 1").unwrap();
+    let record = Record { board, score: -123, result: 2, fullmove: 17 };
+    assert_eq!(Record::from_bytes(&record.to_bytes()).unwrap(), record);
+}
+
+#[test]
+fn test_datagen_is_deterministic_and_resumable() {
+    let dir = std::env::temp_dir().join(format!("axelrot_datagen_{}", std::process::id()));
+    let config = DatagenConfig { games: 1, nodes: 50, max_plies: 60, output: dir.join("data"), ..DatagenConfig::default() };
+
+    let first = play_game(&config, 0).unwrap();
+    assert_eq!(Some(&first), play_game(&config, 0).as_ref());
+    // Games cut off by the ply limit have no result and are dropped, not scored as draws.
+    assert_eq!(play_game(&DatagenConfig { max_plies: 4, ..config.clone() }, 0), None);
+
+    let written = run(&config, |_, _, _| {}).unwrap();
+    assert_eq!(written, first.len());
+    assert_eq!(read_records(config.binary_path()).unwrap(), first);
+    // A second run finds every game already done and adds nothing.
+    assert_eq!(run(&config, |_, _, _| {}).unwrap(), 0);
+    std::fs::remove_dir_all(&dir).unwrap();
+}
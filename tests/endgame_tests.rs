@@ -0,0 +1,33 @@
+use axelrot::endgame::insufficient_material;
+use axelrot::evaluation;
+use chess::Board;
+use std::str::FromStr;
+
+fn eval(fen: &str) -> i32 {
+    evaluation(&Board::from_str(fen).unwrap())
+}
+
+#[test]
+fn test_known_draws_evaluate_to_zero() {
+    assert!(insufficient_material(&Board::from_str("8/8/4k3/8/8/3NK3/8/8 w - - 0 1").unwrap()));
+    assert_eq!(eval("8/8/4k3/8/8/3NK3/8/8 w - - 0 1"), 0);
+    assert_eq!(eval("8/8/4k3/8/8/3NK3/4N3/8 w - - 0 1"), 0);
+    // Wrong-coloured bishop with a rook pawn and the defender in the corner.
+    assert_eq!(eval("7k/8/6K1/7P/8/8/8/5B2 w - - 0 1"), 0);
+    assert!(eval("8/8/4k3/3pp3/3PP3/2B1K3/8/8 w - - 0 1") > 0);
+}
+
+#[test]
+fn test_lone_king_is_driven_to_the_edge_and_corner() {
+    assert!(eval("k7/8/1K6/8/8/8/8/7R w - - 0 1") > eval("8/8/8/4k3/8/1K6/8/7R w - - 0 1"));
+    assert!(eval("8/8/8/4k3/8/8/1K6/7Q b - - 0 1") < -2000);
+    // KBNK with a light-squared bishop: h1 is the mating corner, a1 is not.
+    assert!(eval("8/8/8/8/8/5NK1/8/5B1k w - - 0 1") > eval("8/8/8/8/8/1KN5/8/k4B2 w - - 0 1"));
+}
+
+#[test]
+fn test_opposite_coloured_bishops_are_scaled_down() {
+    let ocb = eval("4k1b1/8/8/3p4/8/8/5PPP/2B1K3 w - - 0 1");
+    let same = eval("4kb2/8/8/3p4/8/8/5PPP/2B1K3 w - - 0 1");
+    assert!(ocb > 0 && ocb < same);
+}
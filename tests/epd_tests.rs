@@ -0,0 +1,62 @@
+use axelrot::epd::{self, SuiteConfig, SuiteSummary};
+use std::process::Command;
+
+const SUITE: &str = "\
+6k1/5ppp/8/8/8/8/8/R5K1 w - - bm Ra8#; id \"bm\";
+6k1/5ppp/8/8/8/8/8/R5K1 w - - am Ra8#; id \"am\";
+6k1/5ppp/8/8/8/8/8/R5K1 w - - dm 1; id \"dm\";
+6k1/5ppp/8/8/8/8/8/R5K1 w - - id \"sts\"; c0 \"Ra8#=10, Ra7=3, Kf1=1\";
+rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - id \"none\";
+";
+
+#[test]
+fn test_suite_scoring_and_threads() {
+    let records: Vec<_> = epd::parse(SUITE).into_iter().map(Result::unwrap).collect();
+    let config = SuiteConfig { depth: 2, movetime: None, nodes: None, threads: 3 };
+    let results = epd::run_suite(&records, &config, |_| {});
+    let ids: Vec<&str> = results.iter().map(|r| r.id.as_str()).collect();
+    assert_eq!(ids, ["bm", "am", "dm", "sts", "none"]);
+    let solved: Vec<Option<bool>> = results.iter().map(|r| r.solved).collect();
+    assert_eq!(solved, [Some(true), Some(false), Some(true), Some(true), None]);
+    assert!(results[0].solve_time_ms.is_some() && results[1].solve_time_ms.is_none());
+    assert_eq!((results[3].points, results[3].max_points), (10, 10));
+
+    let summary = SuiteSummary::new(&results);
+    assert_eq!((summary.positions, summary.checked, summary.solved), (5, 4, 3));
+    assert_eq!((summary.points, summary.max_points), (10, 10));
+}
+
+#[test]
+fn test_epd_command_json_and_node_limit() {
+    let path = std::env::temp_dir().join(format!("axelrot_epd_{}.epd", std::process::id()));
+    std::fs::write(&path, SUITE).unwrap();
+    let output = Command::new(env!("CARGO_BIN_EXE_axelrot"))
+        .args(["epd", path.to_str().unwrap(), "--nodes", "2000", "--threads", "2", "--json"])
+        .output()
+        .unwrap();
+    std::fs::remove_file(&path).unwrap();
+    let json = String::from_utf8_lossy(&output.stdout);
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    assert!(json.starts_with("{\"positions\":5,\"checked\":4,"), "{}", json);
+    assert!(json.contains("\"id\":\"sts\",") && json.contains("\"max_points\":10}"), "{}", json);
+    assert!(json.contains("\"id\":\"none\",") && json.contains("\"solved\":null,"), "{}", json);
+}
+
+#[test]
+fn test_parse_sts_promotions() {
+    let record = epd::parse_line("8/4P1k1/8/8/8/8/8/K7 w - - c0 \"e8=Q=10, e8=N=2, Kb2=1\";").unwrap();
+    let points: Vec<(String, u32)> =
+        epd::sts_points(&record.board, record.op("c0").unwrap()).into_iter().map(|(mv, p)| (mv.to_string(), p)).collect();
+    assert_eq!(points, [("e7e8q".to_string(), 10), ("e7e8n".to_string(), 2), ("a1b2".to_string(), 1)]);
+}
+
+#[test]
+fn test_parse_line_with_move_counters() {
+    let record = epd::parse_line("6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1 bm Ra8#; id \"counters\";").unwrap();
+    assert_eq!(record.fen, "6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1");
+    assert_eq!((record.op("bm"), record.id()), (Some("Ra8#"), "counters"));
+    assert!(record.op("0").is_none());
+    // A lone number after the position is still an operation's business.
+    let record = epd::parse_line("6k1/5ppp/8/8/8/8/8/R5K1 w - - dm 1;").unwrap();
+    assert_eq!((record.fen.as_str(), record.op("dm")), ("6k1/5ppp/8/8/8/8/8/R5K1 w - -", Some("1")));
+}
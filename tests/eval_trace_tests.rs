@@ -0,0 +1,28 @@
+use axelrot::eval_trace::trace;
+use axelrot::evaluation;
+use chess::{Board, Color, Piece, Square};
+use std::str::FromStr;
+
+#[test]
+fn test_trace_total_matches_evaluation() {
+    for fen in [
+        "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+        "r1bq1rk1/pp3ppp/2n2n2/3pp3/1bPP4/2N1PN2/PP3PPP/R2QKB1R b KQ - 0 8",
+        "6k1/5ppp/8/8/8/6R1/1r3PPP/6K1 w - - 0 1",
+    ] {
+        let board = Board::from_str(fen).unwrap();
+        assert_eq!(trace(&board).total, evaluation(&board), "{}", fen);
+    }
+}
+
+#[test]
+fn test_trace_reports_square_contributions() {
+    let board = Board::default();
+    let t = trace(&board);
+    assert_eq!(t.phase, 24);
+    assert_eq!(t.squares[Square::D1.to_index()].map(|(p, c, _)| (p, c)), Some((Piece::Queen, Color::White)));
+    assert!(t.squares[Square::E4.to_index()].is_none());
+    let table = t.to_string();
+    assert!(table.contains("King safety"));
+    assert!(table.contains("Mobility") && table.contains("no such term"));
+}
@@ -0,0 +1,107 @@
+use axelrot::experience::{self, Experience, Outcome};
+use axelrot::{search, SearchInfo};
+use chess::{Board, ChessMove, Color, MoveGen};
+use std::io::Write;
+use std::process::{Command, Stdio};
+use std::str::FromStr;
+
+fn temp_file(name: &str) -> std::path::PathBuf {
+    std::env::temp_dir().join(format!("axelrot_exp_{}_{}.bin", name, std::process::id()))
+}
+
+#[test]
+fn test_experience_records_outcomes_and_size_limit() {
+    let start = Board::default();
+    let e4 = ChessMove::from_str("e2e4").unwrap();
+    let after = start.make_move_new(e4);
+    let c5 = ChessMove::from_str("c7c5").unwrap();
+
+    let mut exp = Experience::new();
+    exp.record_search(&start, e4, 6, 30);
+    exp.record_search(&start, e4, 4, -80);
+    exp.record_search(&after, c5, 5, 450);
+    // The last search was far ahead for Black, so the game counts as a win
+    // for Black and a loss for White's move.
+    exp.finish_game(&after.make_move_new(c5));
+    let e4_record = exp.get(&start, e4).unwrap();
+    assert_eq!((e4_record.depth, e4_record.score), (6, 30));
+    assert_eq!((e4_record.wins, e4_record.losses), (0, 2));
+    assert_eq!(exp.get(&after, c5).unwrap().wins, 1);
+    assert!(exp.get(&start, e4).unwrap().bias() < 0);
+
+    let path = temp_file("limit");
+    exp.save(&path, 1).unwrap();
+    let loaded = Experience::load(&path).unwrap();
+    assert_eq!(loaded.len(), 1);
+    assert_eq!(loaded.get(&start, e4), Some(e4_record));
+
+    std::fs::write(&path, b"AXEX\x01junk").unwrap();
+    assert!(Experience::load(&path).is_err());
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn test_experience_biases_root_move() {
+    let board = Board::default();
+    let score_of = |mv: ChessMove| {
+        let mut info = SearchInfo::new(60_000);
+        info.root_moves = Some(vec![mv]);
+        search(&board, 1, &mut info).score
+    };
+    let mut scores: Vec<(i32, ChessMove)> = MoveGen::new_legal(&board).map(|m| (score_of(m), m)).collect();
+    scores.sort_by_key(|&(s, _)| -s);
+    let (best, runner_up) = (scores[0], scores[1]);
+    assert!(best.0 - runner_up.0 < 40, "{:?} {:?}", best, runner_up);
+
+    let mut exp = Experience::new();
+    for _ in 0..5 {
+        exp.record_search(&board, runner_up.1, 1, runner_up.0);
+        exp.record_outcome(Color::White, Outcome::Win);
+    }
+    let path = temp_file("bias");
+    exp.save(&path, experience::DEFAULT_SIZE).unwrap();
+    assert_eq!(experience::set_file(path.to_str().unwrap()), Ok(1));
+    let chosen = search(&board, 1, &mut SearchInfo::new(60_000)).best_move;
+    experience::set_read(false);
+    let unbiased = search(&board, 1, &mut SearchInfo::new(60_000)).best_move;
+    experience::set_read(true);
+    experience::set_file("").unwrap();
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!(chosen, Some(runner_up.1));
+    assert_eq!(unbiased, Some(best.1));
+}
+
+fn run_engine(commands: &str) -> String {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_axelrot"))
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child.stdin.as_mut().unwrap().write_all(commands.as_bytes()).unwrap();
+    String::from_utf8_lossy(&child.wait_with_output().unwrap().stdout).into_owned()
+}
+
+#[test]
+fn test_uci_experience_file_persists_between_runs() {
+    let path = temp_file("uci");
+    let _ = std::fs::remove_file(&path);
+    let setup = format!("setoption name ExperienceFile value {}\n", path.display());
+    let first = run_engine(&format!("{}position startpos\ngo depth 2\nquit\n", setup));
+    assert!(first.contains("info string loaded 0 experience entries"), "{}", first);
+    let second = run_engine(&format!("{}quit\n", setup));
+    assert!(second.contains("info string loaded 1 experience entries"), "{}", second);
+    let off = run_engine(&format!("setoption name ExperienceRead value false\n{}quit\n", setup));
+    assert!(off.contains("info string loaded 0 experience entries"), "{}", off);
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn test_uci_analyse_mode_records_no_experience() {
+    let path = temp_file("analyse");
+    let _ = std::fs::remove_file(&path);
+    let setup = format!("setoption name ExperienceFile value {}\n", path.display());
+    run_engine(&format!("{}setoption name UCI_AnalyseMode value true\nposition startpos\ngo depth 2\nquit\n", setup));
+    let second = run_engine(&format!("{}quit\n", setup));
+    assert!(second.contains("info string loaded 0 experience entries"), "{}", second);
+    let _ = std::fs::remove_file(&path);
+}
@@ -0,0 +1,51 @@
+use axelrot::king_safety::{king_safety, king_safety_midgame};
+use axelrot::params::EvalParams;
+use chess::{Board, Color};
+use std::str::FromStr;
+
+// Unit danger scale so that every individual weakness shows up in the score.
+fn linear_params() -> EvalParams {
+    EvalParams { king_danger_scale: 1, king_max_penalty: i32::MAX, ..EvalParams::default() }
+}
+
+fn white_king_safety(fen: &str) -> i32 {
+    king_safety(&Board::from_str(fen).unwrap(), Color::White, &linear_params())
+}
+
+#[test]
+fn test_pawn_shield_storm_and_open_files() {
+    let intact = white_king_safety("6k1/8/8/8/8/8/5PPP/6K1 w - - 0 1");
+    let advanced = white_king_safety("6k1/8/8/8/8/6P1/5P1P/6K1 w - - 0 1");
+    let storm = white_king_safety("6k1/8/8/8/6p1/8/5PPP/6K1 w - - 0 1");
+    let semi_open = white_king_safety("6k1/6p1/8/8/8/8/5P1P/6K1 w - - 0 1");
+    let open = white_king_safety("6k1/8/8/8/8/8/5P1P/6K1 w - - 0 1");
+
+    assert_eq!(intact, 0);
+    assert!(advanced < intact, "advanced shield {} vs intact {}", advanced, intact);
+    assert!(storm < intact, "storm pawn {} vs intact {}", storm, intact);
+    assert!(semi_open < advanced, "missing shield {} vs advanced {}", semi_open, advanced);
+    assert!(open < semi_open, "open file {} vs semi-open {}", open, semi_open);
+}
+
+#[test]
+fn test_only_safe_checks_are_penalised() {
+    let quiet = white_king_safety("6k1/8/8/8/8/8/5PPP/6K1 w - - 0 1");
+    // Nc3 can check from e2, which nothing defends.
+    let safe_check = white_king_safety("6k1/8/8/8/8/2n5/5PPP/6K1 w - - 0 1");
+    // Ng5 only reaches f3/h3, both covered by the g2 pawn.
+    let guarded_check = white_king_safety("6k1/8/8/6n1/8/8/5PPP/6K1 w - - 0 1");
+
+    assert!(safe_check < quiet, "safe check {} vs quiet {}", safe_check, quiet);
+    assert_eq!(guarded_check, quiet);
+}
+
+#[test]
+fn test_king_safety_fades_out_in_the_endgame() {
+    let params = linear_params();
+    let bare = Board::from_str("6k1/8/8/8/8/8/5P1P/6K1 w - - 0 1").unwrap();
+    let queens = Board::from_str("3qk3/8/8/8/8/8/5P1P/3QK3 w - - 0 1").unwrap();
+
+    assert!(king_safety(&bare, Color::White, &params) < 0);
+    assert_eq!(king_safety_midgame(&bare, Color::White, &params), 0);
+    assert!(king_safety_midgame(&queens, Color::White, &params) < 0);
+}
@@ -0,0 +1,65 @@
+use axelrot::matches::{self, EngineSpec, MatchConfig, Openings, TimeControl};
+use std::path::PathBuf;
+
+fn axelrot(name: &str) -> EngineSpec {
+    EngineSpec {
+        name: name.to_string(),
+        cmd: PathBuf::from(env!("CARGO_BIN_EXE_axelrot")),
+        depth: Some(1),
+        ..EngineSpec::default()
+    }
+}
+
+#[test]
+fn test_match_between_two_builds_writes_pgn() {
+    let dir = std::env::temp_dir();
+    let openings = dir.join(format!("axelrot_match_{}.epd", std::process::id()));
+    let pgn = dir.join(format!("axelrot_match_{}.pgn", std::process::id()));
+    std::fs::write(&openings, "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq -\n").unwrap();
+    let mut config = MatchConfig::new([axelrot("old"), axelrot("new")]);
+    config.tc = "60+1".parse().unwrap();
+    config.max_moves = 3;
+    config.concurrency = 2;
+    config.openings = Some(Openings::open(&openings, 8).unwrap());
+    config.pgn_out = Some(pgn.clone());
+    let games = matches::run(&config, |_, _| {}).unwrap();
+    let text = std::fs::read_to_string(&pgn).unwrap();
+    std::fs::remove_file(&openings).unwrap();
+    std::fs::remove_file(&pgn).unwrap();
+
+    assert_eq!(games.len(), 2);
+    assert_eq!((games[0].white.as_str(), games[1].white.as_str()), ("old", "new"));
+    for game in &games {
+        assert_eq!((game.result, game.reason.as_str()), ("1/2-1/2", "max moves"));
+        assert_eq!(game.moves.len(), 6);
+        assert!(game.moves.iter().all(|m| m.score.is_some() && m.depth == 1));
+    }
+    assert_eq!(text.matches("[Termination \"max moves\"]").count(), 2, "{}", text);
+    assert!(text.contains("[FEN \"rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq - 0 1\"]\n"), "{}", text);
+    assert!(text.contains("\n\n1... "), "{}", text);
+    assert_eq!("10+0.1".parse(), Ok(TimeControl { base_ms: 10_000, inc_ms: 100 }));
+    assert!("0+1".parse::<TimeControl>().is_err());
+}
+
+#[test]
+fn test_match_recovers_from_engine_crash() {
+    // Answers the handshake, then dies on the first `go`.
+    let crasher = EngineSpec {
+        name: "crasher".to_string(),
+        cmd: PathBuf::from("sh"),
+        args: vec![
+            "-c".to_string(),
+            "while read l; do case $l in uci) echo uciok;; isready) echo readyok;; go*) exit 1;; esac; done".to_string(),
+        ],
+        ..EngineSpec::default()
+    };
+    let mut config = MatchConfig::new([axelrot("axelrot"), crasher]);
+    config.games = 3;
+    let games = matches::run(&config, |_, _| {}).unwrap();
+    assert_eq!(games.iter().map(|g| g.first_points()).collect::<Vec<_>>(), [2, 2, 2]);
+    assert!(games.iter().all(|g| g.reason == "crasher disconnects"), "{:?}", games);
+
+    let missing = EngineSpec { name: "missing".to_string(), cmd: PathBuf::from("/nonexistent/engine"), ..EngineSpec::default() };
+    let config = MatchConfig::new([axelrot("axelrot"), missing]);
+    assert!(matches::run(&config, |_, _| {}).unwrap_err().starts_with("cannot start /nonexistent/engine"));
+}
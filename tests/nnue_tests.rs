@@ -0,0 +1,54 @@
+#![cfg(feature = "nnue")]
+use axelrot::nnue::{AccumulatorStack, Network, NUM_FEATURES};
+use chess::{Board, ChessMove, MoveGen};
+use std::str::FromStr;
+use std::sync::Arc;
+
+fn random_network(hidden: usize) -> Network {
+    let mut state = 0x2545_f491_4f6c_dd1du64;
+    let mut next = move || {
+        state ^= state << 13;
+        state ^= state >> 7;
+        state ^= state << 17;
+        (state % 61) as i16 - 30
+    };
+    Network {
+        hidden,
+        feature_weights: (0..NUM_FEATURES * hidden).map(|_| next()).collect(),
+        feature_bias: (0..hidden).map(|_| next()).collect(),
+        output_weights: (0..2 * hidden).map(|_| next()).collect(),
+        output_bias: next(),
+    }
+}
+
+#[test]
+fn test_network_bytes_round_trip() {
+    let net = random_network(16);
+    let parsed = Network::from_bytes(&net.to_bytes()).unwrap();
+    let board = Board::default();
+    assert_eq!(parsed.evaluate(&board), net.evaluate(&board));
+    assert!(Network::from_bytes(b"{ \"piece_values\": [] }").is_err());
+}
+
+#[test]
+fn test_incremental_accumulator_matches_refresh() {
+    let net = Arc::new(random_network(40));
+    // Castling, en passant, promotion and king moves all exercised below.
+    let board = Board::from_str("r3k2r/pP3ppp/8/3pP3/8/8/PPP2PPP/R3K2R w KQkq d6 0 1").unwrap();
+    let mut stack = AccumulatorStack::new(net.clone(), &board);
+    let mut boards = vec![board];
+    for uci in ["e5d6", "e8g8", "b7a8q", "g8h8", "e1c1", "f8a8"] {
+        let mv = ChessMove::from_str(uci).unwrap();
+        let before = *boards.last().unwrap();
+        assert!(MoveGen::new_legal(&before).any(|m| m == mv), "{} not legal", uci);
+        let after = before.make_move_new(mv);
+        stack.push(&before, &after);
+        assert_eq!(stack.evaluate(&after), net.evaluate(&after), "after {}", uci);
+        boards.push(after);
+    }
+    for _ in 0..3 {
+        stack.pop();
+        boards.pop();
+    }
+    assert_eq!(stack.evaluate(boards.last().unwrap()), net.evaluate(boards.last().unwrap()));
+}
@@ -0,0 +1,69 @@
+#![cfg(feature = "nnue")]
+use axelrot::datagen::Record;
+use axelrot::nnue::SCALE;
+use axelrot::nnue_train::{sample_from_record, FloatNetwork, LrSchedule, TrainConfig, Trainer};
+use chess::Board;
+use std::str::FromStr;
+
+fn samples() -> Vec<axelrot::nnue_train::Sample> {
+    [
+        ("4k3/8/8/8/8/8/4P3/4K3 w - - 0 1", 200, 2),
+        ("4k3/4p3/8/8/8/8/8/4K3 b - - 0 1", -200, 0),
+        ("4k3/8/8/8/8/8/8/3QK3 w - - 0 1", 900, 2),
+        ("3qk3/8/8/8/8/8/8/4K3 w - - 0 1", -900, 0),
+        ("4k3/8/8/8/8/8/8/4K3 w - - 0 1", 0, 1),
+    ]
+    .iter()
+    .map(|&(fen, score, result)| {
+        let record = Record { board: Board::from_str(fen).unwrap(), score, result, fullmove: 1 };
+        sample_from_record(&record, 0.5)
+    })
+    .collect()
+}
+
+#[test]
+fn test_training_reduces_loss() {
+    let samples = samples();
+    let config = TrainConfig { hidden: 8, batch_size: 2, lr: 0.01, schedule: LrSchedule::Constant, ..TrainConfig::default() };
+    let mut trainer = Trainer::new(FloatNetwork::random(8, 7), config);
+    let before = trainer.net.loss(&samples);
+    let mut after = before;
+    for epoch in 0..30 {
+        after = trainer.epoch(&samples, epoch);
+    }
+    assert!(after < before, "loss went from {} to {}", before, after);
+}
+
+#[test]
+fn test_quantised_network_tracks_float_network() {
+    let net = FloatNetwork::random(16, 3);
+    let quantised = net.quantise();
+    let board = Board::from_str("r1bqkbnr/pppp1ppp/2n5/4p3/4P3/5N2/PPPP1PPP/RNBQKB1R w KQkq - 2 3").unwrap();
+    let record = Record { board, score: 0, result: 1, fullmove: 3 };
+    let float_cp = net.forward(&sample_from_record(&record, 0.5)) * SCALE as f32;
+    assert!((quantised.evaluate(&board) as f32 - float_cp).abs() < 15.0);
+}
+
+#[test]
+fn test_resumed_training_matches_uninterrupted_run() {
+    let samples = samples();
+    let config = TrainConfig { hidden: 8, batch_size: 2, lr: 0.01, ..TrainConfig::default() };
+    let path = std::env::temp_dir().join(format!("axelrot_ckpt_{}", std::process::id()));
+
+    let mut straight = Trainer::new(FloatNetwork::random(8, 7), config.clone());
+    let mut interrupted = Trainer::new(FloatNetwork::random(8, 7), config.clone());
+    for epoch in 0..2 {
+        straight.epoch(&samples, epoch);
+        interrupted.epoch(&samples, epoch);
+    }
+    interrupted.save_checkpoint(&path, 2).unwrap();
+    let (mut resumed, start) = Trainer::load_checkpoint(&path, config).unwrap();
+    std::fs::remove_file(&path).unwrap();
+
+    assert_eq!(start, 2);
+    for epoch in 2..4 {
+        assert_eq!(straight.epoch(&samples, epoch), resumed.epoch(&samples, epoch));
+    }
+    assert_eq!(straight.net.feature_weights, resumed.net.feature_weights);
+    assert_eq!(straight.net.output_weights, resumed.net.output_weights);
+}
@@ -0,0 +1,42 @@
+use axelrot::params::{set_active_params, EvalParams};
+use axelrot::{evaluate_with, SearchInfo};
+use chess::Board;
+use std::str::FromStr;
+
+#[test]
+fn test_params_json_round_trip() {
+    let mut params = EvalParams::default();
+    params.piece_values[4] = 950;
+    params.king_open_file = 42;
+
+    let parsed = EvalParams::from_json(&params.to_json()).unwrap();
+    assert_eq!(parsed, params);
+}
+
+#[test]
+fn test_partial_params_file_overrides_defaults() {
+    let params = EvalParams::from_json("{ \"piece_values\": [100, 300, 300, 500, 1000, 0] }").unwrap();
+    let fen = "rnb1kbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+    let board = Board::from_str(fen).unwrap();
+
+    let diff = evaluate_with(&board, &params) - evaluate_with(&board, &EvalParams::default());
+    assert_eq!(diff, 100);
+    assert!(EvalParams::from_json("{ \"bogus\": 1 }").is_err());
+}
+
+#[test]
+fn test_search_keeps_params_loaded_at_start() {
+    let board = Board::from_str("rnb1kbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1").unwrap();
+    let info = SearchInfo::new(1000);
+    let before = info.evaluate(&board);
+
+    let mut params = EvalParams::default();
+    params.piece_values[4] += 500;
+    set_active_params(params);
+    let during = info.evaluate(&board);
+    let fresh = SearchInfo::new(1000).evaluate(&board);
+    set_active_params(EvalParams::default());
+
+    assert_eq!(during, before);
+    assert_eq!(fresh, before + 500);
+}
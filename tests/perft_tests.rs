@@ -0,0 +1,56 @@
+use axelrot::perft::{divide, perft, perft_with, PerftOptions};
+use chess::Board;
+use std::io::Write;
+use std::process::{Command, Stdio};
+use std::str::FromStr;
+
+// Reference counts from the Chess Programming Wiki perft results page,
+// at depths that stay quick in a debug build.
+const POSITIONS: [(&str, &[u64]); 6] = [
+    ("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1", &[20, 400, 8902, 197281]),
+    ("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1", &[48, 2039, 97862]),
+    ("8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1", &[14, 191, 2812, 43238]),
+    ("r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1", &[6, 264, 9467]),
+    ("rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8", &[44, 1486, 62379]),
+    ("r4rk1/1pp1qppp/p1np1n2/2b1p1B1/2B1P1b1/P1NP1N2/1PP1QPPP/R4RK1 w - - 0 10", &[46, 2079, 89890]),
+];
+
+#[test]
+fn test_perft_standard_positions() {
+    for (fen, counts) in POSITIONS {
+        let board = Board::from_str(fen).unwrap();
+        assert_eq!(perft(&board, 0), 1);
+        for (depth, &nodes) in counts.iter().enumerate() {
+            assert_eq!(perft(&board, depth as u32 + 1), nodes, "{} depth {}", fen, depth + 1);
+        }
+    }
+}
+
+#[test]
+fn test_perft_hash_threads_and_divide() {
+    let options = PerftOptions { hash_mb: 1, threads: 3 };
+    for (fen, counts) in POSITIONS {
+        let board = Board::from_str(fen).unwrap();
+        let depth = counts.len().min(3);
+        assert_eq!(perft_with(&board, depth as u32, options), counts[depth - 1], "{}", fen);
+    }
+    let split = divide(&Board::default(), 3, options);
+    assert_eq!(split.len(), 20);
+    let e4 = split.iter().find(|(mv, _)| mv.to_string() == "e2e4").unwrap().1;
+    assert_eq!(e4, 600);
+}
+
+#[test]
+fn test_uci_go_perft() {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_axelrot"))
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .unwrap();
+    let commands = "position startpos moves e2e4\ngo perft 2\ngo perft x\nquit\n";
+    child.stdin.as_mut().unwrap().write_all(commands.as_bytes()).unwrap();
+    let stdout = String::from_utf8_lossy(&child.wait_with_output().unwrap().stdout).into_owned();
+    assert!(stdout.contains("e7e5: 29\n"), "{}", stdout);
+    assert!(stdout.contains("Nodes searched: 600\n"), "{}", stdout);
+    assert!(stdout.contains("info string error: invalid perft depth x"), "{}", stdout);
+}
@@ -0,0 +1,105 @@
+use axelrot::pgn::{eval_comment, format_eval, parse_eval_comment, parse_games, parse_san, to_san, write_game};
+use axelrot::{mate_in, mate_score, MATE};
+use chess::{Board, ChessMove, MoveGen};
+use std::str::FromStr;
+
+const PGN: &str = r#"[Event "Test \"quoted\""]
+[White "A"]
+[Black "B"]
+[Result "1-0"]
+
+1. e4 {best by test} e5 2. Nf3 $1 (2. f4 exf4) Nc6 3. Bb5 a6 4. O-O Nf6 1-0
+
+[Event "Promotion"]
+[FEN "8/P6k/8/8/8/8/8/K7 w - - 0 1"]
+
+1. a8=Q Kg6 2. Qb8 * 
+
+[Event "Broken"]
+
+1. e4 e5 2. Ke3 0-1
+"#;
+
+#[test]
+fn test_parse_games_keeps_main_line() {
+    let games = parse_games(PGN);
+    assert_eq!(games.len(), 3);
+    let first = games[0].as_ref().unwrap();
+    assert_eq!(first.tag("Event"), Some("Test \"quoted\""));
+    assert_eq!(first.result, "1-0");
+    let moves: Vec<String> = first.moves.iter().map(|m| m.to_string()).collect();
+    assert_eq!(moves, ["e2e4", "e7e5", "g1f3", "b8c6", "f1b5", "a7a6", "e1g1", "g8f6"]);
+
+    let second = games[1].as_ref().unwrap();
+    assert_eq!(second.moves[0], ChessMove::from_str("a7a8q").unwrap());
+    assert_eq!(second.result, "*");
+    assert!(games[2].as_ref().unwrap_err().contains("Ke3"));
+}
+
+// Every legal move in a few busy positions survives SAN output and parsing.
+#[test]
+fn test_san_round_trip() {
+    let fens = [
+        "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+        "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1",
+        "n1n5/PPPk4/8/8/8/8/4Kppp/5N1N b - - 0 1",
+        "1R4R1/8/8/R6R/8/3K4/8/3k4 w - - 0 1",
+        "R7/8/8/8/8/8/8/R3K2k w - - 0 1",
+    ];
+    for fen in fens {
+        let board = Board::from_str(fen).unwrap();
+        for mv in MoveGen::new_legal(&board) {
+            let san = to_san(&board, mv);
+            assert_eq!(parse_san(&board, &san), Ok(mv), "{} {}", fen, san);
+        }
+    }
+    let san = |fen: &str, mv: &str| to_san(&Board::from_str(fen).unwrap(), ChessMove::from_str(mv).unwrap());
+    assert_eq!(san("1R4R1/8/8/R6R/8/3K4/8/3k4 w - - 0 1", "a5a1"), "Ra1#");
+    assert_eq!(san("1R4R1/8/8/R6R/8/3K4/8/3k4 w - - 0 1", "h5e5"), "Rhe5");
+    assert_eq!(san("1R4R1/8/8/R6R/8/3K4/8/3k4 w - - 0 1", "g8g2"), "Rg2");
+    assert_eq!(san("R7/8/8/8/8/8/8/R3K2k w - - 0 1", "a8a4"), "R8a4");
+}
+
+#[test]
+fn test_game_tree_and_writing() {
+    let text = "[White \"A\"]\n\n{Opening} 1. e4 e5!? 2. Nf3 $1 {develops} (2. f4 exf4 (2... d5) 3. Nf3) (2. Bc4?!) Nc6 3. Bb5 a6 1/2-1/2\n";
+    let game = parse_games(text).pop().unwrap().unwrap();
+    assert_eq!(game.moves.len(), 6);
+    assert_eq!(game.tree[0].before.as_deref(), Some("Opening"));
+    assert_eq!(game.tree[1].nags, [5]);
+    let knight = &game.tree[2];
+    assert_eq!((knight.nags.as_slice(), knight.after.as_deref()), (&[1][..], Some("develops")));
+    assert_eq!(knight.variations.len(), 2);
+    assert_eq!(knight.variations[0].len(), 3);
+    assert_eq!(knight.variations[0][1].variations[0][0].mv, ChessMove::from_str("d7d5").unwrap());
+    assert_eq!(knight.variations[1][0].nags, [6]);
+
+    let written = write_game(&game).unwrap();
+    assert!(written.starts_with("[Event \"?\"]\n[Site \"?\"]\n[Date \"?\"]\n[Round \"?\"]\n[White \"A\"]\n"), "{}", written);
+    assert!(written.contains(
+        "{Opening} 1. e4 e5 $5 2. Nf3 $1 {develops} (2. f4 exf4 (2... d5) 3. Nf3) (2. Bc4\n$6) 2... Nc6 3. Bb5 a6 1/2-1/2\n"
+    ), "{}", written);
+    let reread = parse_games(&written).pop().unwrap().unwrap();
+    assert_eq!((&reread.tree, &reread.result, reread.tag("White")), (&game.tree, &game.result, Some("A")));
+
+    assert_eq!(eval_comment(35, 12, 512), "+0.35/12 0.512s");
+    assert_eq!(eval_comment(-9996, 5, 0), "-M2/5 0.000s");
+    assert_eq!(parse_eval_comment("-M2/5 0.000s"), Some((-9996, 5)));
+    assert_eq!(parse_eval_comment("-1.20/7 1.5s"), Some((-120, 7)));
+    assert_eq!(parse_eval_comment("book"), None);
+}
+
+#[test]
+fn test_mate_scores_round_trip_through_eval_comments() {
+    assert_eq!((format_eval(MATE - 1), format_eval(-MATE + 4), format_eval(35)), ("+M1".into(), "-M2".into(), "+0.35".into()));
+    assert_eq!((mate_in(MATE - 500), mate_in(35)), (None, None));
+    for moves in (-20..=20).filter(|&n| n != 0) {
+        let score = mate_score(moves);
+        assert_eq!(mate_in(score), Some(moves));
+        assert_eq!(parse_eval_comment(&eval_comment(score, 9, 0)), Some((score, 9)), "{}", moves);
+    }
+    // Mating takes an odd number of plies and being mated an even one.
+    for score in [MATE - 1, MATE - 19, -MATE + 2, -MATE + 20] {
+        assert_eq!(mate_score(mate_in(score).unwrap()), score);
+    }
+}
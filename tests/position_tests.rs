@@ -0,0 +1,61 @@
+use axelrot::uci::{parse_fen, parse_position};
+use chess::Board;
+use std::io::Write;
+use std::process::{Command, Stdio};
+use std::str::FromStr;
+
+#[test]
+fn test_fen_validation() {
+    let (board, fullmove) = parse_fen("rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3").unwrap();
+    assert_eq!(fullmove, 1);
+    assert_eq!(board, Board::from_str("rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1").unwrap());
+    assert_eq!(parse_fen("8/8/8/4k3/8/8/8/4K3 w - - 12 40").unwrap().1, 40);
+
+    let bad = [
+        "8/8/8/4k3/8/8/8/4K3 w - - 0",
+        "8/8/8/4k3/8/8/4K3 w - - 0 1",
+        "8/8/8/4k3/8/8/8/4K4 w - - 0 1",
+        "8/8/8/4k3/8/8/8/4X3 w - - 0 1",
+        "8/8/8/8/8/8/8/4K3 w - - 0 1",
+        "4k3/8/8/8/8/8/8/P3K3 w - - 0 1",
+        "8/8/8/4k3/8/8/8/4K3 x - - 0 1",
+        "8/8/8/4k3/8/8/8/4K3 w K - 0 1",
+        "8/8/8/4k3/8/8/8/4K3 w - e6 0 1",
+        "8/8/8/8/8/8/3k4/3K4 w - - 0 1",
+        "4k3/4R3/8/8/8/8/8/4K3 w - - 0 1",
+        "8/8/8/4k3/8/8/8/4K3 w - - 0 0",
+    ];
+    for fen in bad {
+        assert!(parse_fen(fen).is_err(), "{}", fen);
+    }
+}
+
+#[test]
+fn test_position_stops_at_first_illegal_move() {
+    let position = parse_position("startpos moves e2e4 e7e5 e1e3 g1f3").unwrap();
+    assert_eq!(position.board, Board::from_str("rnbqkbnr/pppp1ppp/8/4p3/4P3/8/PPPP1PPP/RNBQKBNR w KQkq - 0 2").unwrap());
+    assert_eq!(position.game_ply, 2);
+    assert!(position.error.unwrap().contains("illegal move e1e3"));
+
+    let position = parse_position("fen 4k3/8/8/8/8/8/8/4K2R w K - 3 20 moves e1g1").unwrap();
+    assert_eq!(position.game_ply, 39);
+    assert!(position.error.is_none());
+    assert!(parse_position("fen 4k3/8/8/8/8/8/8/4K2R w moves e1g1").is_err());
+    assert!(parse_position("start").is_err());
+}
+
+#[test]
+fn test_uci_reports_position_errors() {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_axelrot"))
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .unwrap();
+    let commands = "position startpos moves e2e4\nposition fen 8/8/8/8/8/8/8/8 w - - 0 1\nd\nposition startpos moves e2e4 e2e4\nd\nquit\n";
+    child.stdin.as_mut().unwrap().write_all(commands.as_bytes()).unwrap();
+    let stdout = String::from_utf8_lossy(&child.wait_with_output().unwrap().stdout).into_owned();
+    assert!(stdout.contains("info string error: invalid FEN 8/8/8/8/8/8/8/8 w - - 0 1: each side needs exactly one king"), "{}", stdout);
+    assert!(stdout.contains("info string error: illegal move e2e4"), "{}", stdout);
+    let after_e4 = Board::from_str("rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq - 0 1").unwrap().to_string();
+    assert_eq!(stdout.lines().filter(|l| *l == after_e4).count(), 2, "{}", stdout);
+}
@@ -0,0 +1,53 @@
+use axelrot::matches::{self, EngineSpec, MatchConfig, Tally};
+use axelrot::stats::{self, Sprt, SprtStatus};
+use std::path::PathBuf;
+
+#[test]
+fn test_elo_estimates_and_los() {
+    assert!(stats::elo(0.5).abs() < 1e-9);
+    assert!((stats::elo(0.75) - 190.85).abs() < 0.01);
+    assert!((stats::expected_score(stats::elo(0.64)) - 0.64).abs() < 1e-9);
+
+    let even = stats::estimate_wdl(30, 40, 30).unwrap();
+    assert!(even.elo.abs() < 1e-9 && (even.los - 0.5).abs() < 1e-6);
+    let ahead = stats::estimate_wdl(60, 20, 20).unwrap();
+    assert!((ahead.elo - 147.19).abs() < 0.01, "{:?}", ahead);
+    assert!(ahead.error > 60.0 && ahead.error < 80.0, "{:?}", ahead);
+    assert!(ahead.los > 0.9999);
+    // Pairs that split their points carry less noise than the games alone.
+    let pairs = stats::estimate_pentanomial(&[0, 10, 30, 10, 0]).unwrap();
+    assert!(pairs.elo.abs() < 1e-9 && pairs.error < stats::estimate_wdl(25, 50, 25).unwrap().error);
+    assert_eq!(stats::estimate_wdl(0, 0, 0), None);
+}
+
+#[test]
+fn test_sprt_bounds_and_decisions() {
+    let sprt = Sprt { elo0: 0.0, elo1: 5.0, alpha: 0.05, beta: 0.05 };
+    let (lower, upper) = sprt.bounds();
+    assert!((lower + 2.944).abs() < 0.001 && (upper - 2.944).abs() < 0.001);
+    assert_eq!(sprt.status(sprt.llr_pentanomial(&[0, 0, 10, 30, 10])), SprtStatus::AcceptH1);
+    assert_eq!(sprt.status(sprt.llr_pentanomial(&[10, 30, 10, 0, 0])), SprtStatus::AcceptH0);
+    assert_eq!(sprt.status(sprt.llr_pentanomial(&[5, 10, 20, 10, 5])), SprtStatus::Continue);
+    assert_eq!(sprt.status(sprt.llr_wdl(0, 0, 0)), SprtStatus::Continue);
+    let summary = stats::summary([30, 40, 30], &[5, 10, 20, 10, 5], Some(&sprt));
+    assert!(summary.contains("Elo difference: 0.0 +/- ") && summary.contains("SPRT: llr -0.02 "), "{}", summary);
+}
+
+#[test]
+fn test_match_stops_when_sprt_concludes() {
+    let axelrot = EngineSpec { name: "axelrot".to_string(), cmd: PathBuf::from(env!("CARGO_BIN_EXE_axelrot")), ..EngineSpec::default() };
+    let crasher = EngineSpec {
+        name: "crasher".to_string(),
+        cmd: PathBuf::from("sh"),
+        args: vec!["-c".to_string(), "while read l; do case $l in uci) echo uciok;; isready) echo readyok;; go*) exit 1;; esac; done".to_string()],
+        ..EngineSpec::default()
+    };
+    let mut config = MatchConfig::new([crasher, axelrot]);
+    config.games = 100;
+    config.sprt = Some(Sprt::default());
+    let games = matches::run(&config, |_, _| {}).unwrap();
+    let tally = Tally::new(&games);
+    assert!(games.len() < 100, "{}", games.len());
+    assert_eq!(tally.pairs, [games.len() as u32 / 2, 0, 0, 0, 0]);
+    assert_eq!(tally.sprt_status(&Sprt::default()), SprtStatus::AcceptH0);
+}
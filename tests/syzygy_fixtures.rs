@@ -0,0 +1,631 @@
+use axelrot::dtm::{DtmTables, Material};
+use axelrot::syzygy::Wdl;
+use chess::{Board, BoardBuilder, Color, MoveGen, Piece, Square, ALL_SQUARES, EMPTY};
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap};
+use std::path::Path;
+
+// Writes the Syzygy files under tests/syzygy that syzygy_tests.rs probes:
+// WDL and DTZ for KQvK, KRvK and KPvK, plus the all-draw KBvK and KNvK WDL
+// files that KPvK promotes into. This is a test-only encoder: it handles what
+// these five tables need and nothing more, and it is not a table generator.
+// Values come from the DTM generator; the index and the compression follow
+// the reference format and are written here independently of src/syzygy.rs,
+// but agreeing with the prober only shows both read the format the same way.
+// syzygy_tests.rs adds known answers from outside both, and can compare the
+// fixtures with real tables when they are available. Regenerate with
+//
+//     cargo test --release --test syzygy_fixtures -- --ignored
+const WDL_MAGIC: [u8; 4] = [0x71, 0xE8, 0x23, 0x5D];
+const DTZ_MAGIC: [u8; 4] = [0xD7, 0x66, 0x0C, 0xA5];
+const FLAG_STM: u8 = 1;
+const FLAG_MAPPED: u8 = 2;
+const FLAG_WIN_PLIES: u8 = 4;
+const FLAG_LOSS_PLIES: u8 = 8;
+const FLAG_SINGLE_VALUE: u8 = 128;
+
+// Piece codes as stored in the files; black pieces have bit 3 set.
+const PAWN: u8 = 1;
+const KING: u8 = 6;
+const BLACK_KING: u8 = 14;
+
+const BLOCK_BITS: u8 = 6;
+const SPAN_BITS: u8 = 8;
+// Values left free for the prober's own search.
+const ANY: (u16, u16) = (0, u16::MAX);
+
+// Piece order of one stored side and where the leading group sits among the
+// index multipliers.
+struct Layout {
+    pieces: [u8; 3],
+    lead_group: u8,
+}
+
+struct DtzSpec {
+    black_to_move: bool,
+    flags: u8,
+    layout: Layout,
+}
+
+// White has a king and `piece`, black a bare king.
+struct Fixture {
+    name: &'static str,
+    piece: Piece,
+    wdl: [Layout; 2],
+    dtz: Option<DtzSpec>,
+}
+
+const fn layout(pieces: [u8; 3], lead_group: u8) -> Layout {
+    Layout { pieces, lead_group }
+}
+
+// Different piece orders and DTZ encodings per table, so the prober's
+// reordering, side switching and value mapping all get exercised.
+static FIXTURES: [Fixture; 5] = [
+    Fixture {
+        name: "KQvK",
+        piece: Piece::Queen,
+        wdl: [layout([KING, 5, BLACK_KING], 0), layout([5, BLACK_KING, KING], 0)],
+        dtz: Some(DtzSpec { black_to_move: false, flags: FLAG_MAPPED, layout: layout([BLACK_KING, KING, 5], 0) }),
+    },
+    Fixture {
+        name: "KRvK",
+        piece: Piece::Rook,
+        wdl: [layout([KING, 4, BLACK_KING], 0), layout([BLACK_KING, 4, KING], 0)],
+        dtz: Some(DtzSpec { black_to_move: true, flags: FLAG_LOSS_PLIES, layout: layout([4, KING, BLACK_KING], 0) }),
+    },
+    Fixture {
+        name: "KPvK",
+        piece: Piece::Pawn,
+        wdl: [layout([PAWN, KING, BLACK_KING], 0), layout([PAWN, BLACK_KING, KING], 2)],
+        dtz: Some(DtzSpec {
+            black_to_move: false,
+            flags: FLAG_MAPPED | FLAG_WIN_PLIES,
+            layout: layout([PAWN, BLACK_KING, KING], 1),
+        }),
+    },
+    Fixture { name: "KBvK", piece: Piece::Bishop, wdl: [layout([KING, 3, BLACK_KING], 0), layout([KING, 3, BLACK_KING], 0)], dtz: None },
+    Fixture { name: "KNvK", piece: Piece::Knight, wdl: [layout([KING, 2, BLACK_KING], 0), layout([KING, 2, BLACK_KING], 0)], dtz: None },
+];
+
+// Positions are numbered by side to move, white king, white piece and black
+// king.
+const POSITIONS: usize = 1 << 19;
+
+fn squares(id: usize) -> (usize, usize, usize, usize) {
+    (id >> 18, (id >> 12) & 63, (id >> 6) & 63, id & 63)
+}
+
+fn id_of(board: &Board) -> usize {
+    let piece = (board.color_combined(Color::White) & !board.pieces(Piece::King)).to_square();
+    let stm = (board.side_to_move() == Color::Black) as usize;
+    (((stm << 6) | board.king_square(Color::White).to_index()) << 12)
+        | (piece.to_index() << 6)
+        | board.king_square(Color::Black).to_index()
+}
+
+fn build(piece: Piece, id: usize) -> Option<Board> {
+    let (stm, wk, x, bk) = squares(id);
+    if wk == x || wk == bk || x == bk || chess::get_king_moves(ALL_SQUARES[wk]) & chess::BitBoard::from_square(ALL_SQUARES[bk]) != EMPTY {
+        return None;
+    }
+    if piece == Piece::Pawn && !(8..56).contains(&x) {
+        return None;
+    }
+    let mut builder = BoardBuilder::new();
+    builder
+        .piece(ALL_SQUARES[wk], Piece::King, Color::White)
+        .piece(ALL_SQUARES[x], piece, Color::White)
+        .piece(ALL_SQUARES[bk], Piece::King, Color::Black)
+        .side_to_move(if stm == 1 { Color::Black } else { Color::White });
+    Board::try_from(&builder).ok()
+}
+
+#[derive(Clone, Copy)]
+struct Node {
+    wdl: Wdl,
+    moves: usize,
+    captures: usize,
+    zeroing: usize,
+    // Best result of a capture, and whether a capture or pawn move wins.
+    best_capture: Option<Wdl>,
+    zeroing_win: bool,
+    mates: bool,
+    dtz: i32,
+}
+
+fn negate(wdl: Wdl) -> Wdl {
+    match wdl {
+        Wdl::Win => Wdl::Loss,
+        Wdl::Loss => Wdl::Win,
+        wdl => wdl,
+    }
+}
+
+// WDL from the DTM tables, then DTZ by retrograde levels: a win is one ply
+// more than its fastest move into a loss (or 1 if it zeroes or mates), and a
+// loss one ply more than its slowest move.
+fn solve(tables: &DtmTables, piece: Piece) -> Vec<Option<Node>> {
+    let mut nodes: Vec<Option<Node>> = vec![None; POSITIONS];
+    let mut children = vec![Vec::new(); POSITIONS];
+    for id in 0..POSITIONS {
+        let Some(board) = build(piece, id) else { continue };
+        let wdl = tables.probe_dtm(&board).unwrap().wdl();
+        let mut node = Node { wdl, moves: 0, captures: 0, zeroing: 0, best_capture: None, zeroing_win: false, mates: false, dtz: 0 };
+        for mv in MoveGen::new_legal(&board) {
+            let next = board.make_move_new(mv);
+            let value = negate(tables.probe_dtm(&next).unwrap().wdl());
+            let capture = board.piece_on(mv.get_dest()).is_some();
+            node.moves += 1;
+            if capture {
+                node.captures += 1;
+                node.best_capture = node.best_capture.max(Some(value));
+            }
+            if capture || board.piece_on(mv.get_source()) == Some(Piece::Pawn) {
+                node.zeroing += 1;
+                node.zeroing_win |= value == Wdl::Win;
+            } else {
+                children[id].push(id_of(&next));
+            }
+            node.mates |= *next.checkers() != EMPTY && MoveGen::new_legal(&next).len() == 0;
+        }
+        if (wdl == Wdl::Win && (node.zeroing_win || node.mates)) || (wdl == Wdl::Loss && node.zeroing == node.moves) {
+            node.dtz = if wdl == Wdl::Win { 1 } else { -1 };
+        }
+        nodes[id] = Some(node);
+    }
+
+    let dtz = |nodes: &[Option<Node>], id: usize| nodes[id].map_or(0, |n| n.dtz);
+    for level in 2.. {
+        let mut changed = false;
+        // Losses first, so a level's wins only see losses from earlier levels.
+        for result in [Wdl::Loss, Wdl::Win] {
+            for id in 0..POSITIONS {
+                let Some(node) = nodes[id] else { continue };
+                if node.dtz != 0 || node.wdl != result {
+                    continue;
+                }
+                let value = match node.wdl {
+                    Wdl::Loss if children[id].iter().all(|&c| dtz(&nodes, c) > 0) => {
+                        -(1 + children[id].iter().map(|&c| dtz(&nodes, c)).max().unwrap())
+                    }
+                    Wdl::Win if children[id].iter().any(|&c| dtz(&nodes, c) == -(level - 1)) => level,
+                    _ => continue,
+                };
+                nodes[id].as_mut().unwrap().dtz = value;
+                changed = true;
+            }
+        }
+        if !changed {
+            break;
+        }
+    }
+    for node in nodes.iter().flatten() {
+        assert!(node.wdl == Wdl::Draw || node.dtz != 0, "unresolved position");
+    }
+    nodes
+}
+
+fn off_diagonal(sq: usize) -> i32 {
+    (sq / 8) as i32 - (sq % 8) as i32
+}
+
+// a1-d1-d4 triangle: squares below the diagonal first, then the diagonal.
+fn triangle(sq: usize) -> usize {
+    let order = [Square::B1, Square::C1, Square::D1, Square::C2, Square::D2, Square::D3, Square::A1, Square::B2, Square::C3, Square::D4];
+    order.iter().position(|s| s.to_index() == sq).unwrap()
+}
+
+// b1-h1-h7: the 28 squares below the diagonal.
+fn below_diagonal(sq: usize) -> usize {
+    (0..sq).filter(|&s| off_diagonal(s) < 0).count()
+}
+
+// Three unique pieces without pawns: the first goes into the a1-d1-d4
+// triangle, then the first piece off the long diagonal goes below it.
+fn pawnless_index(mut s: [usize; 3]) -> usize {
+    if s[0] % 8 > 3 {
+        s = s.map(|q| q ^ 7);
+    }
+    if s[0] / 8 > 3 {
+        s = s.map(|q| q ^ 56);
+    }
+    if let Some(i) = (0..3).find(|&i| off_diagonal(s[i]) != 0)
+        && off_diagonal(s[i]) > 0
+    {
+        for q in &mut s[i..] {
+            *q = (*q % 8) * 8 + *q / 8;
+        }
+    }
+    let adjust1 = (s[1] > s[0]) as usize;
+    let adjust2 = (s[2] > s[0]) as usize + (s[2] > s[1]) as usize;
+    let rank = |q: usize| q / 8;
+    if off_diagonal(s[0]) != 0 {
+        (triangle(s[0]) * 63 + s[1] - adjust1) * 62 + s[2] - adjust2
+    } else if off_diagonal(s[1]) != 0 {
+        (6 * 63 + rank(s[0]) * 28 + below_diagonal(s[1])) * 62 + s[2] - adjust2
+    } else if off_diagonal(s[2]) != 0 {
+        6 * 63 * 62 + 4 * 28 * 62 + rank(s[0]) * 7 * 28 + (rank(s[1]) - adjust1) * 28 + below_diagonal(s[2])
+    } else {
+        6 * 63 * 62 + 4 * 28 * 62 + 4 * 7 * 28 + rank(s[0]) * 7 * 6 + (rank(s[1]) - adjust1) * 6 + rank(s[2]) - adjust2
+    }
+}
+
+// Multipliers of the lead pawn and the two kings, with the lead group placed
+// `lead_group` steps into the index.
+fn multipliers(lead_group: u8) -> [usize; 3] {
+    let sizes = [6, 63, 62];
+    let mut out = [0; 3];
+    let (mut idx, mut next, mut k) = (1, 1, 0);
+    while next < 3 || k == lead_group {
+        let group = if k == lead_group { 0 } else { next };
+        out[group] = idx;
+        idx *= sizes[group];
+        if group != 0 {
+            next += 1;
+        }
+        k += 1;
+    }
+    out
+}
+
+fn table_size(piece: Piece) -> usize {
+    if piece == Piece::Pawn { 6 * 63 * 62 } else { 31332 }
+}
+
+// (file, index) of position `id` in a side stored with `layout`.
+fn index(layout: &Layout, piece: Piece, id: usize) -> (usize, usize) {
+    let (_, wk, x, bk) = squares(id);
+    let mut s = layout.pieces.map(|code| match code {
+        KING => wk,
+        BLACK_KING => bk,
+        _ => x,
+    });
+    if piece != Piece::Pawn {
+        return (0, pawnless_index(s));
+    }
+    if s[0] % 8 > 3 {
+        s = s.map(|q| q ^ 7);
+    }
+    let m = multipliers(layout.lead_group);
+    let first = s[1] - (s[1] > s[0]) as usize;
+    let second = s[2] - (s[2] > s[0]) as usize - (s[2] > s[1]) as usize;
+    (s[0] % 8, (s[0] / 8 - 1) * m[0] + first * m[1] + second * m[2])
+}
+
+fn narrow(range: &mut (u16, u16), allowed: (u16, u16)) {
+    *range = (range.0.max(allowed.0), range.1.min(allowed.1));
+    assert!(range.0 <= range.1, "positions sharing an index disagree");
+}
+
+// Free values repeat their neighbour, which compresses best.
+fn fill(ranges: &[(u16, u16)]) -> Vec<u16> {
+    let mut previous = ranges.iter().find(|r| r.0 == r.1).map_or(0, |r| r.0);
+    ranges
+        .iter()
+        .map(|&(lo, hi)| {
+            previous = previous.clamp(lo, hi);
+            previous
+        })
+        .collect()
+}
+
+// The prober reads a WDL value only when no capture reaches the result.
+fn wdl_range(node: &Node) -> (u16, u16) {
+    let value = (node.wdl as i32 + 2) as u16;
+    if node.captures > 0 && node.captures == node.moves {
+        ANY
+    } else if node.best_capture >= Some(node.wdl) {
+        (0, value)
+    } else {
+        (value, value)
+    }
+}
+
+#[derive(Default)]
+struct Encoded {
+    header: Vec<u8>,
+    sparse: Vec<u8>,
+    lengths: Vec<u8>,
+    data: Vec<u8>,
+}
+
+enum Symbol {
+    Value(u16),
+    Pair(usize, usize),
+}
+
+// Huffman code lengths for symbols with the given frequencies.
+fn code_lengths(freq: &[usize]) -> Vec<usize> {
+    let mut parent = vec![usize::MAX; freq.len()];
+    let mut heap: BinaryHeap<Reverse<(usize, usize)>> = freq.iter().enumerate().map(|(i, &f)| Reverse((f, i))).collect();
+    while heap.len() > 1 {
+        let Reverse((a, i)) = heap.pop().unwrap();
+        let Reverse((b, j)) = heap.pop().unwrap();
+        let node = parent.len();
+        parent.push(usize::MAX);
+        parent[i] = node;
+        parent[j] = node;
+        heap.push(Reverse((a + b, node)));
+    }
+    (0..freq.len())
+        .map(|mut i| {
+            let mut depth = 0;
+            while parent[i] != usize::MAX {
+                i = parent[i];
+                depth += 1;
+            }
+            depth
+        })
+        .collect()
+}
+
+fn compress(values: &[u16], flags: u8) -> Encoded {
+    if values.iter().all(|&v| v == values[0]) {
+        return Encoded { header: vec![flags | FLAG_SINGLE_VALUE, values[0] as u8], ..Encoded::default() };
+    }
+
+    // Recursive pairing: keep replacing the most common adjacent pair with a
+    // new symbol, as long as a symbol expands to at most 256 values.
+    let mut leaves: Vec<u16> = values.to_vec();
+    leaves.sort_unstable();
+    leaves.dedup();
+    let mut symbols: Vec<Symbol> = leaves.iter().map(|&v| Symbol::Value(v)).collect();
+    let mut expansion = vec![1usize; symbols.len()];
+    let mut seq: Vec<usize> = values.iter().map(|v| leaves.binary_search(v).unwrap()).collect();
+    while symbols.len() < 1000 {
+        let mut counts: HashMap<(usize, usize), usize> = HashMap::new();
+        for w in seq.windows(2) {
+            *counts.entry((w[0], w[1])).or_default() += 1;
+        }
+        let best = counts
+            .into_iter()
+            .filter(|&((a, b), _)| expansion[a] + expansion[b] <= 256)
+            .max_by_key(|&(pair, n)| (n, Reverse(pair)));
+        let Some(((a, b), n)) = best else { break };
+        if n < 8 {
+            break;
+        }
+        let id = symbols.len();
+        symbols.push(Symbol::Pair(a, b));
+        expansion.push(expansion[a] + expansion[b]);
+        let mut out = Vec::with_capacity(seq.len());
+        let mut i = 0;
+        while i < seq.len() {
+            if i + 1 < seq.len() && seq[i] == a && seq[i + 1] == b {
+                out.push(id);
+                i += 2;
+            } else {
+                out.push(seq[i]);
+                i += 1;
+            }
+        }
+        seq = out;
+    }
+
+    // Canonical Huffman code over the symbols still in use. Unused symbols
+    // get the lowest ids, then ids ascend from the longest codes to the
+    // shortest, and codes of each length start where the longer ones ended.
+    let mut freq = vec![0; symbols.len()];
+    for &s in &seq {
+        freq[s] += 1;
+    }
+    let mut coded: Vec<usize> = (0..symbols.len()).filter(|&s| freq[s] > 0).collect();
+    if coded.len() == 1 {
+        coded.push((0..symbols.len()).find(|&s| freq[s] == 0).unwrap());
+    }
+    let lengths = code_lengths(&coded.iter().map(|&s| freq[s]).collect::<Vec<_>>());
+    let mut length = vec![0; symbols.len()];
+    for (&s, &len) in coded.iter().zip(&lengths) {
+        length[s] = len;
+    }
+    let (min, max) = (*lengths.iter().min().unwrap(), *lengths.iter().max().unwrap());
+    assert!(max <= 32, "code too long");
+    let mut order: Vec<usize> = (0..symbols.len()).filter(|&s| length[s] == 0).collect();
+    let unused = order.len();
+    let mut by_length = coded.clone();
+    by_length.sort_by_key(|&s| (Reverse(length[s]), s));
+    order.extend(by_length);
+    let mut new_id = vec![0; symbols.len()];
+    for (id, &s) in order.iter().enumerate() {
+        new_id[s] = id;
+    }
+    let count = |len: usize| coded.iter().filter(|&&s| length[s] == len).count();
+    let mut lowest = vec![0; max - min + 1];
+    lowest[max - min] = unused;
+    for i in (0..max - min).rev() {
+        lowest[i] = lowest[i + 1] + count(min + i + 1);
+    }
+    let mut first = vec![0u64; max + 1];
+    for len in (min..=max).rev() {
+        if len < max {
+            let next = first[len + 1] + count(len + 1) as u64;
+            assert_eq!(next % 2, 0);
+            first[len] = next / 2;
+        }
+    }
+    assert_eq!(first[min] + count(min) as u64, 1 << min);
+    let code = |s: usize| first[length[s]] + (new_id[s] - lowest[length[s] - min]) as u64;
+
+    let mut header = vec![flags, BLOCK_BITS, SPAN_BITS, 0, 0, 0, 0, 0, max as u8, min as u8];
+    for &l in &lowest {
+        header.extend_from_slice(&(l as u16).to_le_bytes());
+    }
+    header.extend_from_slice(&(symbols.len() as u16).to_le_bytes());
+    for &s in &order {
+        let (left, right) = match symbols[s] {
+            Symbol::Value(v) => (v as usize, 0xFFF),
+            Symbol::Pair(a, b) => (new_id[a], new_id[b]),
+        };
+        header.extend_from_slice(&[left as u8, ((left >> 8) & 0xF) as u8 | ((right & 0xF) << 4) as u8, (right >> 4) as u8]);
+    }
+    if symbols.len() % 2 == 1 {
+        header.push(0);
+    }
+
+    // Whole symbols per block, bits most significant first.
+    let block_size = 1usize << BLOCK_BITS;
+    let mut blocks: Vec<(Vec<u8>, usize)> = Vec::new();
+    let (mut bits, mut block, mut block_values) = (0, vec![0u8; block_size], 0);
+    for &s in &seq {
+        let len = length[s];
+        if bits + len > block_size * 8 || block_values + expansion[s] > 60000 {
+            blocks.push((std::mem::replace(&mut block, vec![0; block_size]), block_values));
+            bits = 0;
+            block_values = 0;
+        }
+        let c = code(s);
+        for b in 0..len {
+            if c >> (len - 1 - b) & 1 == 1 {
+                block[(bits + b) / 8] |= 0x80 >> ((bits + b) % 8);
+            }
+        }
+        bits += len;
+        block_values += expansion[s];
+    }
+    blocks.push((block, block_values));
+    header[4..8].copy_from_slice(&(blocks.len() as u32).to_le_bytes());
+
+    // Sparse index: the block and offset of the middle of every span.
+    let mut starts = vec![0];
+    for (_, n) in &blocks {
+        starts.push(starts.last().unwrap() + n);
+    }
+    let span = 1usize << SPAN_BITS;
+    let mut sparse = Vec::new();
+    for k in 0..values.len().div_ceil(span) {
+        let pos = k * span + span / 2;
+        let b = (0..blocks.len()).rfind(|&b| starts[b] <= pos).unwrap();
+        sparse.extend_from_slice(&(b as u32).to_le_bytes());
+        sparse.extend_from_slice(&((pos - starts[b]) as u16).to_le_bytes());
+    }
+    let lengths = blocks.iter().flat_map(|(_, n)| ((n - 1) as u16).to_le_bytes()).collect();
+    let data = blocks.into_iter().flat_map(|(bytes, _)| bytes).collect();
+    Encoded { header, sparse, lengths, data }
+}
+
+struct Item {
+    layout: &'static Layout,
+    encoded: Encoded,
+    // DTZ value lists for wins, losses, cursed wins and blessed losses.
+    map: Option<[Vec<u8>; 4]>,
+}
+
+// `items` holds the stored sides of each file.
+fn write_file(path: &Path, magic: [u8; 4], pawns: bool, items: &[Vec<Item>]) {
+    let mut out = magic.to_vec();
+    out.push(1 | (pawns as u8) << 1);
+    for sides in items {
+        let other = sides.last().unwrap().layout;
+        out.push(sides[0].layout.lead_group | other.lead_group << 4);
+        for k in 0..3 {
+            out.push(sides[0].layout.pieces[k] | other.pieces[k] << 4);
+        }
+    }
+    out.resize(out.len().next_multiple_of(2), 0);
+    for item in items.iter().flatten() {
+        out.extend_from_slice(&item.encoded.header);
+    }
+    if magic == DTZ_MAGIC {
+        for map in items.iter().flatten().filter_map(|item| item.map.as_ref()) {
+            for list in map {
+                out.push(list.len() as u8);
+                out.extend_from_slice(list);
+            }
+        }
+        out.resize(out.len().next_multiple_of(2), 0);
+    }
+    for item in items.iter().flatten() {
+        out.extend_from_slice(&item.encoded.sparse);
+    }
+    for item in items.iter().flatten() {
+        out.extend_from_slice(&item.encoded.lengths);
+    }
+    for item in items.iter().flatten() {
+        out.resize(out.len().next_multiple_of(64), 0);
+        out.extend_from_slice(&item.encoded.data);
+    }
+    out.resize(out.len().next_multiple_of(64) + 16, 0);
+    std::fs::write(path, out).unwrap();
+}
+
+fn write_fixture(fixture: &'static Fixture, tables: &DtmTables, dir: &Path) {
+    let nodes = solve(tables, fixture.piece);
+    let pawns = fixture.piece == Piece::Pawn;
+    let files = if pawns { 4 } else { 1 };
+    let size = table_size(fixture.piece);
+    let stored = |side: usize| (0..POSITIONS).filter(move |&id| id >> 18 == side).filter_map(|id| nodes[id].map(|n| (id, n)));
+
+    let mut items: Vec<Vec<Item>> = (0..files).map(|_| Vec::new()).collect();
+    for (side, layout) in fixture.wdl.iter().enumerate() {
+        let mut ranges = vec![vec![ANY; size]; files];
+        for (id, node) in stored(side) {
+            let (file, idx) = index(layout, fixture.piece, id);
+            narrow(&mut ranges[file][idx], wdl_range(&node));
+        }
+        for (file, ranges) in ranges.iter().enumerate() {
+            items[file].push(Item { layout, encoded: compress(&fill(ranges), 0), map: None });
+        }
+    }
+    write_file(&dir.join(format!("{}.rtbw", fixture.name)), WDL_MAGIC, pawns, &items);
+
+    let Some(dtz) = &fixture.dtz else { return };
+    let flags = dtz.flags | if dtz.black_to_move { FLAG_STM } else { 0 };
+    // Values the prober reads from the file: positions with a result that no
+    // capture or pawn move already reaches.
+    let mut cared = Vec::new();
+    for (id, node) in stored(dtz.black_to_move as usize) {
+        if node.wdl == Wdl::Draw || node.zeroing_win || (node.zeroing == node.moves && node.moves > 0) {
+            continue;
+        }
+        let win = node.wdl == Wdl::Win;
+        let plies = flags & if win { FLAG_WIN_PLIES } else { FLAG_LOSS_PLIES } != 0;
+        let distance = node.dtz.unsigned_abs() - 1;
+        assert!(plies || distance % 2 == 0, "DTZ not representable in moves");
+        let value = if plies { distance } else { distance / 2 } as u16;
+        let (file, idx) = index(&dtz.layout, fixture.piece, id);
+        cared.push((file, idx, !win as usize, value));
+    }
+    let mut items: Vec<Vec<Item>> = Vec::new();
+    for file in 0..files {
+        let mut map: [Vec<u8>; 4] = Default::default();
+        if flags & FLAG_MAPPED != 0 {
+            for (class, list) in map.iter_mut().enumerate().take(2) {
+                let mut freq: HashMap<u16, usize> = HashMap::new();
+                for &(_, _, _, value) in cared.iter().filter(|c| c.0 == file && c.2 == class) {
+                    *freq.entry(value).or_default() += 1;
+                }
+                let mut values: Vec<(u16, usize)> = freq.into_iter().collect();
+                values.sort_by_key(|&(value, n)| (Reverse(n), value));
+                *list = values.into_iter().map(|(value, _)| u8::try_from(value).unwrap()).collect();
+            }
+        }
+        let mut ranges = vec![ANY; size];
+        for &(_, idx, class, value) in cared.iter().filter(|c| c.0 == file) {
+            let symbol = if flags & FLAG_MAPPED != 0 {
+                map[class].iter().position(|&v| v as u16 == value).unwrap() as u16
+            } else {
+                value
+            };
+            narrow(&mut ranges[idx], (symbol, symbol));
+        }
+        let map = (flags & FLAG_MAPPED != 0).then_some(map);
+        items.push(vec![Item { layout: &dtz.layout, encoded: compress(&fill(&ranges), flags), map }]);
+    }
+    write_file(&dir.join(format!("{}.rtbz", fixture.name)), DTZ_MAGIC, pawns, &items);
+}
+
+#[test]
+#[ignore]
+fn write_fixtures() {
+    let mut tables = DtmTables::new();
+    let threads = std::thread::available_parallelism().map_or(1, |n| n.get());
+    for name in ["KQvK", "KRvK", "KPvK"] {
+        tables.generate(&Material::parse(name).unwrap(), threads).unwrap();
+    }
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/syzygy");
+    std::fs::create_dir_all(&dir).unwrap();
+    for fixture in &FIXTURES {
+        write_fixture(fixture, &tables, &dir);
+    }
+}
@@ -0,0 +1,253 @@
+use axelrot::dtm::{DtmTables, Material};
+use axelrot::syzygy::{best_root_moves, Tablebases, Wdl};
+use axelrot::tablebase::Dtm;
+use chess::{get_king_moves, BitBoard, Board, BoardBuilder, Color, MoveGen, Piece, ALL_SQUARES, EMPTY};
+use std::io::Write;
+use std::process::{Command, Stdio};
+use std::str::FromStr;
+use std::sync::LazyLock;
+
+// Real-format WDL and DTZ files for KQvK, KRvK and KPvK (plus the all-draw
+// KBvK and KNvK), written by tests/syzygy_fixtures.rs.
+const FIXTURES: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/syzygy");
+
+static DTM: LazyLock<DtmTables> = LazyLock::new(|| {
+    let mut tables = DtmTables::new();
+    for name in ["KQvK", "KRvK", "KPvK"] {
+        tables.generate(&Material::parse(name).unwrap(), 1).unwrap();
+    }
+    tables
+});
+
+// Legal placements of a white king and `piece` against a bare king, every
+// `step`th one, in both colour orientations.
+fn positions(piece: Piece, step: usize) -> Vec<Board> {
+    let mut out = Vec::new();
+    for i in (0..2 * 64 * 64 * 64).step_by(step) {
+        let (white_to_move, wk, x, bk) = (i & 1 == 0, (i >> 1) & 63, (i >> 7) & 63, i >> 13);
+        if wk == x || wk == bk || x == bk || get_king_moves(ALL_SQUARES[wk]) & BitBoard::from_square(ALL_SQUARES[bk]) != EMPTY {
+            continue;
+        }
+        if piece == Piece::Pawn && !(8..56).contains(&x) {
+            continue;
+        }
+        for flip in [0, 56] {
+            let colour = |c: Color| if flip == 0 { c } else { !c };
+            let mut builder = BoardBuilder::new();
+            builder
+                .piece(ALL_SQUARES[wk ^ flip], Piece::King, colour(Color::White))
+                .piece(ALL_SQUARES[x ^ flip], piece, colour(Color::White))
+                .piece(ALL_SQUARES[bk ^ flip], Piece::King, colour(Color::Black))
+                .side_to_move(colour(if white_to_move { Color::White } else { Color::Black }));
+            if let Ok(board) = Board::try_from(&builder) {
+                out.push(board);
+            }
+        }
+    }
+    out
+}
+
+fn open() -> Tablebases {
+    Tablebases::open(FIXTURES).unwrap()
+}
+
+#[test]
+fn test_wdl_and_dtz_match_dtm_without_pawns() {
+    let tb = open();
+    assert_eq!((tb.len(), tb.max_pieces()), (5, 3));
+    for piece in [Piece::Queen, Piece::Rook] {
+        for board in positions(piece, 31) {
+            let dtm = DTM.probe_dtm(&board).unwrap();
+            assert_eq!(tb.probe_wdl(&board), Some(dtm.wdl()), "{}", board);
+            // The winner has nothing to capture, so DTZ is the distance to mate.
+            let expected = match dtm {
+                Dtm::Win(plies) => plies as i32,
+                Dtm::Loss(plies) => -(plies.max(1) as i32),
+                Dtm::Draw => 0,
+            };
+            assert_eq!(tb.probe_dtz(&board), Some(expected), "{}", board);
+        }
+    }
+    // The longest wins: mate in 10 with the queen and in 16 with the rook.
+    for (fen, longest) in [("8/8/8/5k2/8/8/1Q6/K7 w - - 0 1", 19), ("8/8/8/8/8/2k5/1R6/K7 w - - 0 1", 31)] {
+        let board = Board::from_str(fen).unwrap();
+        assert_eq!(DTM.probe_dtm(&board), Some(Dtm::Win(longest as u32)));
+        assert_eq!(tb.probe_dtz(&board), Some(longest));
+    }
+}
+
+// Known answers that come from neither the fixture writer nor the DTM
+// generator: the format's magic numbers, positions the rules decide, and the
+// longest KQK and KRK mates (10 and 16 moves) from endgame theory.
+#[test]
+fn test_known_answers_from_the_format_and_theory() {
+    for (name, magic) in [("KQvK.rtbw", 0x5d23e871u32), ("KRvK.rtbz", 0xa50c66d7), ("KPvK.rtbw", 0x5d23e871)] {
+        let bytes = std::fs::read(format!("{}/{}", FIXTURES, name)).unwrap();
+        assert_eq!(u32::from_le_bytes(bytes[..4].try_into().unwrap()), magic, "{}", name);
+    }
+    let tb = open();
+    let probe = |fen: &str| {
+        let board = Board::from_str(fen).unwrap();
+        (tb.probe_wdl(&board), tb.probe_dtz(&board))
+    };
+    // Mate in one, stalemate and a queen that can be taken.
+    assert_eq!(probe("k7/8/1K6/8/8/8/8/7R w - - 0 1"), (Some(Wdl::Win), Some(1)));
+    assert_eq!(probe("6k1/8/6K1/8/8/8/8/Q7 w - - 0 1"), (Some(Wdl::Win), Some(1)));
+    assert_eq!(probe("k7/2Q5/1K6/8/8/8/8/8 b - - 0 1"), (Some(Wdl::Draw), Some(0)));
+    assert_eq!(probe("8/8/8/8/8/1Q6/2k5/K7 b - - 0 1"), (Some(Wdl::Draw), Some(0)));
+
+    // Every pawnless position is a mirror of one with the white king in the
+    // a1-d1-d4 triangle.
+    let triangle = [0, 1, 2, 3, 9, 10, 11, 18, 19, 27];
+    for (piece, longest) in [(Piece::Queen, 19), (Piece::Rook, 31)] {
+        let mut dtz = 0;
+        for wk in triangle {
+            for (x, bk) in (0..64 * 64).map(|i| (i % 64, i / 64)) {
+                let mut builder = BoardBuilder::new();
+                builder
+                    .piece(ALL_SQUARES[wk], Piece::King, Color::White)
+                    .piece(ALL_SQUARES[x], piece, Color::White)
+                    .piece(ALL_SQUARES[bk], Piece::King, Color::Black);
+                if wk != x && wk != bk && x != bk
+                    && let Ok(board) = Board::try_from(&builder)
+                {
+                    dtz = dtz.max(tb.probe_dtz(&board).unwrap());
+                }
+            }
+        }
+        assert_eq!(dtz, longest, "{:?}", piece);
+    }
+}
+
+// Compares the fixtures with real Syzygy tables for the same material, e.g.
+//
+//     SYZYGY_REFERENCE=/path/to/3-4-5 cargo test --test syzygy_tests -- --ignored
+//
+// Real DTZ files may store wins in moves rather than plies, so DTZ can be one
+// higher there.
+#[test]
+#[ignore]
+fn test_fixtures_match_real_tables() {
+    let path = std::env::var("SYZYGY_REFERENCE").expect("SYZYGY_REFERENCE names a directory of real tables");
+    let (tb, real) = (open(), Tablebases::open(&path).unwrap());
+    for piece in [Piece::Queen, Piece::Rook, Piece::Pawn] {
+        for board in positions(piece, 7) {
+            assert_eq!(tb.probe_wdl(&board), real.probe_wdl(&board), "{}", board);
+            let (ours, theirs) = (tb.probe_dtz(&board).unwrap(), real.probe_dtz(&board).unwrap());
+            assert!(ours == theirs || ours.signum() == theirs.signum() && (ours - theirs).abs() == 1, "{} {} {}", board, ours, theirs);
+        }
+    }
+}
+
+#[test]
+fn test_kpvk_wdl_and_dtz_are_consistent() {
+    let tb = open();
+    for (i, board) in positions(Piece::Pawn, 7).into_iter().enumerate() {
+        let wdl = DTM.probe_dtm(&board).unwrap().wdl();
+        assert_eq!(tb.probe_wdl(&board), Some(wdl), "{}", board);
+        if i % 20 != 0 {
+            continue;
+        }
+        // DTZ has to follow from the children: the fastest zeroing or
+        // winning move for the winner, the slowest move for the loser.
+        let dtz = tb.probe_dtz(&board).unwrap();
+        let children = MoveGen::new_legal(&board).map(|mv| {
+            let zeroing = board.piece_on(mv.get_source()) == Some(Piece::Pawn) || board.piece_on(mv.get_dest()).is_some();
+            let next = board.make_move_new(mv);
+            let lost = DTM.probe_dtm(&next).unwrap().wdl() == Wdl::Loss;
+            let mate = lost && MoveGen::new_legal(&next).len() == 0;
+            (zeroing || mate, lost, tb.probe_dtz(&next).unwrap())
+        });
+        let children: Vec<_> = children.collect();
+        let expected = match wdl {
+            Wdl::Win => children.iter().filter(|c| c.1).map(|&(zeroing, _, d)| if zeroing { 1 } else { 1 - d }).min().unwrap(),
+            Wdl::Loss if children.is_empty() => -1,
+            Wdl::Loss => -children.iter().map(|&(zeroing, _, d)| if zeroing { 1 } else { 1 + d }).max().unwrap(),
+            _ => 0,
+        };
+        assert_eq!(dtz, expected, "{}", board);
+    }
+}
+
+#[test]
+fn test_dtz_root_moves_keep_the_fastest_win() {
+    let tb = open();
+    for piece in [Piece::Rook, Piece::Pawn] {
+        for board in positions(piece, 97) {
+            if DTM.probe_dtm(&board).unwrap().wdl() != Wdl::Win {
+                continue;
+            }
+            let ranked = tb.rank_root_moves(&board).unwrap();
+            let best = best_root_moves(&ranked);
+            for &(mv, dtz) in &ranked {
+                let child = DTM.probe_dtm(&board.make_move_new(mv)).unwrap().wdl();
+                assert_eq!(child == Wdl::Loss, dtz > 0, "{} {}", board, mv);
+            }
+            let fastest = ranked.iter().filter(|&&(_, dtz)| dtz > 0).map(|&(_, dtz)| dtz).min().unwrap();
+            for mv in &best {
+                let next = board.make_move_new(*mv);
+                assert_eq!(DTM.probe_dtm(&next).unwrap().wdl(), Wdl::Loss, "{} {}", board, mv);
+                assert!(ranked.contains(&(*mv, fastest)), "{} {}", board, mv);
+            }
+            // Without pawns DTZ is DTM, so the kept moves are the mating line.
+            if piece == Piece::Rook
+                && let Some(Dtm::Win(plies)) = DTM.probe_dtm(&board)
+            {
+                for mv in &best {
+                    let child = DTM.probe_dtm(&board.make_move_new(*mv)).unwrap();
+                    assert_eq!(child, Dtm::Loss(plies - 1), "{} {}", board, mv);
+                }
+            }
+        }
+    }
+
+    // Only Ka2 wins: the pawn moves and Kb1 all let the black king draw.
+    let board = Board::from_str("8/8/3k4/8/8/8/1P6/K7 w - - 0 1").unwrap();
+    let ranked = tb.rank_root_moves(&board).unwrap();
+    for &(mv, dtz) in &ranked {
+        let child = DTM.probe_dtm(&board.make_move_new(mv)).unwrap().wdl();
+        assert_eq!(child == Wdl::Loss, dtz > 0, "{} {}", mv, dtz);
+    }
+    let best: Vec<String> = best_root_moves(&ranked).iter().map(|mv| mv.to_string()).collect();
+    assert_eq!(best, ["a1a2"]);
+}
+
+#[test]
+fn test_probe_draw_tables_colour_flip_and_bad_files() {
+    let tb = open();
+    let wdl = |fen: &str| tb.probe_wdl(&Board::from_str(fen).unwrap());
+    assert_eq!(wdl("8/8/8/8/8/2k5/8/1B2K3 w - - 0 1"), Some(Wdl::Draw));
+    assert_eq!(wdl("8/8/8/8/8/2k5/8/4K1n1 b - - 0 1"), Some(Wdl::Draw));
+    // A hanging rook is a draw whoever holds it.
+    assert_eq!(wdl("8/8/8/8/8/8/1k6/R3K3 b - - 0 1"), Some(Wdl::Draw));
+    assert_eq!(wdl("r3k3/8/2K5/8/8/8/8/8 w - - 0 1"), Some(Wdl::Loss));
+    assert_eq!(wdl("k7/8/8/8/8/8/8/2QQK3 w - - 0 1"), None);
+
+    let dir = std::env::temp_dir().join(format!("axelrot_syzygy_{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(dir.join("KQvK.rtbw"), [0u8; 16]).unwrap();
+    assert!(Tablebases::open(dir.to_str().unwrap()).is_err());
+    assert!(Tablebases::open(dir.join("missing").to_str().unwrap()).is_err());
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_uci_reports_tbhits() {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_axelrot"))
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .unwrap();
+    let commands = format!(
+        "setoption name SyzygyPath value {}\nposition fen 8/8/8/8/8/2k5/8/R3K3 w - - 0 1\ngo depth 3\nquit\n",
+        FIXTURES
+    );
+    child.stdin.as_mut().unwrap().write_all(commands.as_bytes()).unwrap();
+    let output = child.wait_with_output().unwrap();
+    let stdout = String::from_utf8_lossy(&output.stdout);
+
+    assert!(stdout.contains("info string found 5 tablebases"), "{}", stdout);
+    let info = stdout.lines().rfind(|l| l.starts_with("info depth 3")).unwrap();
+    let tbhits: u64 = info.split_whitespace().skip_while(|&t| t != "tbhits").nth(1).unwrap().parse().unwrap();
+    assert!(tbhits > 0, "{}", info);
+}
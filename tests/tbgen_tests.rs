@@ -0,0 +1,105 @@
+use axelrot::bitbase::{self, BitbaseResult};
+use axelrot::dtm::{self, DtmTable, DtmTables, Material};
+use axelrot::tablebase::{Dtm, Wdl};
+use axelrot::{evaluation, search, SearchInfo, MATE};
+use chess::{get_king_moves, BitBoard, Board, BoardBuilder, Color, Piece, ALL_SQUARES, EMPTY};
+use std::str::FromStr;
+
+fn threads() -> usize {
+    std::thread::available_parallelism().map(|n| n.get()).unwrap_or(1)
+}
+
+fn dtm(tables: &DtmTables, fen: &str) -> Option<Dtm> {
+    tables.probe_dtm(&Board::from_str(fen).unwrap())
+}
+
+#[test]
+fn test_longest_mates_match_known_values() {
+    let mut tables = DtmTables::new();
+    let built = tables.generate(&Material::parse("KQvK").unwrap(), threads()).unwrap();
+    assert_eq!(built, vec![Material::parse("KQvK").unwrap()]);
+    tables.generate(&Material::parse("kvkr").unwrap(), threads()).unwrap();
+
+    // Mate in 10 and mate in 16 moves from the worst positions.
+    assert_eq!(tables.get(&Material::parse("KQvK").unwrap()).unwrap().stats().longest_mate, 19);
+    assert_eq!(tables.get(&Material::parse("KRvK").unwrap()).unwrap().stats().longest_mate, 31);
+
+    assert_eq!(dtm(&tables, "k7/8/1K6/8/8/8/8/7R w - - 0 1"), Some(Dtm::Win(1)));
+    assert_eq!(dtm(&tables, "k6R/8/1K6/8/8/8/8/8 b - - 0 1"), Some(Dtm::Loss(0)));
+    assert_eq!(dtm(&tables, "K7/8/1k6/8/8/8/8/7r b - - 0 1"), Some(Dtm::Win(1)));
+    assert_eq!(dtm(&tables, "k7/2Q5/1K6/8/8/8/8/8 b - - 0 1"), Some(Dtm::Draw));
+    assert_eq!(dtm(&tables, "8/8/8/8/8/8/1k6/1R2K3 b - - 0 1"), Some(Dtm::Draw));
+    assert_eq!(dtm(&tables, "8/8/8/4k3/8/8/1K6/5BN1 w - - 0 1"), None);
+    assert!(Material::parse("KQRvKRN").is_err());
+    assert!(Material::parse("KRPvKR").is_err());
+    assert!(Material::parse("KXvK").is_err());
+}
+
+// Every KPK position checked against the built-in bitbase and the endgame
+// evaluation that relies on it.
+#[test]
+fn test_kpk_ground_truth_matches_bitbase_and_eval() {
+    let mut tables = DtmTables::new();
+    tables.generate(&Material::parse("KPvK").unwrap(), threads()).unwrap();
+    let kpk = tables.get(&Material::parse("KPvK").unwrap()).unwrap();
+    let mut checked = 0;
+    for wk in ALL_SQUARES {
+        for bk in ALL_SQUARES {
+            if wk == bk || get_king_moves(wk) & BitBoard::from_square(bk) != EMPTY {
+                continue;
+            }
+            for pawn in &ALL_SQUARES[8..56] {
+                if *pawn == wk || *pawn == bk {
+                    continue;
+                }
+                for stm in [Color::White, Color::Black] {
+                    let mut builder = BoardBuilder::new();
+                    builder
+                        .piece(wk, Piece::King, Color::White)
+                        .piece(bk, Piece::King, Color::Black)
+                        .piece(*pawn, Piece::Pawn, Color::White)
+                        .side_to_move(stm);
+                    let Ok(board) = Board::try_from(&builder) else { continue };
+                    let truth = kpk.probe(&board).unwrap();
+                    let expected = match bitbase::probe(&board).unwrap() {
+                        BitbaseResult::Draw => Wdl::Draw,
+                        BitbaseResult::Win(color) if color == stm => Wdl::Win,
+                        BitbaseResult::Win(_) => Wdl::Loss,
+                    };
+                    assert_eq!(truth.wdl(), expected, "{}", board);
+                    let eval = evaluation(&board);
+                    assert_eq!(eval.signum(), expected as i32 / 2, "{} eval {}", board, eval);
+                    checked += 1;
+                }
+            }
+        }
+    }
+    assert!(checked > 300_000);
+}
+
+#[test]
+fn test_tables_round_trip_and_give_exact_mate_scores() {
+    let dir = std::env::temp_dir().join(format!("axelrot_tbgen_{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let mut tables = DtmTables::new();
+    tables.generate(&Material::parse("KRvK").unwrap(), threads()).unwrap();
+    let table = tables.get(&Material::parse("KRvK").unwrap()).unwrap();
+    let path = dir.join(table.file_name());
+    table.save(&path).unwrap();
+    let loaded = DtmTable::load(&path).unwrap();
+    assert_eq!(loaded.material(), table.material());
+    assert_eq!(loaded.len(), table.len());
+
+    assert_eq!(dtm::set_path(dir.to_str().unwrap()), Ok(1));
+    let fen = "8/8/8/4k3/8/8/1K6/7R w - - 0 1";
+    let Some(Dtm::Win(plies)) = dtm(&tables, fen) else { panic!("KRvK should be won") };
+    let mut info = SearchInfo::new(60_000);
+    let result = search(&Board::from_str(fen).unwrap(), 2, &mut info);
+    assert_eq!(result.score, MATE - plies as i32);
+    assert!(info.tb_hits > 0);
+    assert_eq!(dtm::set_path(""), Ok(0));
+
+    std::fs::write(dir.join("KQvK.axtb"), b"AXTB\x01").unwrap();
+    assert!(DtmTables::open(dir.to_str().unwrap()).is_err());
+    std::fs::remove_dir_all(&dir).unwrap();
+}
@@ -0,0 +1,45 @@
+use axelrot::evaluate_with;
+use axelrot::params::EvalParams;
+use axelrot::tune::{parse_position_line, sigmoid, Tuner, TuningPosition};
+use chess::Board;
+use std::str::FromStr;
+
+#[test]
+fn test_parse_tuning_position_formats() {
+    let bracket = parse_position_line("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1 [0.5]").unwrap();
+    assert_eq!(bracket.result, 0.5);
+    let epd = parse_position_line("4k3/8/8/8/8/8/4P3/4K3 w - - c9 \"1-0\";").unwrap();
+    assert_eq!(epd.result, 1.0);
+    assert!(parse_position_line("not a position [1.0]").is_none());
+}
+
+#[test]
+fn test_tuner_moves_wrong_weight_towards_labels() {
+    // Labels come from the default weights, so a knight valued like a pawn is
+    // plainly wrong and the tuner has to pull it back up.
+    let truth = EvalParams::default();
+    let positions: Vec<TuningPosition> = [
+        "4k3/8/8/8/8/8/3P4/4K3 w - - 0 1",
+        "4k3/3p4/8/8/8/8/8/4K3 w - - 0 1",
+        "4k3/8/8/8/8/8/8/3NK3 w - - 0 1",
+        "3nk3/8/8/8/8/8/8/4K3 w - - 0 1",
+        "4k3/2ppp3/8/8/8/8/8/3NK3 w - - 0 1",
+        "3nk3/8/8/8/8/8/2PP4/4K3 w - - 0 1",
+    ]
+    .iter()
+    .map(|fen| {
+        let board = Board::from_str(fen).unwrap();
+        TuningPosition { board, result: sigmoid(evaluate_with(&board, &truth) as f64, 1.0) }
+    })
+    .collect();
+
+    let mut wrong = truth.clone();
+    wrong.piece_values[1] = wrong.piece_values[0];
+    let mut tuner = Tuner::new(wrong.clone(), &positions, 2);
+    let before = tuner.best_error;
+    tuner.epoch(&positions);
+
+    assert!(tuner.best_error < before, "error {} -> {}", before, tuner.best_error);
+    assert!(tuner.params.piece_values[1] > wrong.piece_values[1]);
+    assert!(tuner.params.piece_values[1] <= truth.piece_values[1]);
+}
@@ -0,0 +1,89 @@
+use axelrot::uci::{parse_setoption, OptionValue, Options};
+use axelrot::{search, NodeType, SearchInfo, TTEntry, TranspositionTable};
+use chess::Board;
+use std::io::Write;
+use std::process::{Command, Stdio};
+use std::str::FromStr;
+
+#[test]
+fn test_setoption_parsing_and_validation() {
+    assert_eq!(parse_setoption("setoption name Clear Hash"), Ok(("Clear Hash", None)));
+    assert_eq!(parse_setoption("setoption name UCI_Opponent value GM 2800 human Some Player"), Ok(("UCI_Opponent", Some("GM 2800 human Some Player"))));
+    assert_eq!(parse_setoption("setoption name BookFile value"), Ok(("BookFile", Some(""))));
+    assert!(parse_setoption("setoption Hash 32").is_err());
+
+    let mut options = Options::new();
+    assert_eq!(options.set("hash", Some("64")), Ok("Hash"));
+    assert_eq!(options.set("multipv", Some("3")), Ok("MultiPV"));
+    assert_eq!(options.set("Nullmove", Some("FALSE")), Ok("Nullmove"));
+    assert_eq!(options.set("EvalFile", Some("<empty>")), Ok("EvalFile"));
+    assert!(options.set("Hash", Some("0")).is_err());
+    assert!(options.set("Threads", Some("many")).is_err());
+    assert!(options.set("Style", Some("Risky")).is_err());
+    assert!(options.set("OwnBook", Some("yes")).is_err());
+    assert!(options.set("MultiPV", None).is_err());
+    assert!(options.set("Contempt", Some("10")).is_err());
+
+    let config = options.config();
+    assert_eq!((config.hash_mb, config.threads, config.multi_pv), (64, 1, 3));
+    assert!(!config.nullmove);
+    assert_eq!(options.get("EvalFile").unwrap().value, OptionValue::String(String::new()));
+}
+
+fn run_engine(commands: &str) -> String {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_axelrot"))
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child.stdin.as_mut().unwrap().write_all(commands.as_bytes()).unwrap();
+    String::from_utf8_lossy(&child.wait_with_output().unwrap().stdout).into_owned()
+}
+
+#[test]
+fn test_uci_listing_and_setoption_errors() {
+    let stdout = run_engine("uci\nsetoption name Hash value 100000\nsetoption name Threads value 4\nsetoption name Foo value 1\nquit\n");
+    assert!(stdout.contains("option name Hash type spin default 16 min 1 max 65536\n"), "{}", stdout);
+    assert!(stdout.contains("option name MultiPV type spin default 1 min 1 max 256\n"));
+    for removed in ["Style", "Clear Hash", "UCI_SetPositionValue"] {
+        assert!(!stdout.contains(&format!("option name {} ", removed)), "{}", stdout);
+    }
+    assert!(stdout.contains("option name EvalFile type string default <empty>\n"));
+    assert!(stdout.contains("info string error: value 100000 for option Hash is outside 1..65536"), "{}", stdout);
+    assert!(stdout.contains("info string error: unknown option Foo"), "{}", stdout);
+    assert_eq!(stdout.matches("info string error").count(), 2, "{}", stdout);
+}
+
+#[test]
+fn test_search_reads_hash_and_multipv() {
+    let mut tt = TranspositionTable::with_capacity_mb(1);
+    for hash in 0..1_000_000 {
+        tt.put(hash, 0, 1, NodeType::Exact, 0);
+    }
+    assert!(tt.table.len() < 1_000_000 && tt.table.len() * std::mem::size_of::<(u64, TTEntry)>() <= 1 << 20);
+
+    let stdout = run_engine("setoption name MultiPV value 3\nposition startpos\ngo depth 2\nquit\n");
+    let lines: Vec<_> = stdout.lines().filter(|l| l.starts_with("info depth 2 multipv")).collect();
+    assert_eq!(lines.len(), 3, "{}", stdout);
+    let first_moves: Vec<_> = lines.iter().map(|l| l.split(" pv ").nth(1).unwrap().split(' ').next().unwrap()).collect();
+    assert!(first_moves[0] != first_moves[1] && first_moves[1] != first_moves[2] && first_moves[0] != first_moves[2]);
+
+    // The same lines come back in the result, best first.
+    let mut info = SearchInfo::new(u64::MAX);
+    info.config.multi_pv = 3;
+    let result = search(&Board::default(), 2, &mut info);
+    assert_eq!(result.lines.len(), 3);
+    assert_eq!(result.lines[0], (result.score, result.pv.clone()));
+    assert!(result.lines.windows(2).all(|w| w[0].0 >= w[1].0));
+}
+
+#[test]
+fn test_nullmove_option_prunes_the_search() {
+    let board = Board::from_str("6k1/5pp1/8/3r4/8/2R5/5PP1/6K1 w - - 0 1").unwrap();
+    let nodes = |nullmove: bool| {
+        let mut info = SearchInfo::new(u64::MAX);
+        info.config.nullmove = nullmove;
+        search(&board, 4, &mut info).nodes
+    };
+    assert!(nodes(true) < nodes(false));
+}
@@ -0,0 +1,100 @@
+use axelrot::xboard::format_score;
+use axelrot::MATE;
+use chess::{Board, ChessMove, MoveGen};
+use std::io::{BufRead, BufReader, Write};
+use std::process::{Child, ChildStdin, ChildStdout, Command, Stdio};
+use std::str::FromStr;
+
+struct Engine {
+    child: Child,
+    stdin: ChildStdin,
+    stdout: BufReader<ChildStdout>,
+}
+
+impl Engine {
+    fn start() -> Self {
+        let mut child = Command::new(env!("CARGO_BIN_EXE_axelrot"))
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .spawn()
+            .unwrap();
+        let stdin = child.stdin.take().unwrap();
+        let stdout = BufReader::new(child.stdout.take().unwrap());
+        Engine { child, stdin, stdout }
+    }
+
+    fn send(&mut self, commands: &str) {
+        self.stdin.write_all(commands.as_bytes()).unwrap();
+        self.stdin.flush().unwrap();
+    }
+
+    // Reads output up to and including the first line starting with `prefix`.
+    fn expect(&mut self, prefix: &str) -> Vec<String> {
+        let mut lines = Vec::new();
+        loop {
+            let mut line = String::new();
+            assert!(self.stdout.read_line(&mut line).unwrap() > 0, "no {} in {:?}", prefix, lines);
+            let line = line.trim_end().to_string();
+            let done = line.starts_with(prefix);
+            lines.push(line);
+            if done {
+                return lines;
+            }
+        }
+    }
+
+    fn quit(mut self) {
+        self.send("quit\n");
+        self.child.wait().unwrap();
+    }
+}
+
+#[test]
+fn test_xboard_handshake_and_moves() {
+    let mut engine = Engine::start();
+    engine.send("xboard\nprotover 2\n");
+    let features = engine.expect("feature").pop().unwrap();
+    assert!(features.contains("usermove=1") && features.contains("setboard=1") && features.ends_with("done=1"));
+
+    engine.send("new\nsd 2\nusermove e2e5\nping 1\n");
+    assert_eq!(engine.expect("pong"), ["Illegal move: e2e5", "pong 1"]);
+
+    // The engine plays Black after "new" and answers the user's move.
+    engine.send("usermove e2e4\n");
+    let reply = engine.expect("move ").pop().unwrap();
+    let after = Board::default().make_move_new(ChessMove::from_str("e2e4").unwrap());
+    let mv = ChessMove::from_str(reply.strip_prefix("move ").unwrap()).unwrap();
+    assert!(MoveGen::new_legal(&after).any(|m| m == mv), "{}", reply);
+
+    engine.send("remove\nremove\nping 2\n");
+    assert_eq!(engine.expect("pong"), ["Error (no moves to undo): remove", "pong 2"]);
+    engine.send("bogus\nping 3\n");
+    assert_eq!(engine.expect("pong"), ["Error (unknown command): bogus", "pong 3"]);
+    engine.quit();
+}
+
+#[test]
+fn test_xboard_setboard_go_post_and_force() {
+    let mut engine = Engine::start();
+    engine.send("xboard\nprotover 2\nnew\npost\nsd 2\nsetboard 6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1\ngo\n");
+    let lines = engine.expect("move ");
+    // Thinking lines: ply, score (100000 + N for mate in N), time, nodes, PV.
+    assert!(lines.iter().any(|l| l.starts_with("2 100001 ") && l.ends_with(" a1a8")), "{:?}", lines);
+    assert_eq!(lines.last().unwrap(), "move a1a8");
+    assert_eq!(engine.expect("1-0"), ["1-0 {White mates}"]);
+
+    // In force mode moves are only played on the board, and undo takes one back.
+    engine.send("new\nforce\nusermove e2e4\nusermove e7e5\nundo\nping 1\n");
+    assert_eq!(engine.expect("pong"), ["pong 1"]);
+    engine.send("setboard 8/8/8/8/8/8/8/8 w - - 0 1\nping 2\n");
+    let lines = engine.expect("pong");
+    assert!(lines[0].starts_with("tellusererror Illegal position"), "{:?}", lines);
+    engine.quit();
+}
+
+#[test]
+fn test_thinking_output_mate_scores() {
+    assert_eq!(format_score(MATE - 1), "100001");
+    assert_eq!(format_score(-MATE + 4), "-100002");
+    assert_eq!(format_score(-35), "-35");
+}